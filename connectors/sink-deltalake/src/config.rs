@@ -2,15 +2,28 @@
 //!
 //! This module handles all configuration aspects including:
 //! - Cloud provider selection (S3, Azure, or GCS)
-//! - Delta Lake table schemas (user-defined)
+//! - Delta Lake table schemas (user-defined or derived from an Avro schema)
 //! - Topic-to-table mappings with per-table configurations
 //! - Batch processing and performance tuning
 //! - Environment variable overrides
 
-use danube_connect_core::{ConnectorConfig, ConnectorError, ConnectorResult, SchemaType};
+#[cfg(feature = "avro")]
+use danube_connect_core::AvroSchema;
+use danube_connect_core::{
+    load_config_with_aliases, ConnectorConfig, ConnectorError, ConnectorResult, FailurePolicy,
+    PayloadSchema, SchemaType, StartPosition,
+};
 use serde::{Deserialize, Serialize};
-use std::env;
-use std::fs;
+
+/// Former environment variables, still accepted as aliases of the generic overrides
+const DEPRECATED_ENV_VARS: &[(&str, &str)] = &[
+    ("DANUBE_SERVICE_URL", "DANUBE_CONNECT__DANUBE_SERVICE_URL"),
+    ("CONNECTOR_NAME", "DANUBE_CONNECT__CONNECTOR_NAME"),
+    ("AWS_REGION", "DANUBE_CONNECT__DELTALAKE__S3_REGION"),
+    ("S3_ENDPOINT", "DANUBE_CONNECT__DELTALAKE__S3_ENDPOINT"),
+    ("AZURE_STORAGE_ACCOUNT", "DANUBE_CONNECT__DELTALAKE__AZURE_STORAGE_ACCOUNT"),
+    ("GCP_PROJECT_ID", "DANUBE_CONNECT__DELTALAKE__GCP_PROJECT_ID"),
+];
 
 /// Cloud storage backend for Delta Lake
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -25,21 +38,16 @@ pub enum StorageBackend {
 }
 
 /// Write mode for Delta Lake operations
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum WriteMode {
     /// Append new data to existing table (default)
+    #[default]
     Append,
     /// Overwrite existing table data
     Overwrite,
 }
 
-impl Default for WriteMode {
-    fn default() -> Self {
-        WriteMode::Append
-    }
-}
-
 /// Delta Lake table schema field definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaField {
@@ -125,10 +133,24 @@ pub struct TopicMapping {
     pub delta_table_path: String,
 
     /// Schema type for messages in this topic
+    /// (default: the schema type registered for the topic, `Bytes` if it has none)
     #[serde(default)]
-    pub schema_type: SchemaType,
+    pub schema_type: Option<SchemaType>,
+
+    /// Avro schema (`.avsc`) or Protobuf descriptor set of `Avro` and `Protobuf` payloads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_file: Option<String>,
+
+    /// Fully qualified Protobuf message type of `Protobuf` payloads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_type: Option<String>,
 
-    /// Table schema definition (user-defined)
+    /// Schema definition loaded from `schema_file` by `load_schema()`
+    #[serde(skip)]
+    pub loaded_schema: Option<PayloadSchema>,
+
+    /// Table schema definition (user-defined, derived from the Avro schema when empty)
+    #[serde(default)]
     pub schema: Vec<SchemaField>,
 
     /// Write mode (append or overwrite)
@@ -146,9 +168,73 @@ pub struct TopicMapping {
     /// Flush interval for this specific topic (overrides global)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub flush_interval_ms: Option<u64>,
+
+    /// Dead-letter topic for records that cannot be converted or written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead_letter_topic: Option<String>,
+
+    /// What happens to records that still fail after the retries
+    /// (default: `retry.failure_policy`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_policy: Option<FailurePolicy>,
+
+    /// Position to start consuming from: `"earliest"`, `"latest"`, `{ offset = n }` or
+    /// `{ publish_time = ms }` (default: where the broker places the subscription)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_position: Option<StartPosition>,
+
+    /// JSON Schema file the payloads must match; records that don't are dead-lettered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_schema: Option<String>,
 }
 
 impl TopicMapping {
+    /// Load the schema definition of `Avro` and `Protobuf` payloads from `schema_file`
+    ///
+    /// Without user-defined `schema` fields, the table schema is derived from the fields of
+    /// the Avro record.
+    pub fn load_schema(&mut self) -> ConnectorResult<()> {
+        let Some(schema_type) = self.schema_type else {
+            if self.schema_file.is_some() {
+                return Err(ConnectorError::config(format!(
+                    "Topic '{}': schema_file requires schema_type Avro or Protobuf",
+                    self.topic
+                )));
+            }
+            return Ok(());
+        };
+        let schema = PayloadSchema::load(
+            schema_type,
+            self.schema_file.as_deref(),
+            self.message_type.as_deref(),
+        )
+        .map_err(|e| ConnectorError::config(format!("Topic '{}': {}", self.topic, e)))?;
+
+        #[cfg(feature = "avro")]
+        if self.schema.is_empty() {
+            if let Some(avro) = schema.avro_schema() {
+                self.schema = schema_fields_from_avro(avro).map_err(|e| {
+                    ConnectorError::config(format!("Topic '{}': {}", self.topic, e))
+                })?;
+            }
+        }
+        self.loaded_schema = Some(schema);
+        Ok(())
+    }
+
+    /// Schema the payloads of this topic are deserialized with
+    ///
+    /// Without a configured `schema_type`, the schema type `registered` for the topic is
+    /// used, `Bytes` if it has none.
+    pub fn payload_schema(&self, registered: Option<SchemaType>) -> PayloadSchema {
+        self.loaded_schema.clone().unwrap_or_else(|| {
+            self.schema_type
+                .or(registered)
+                .unwrap_or_default()
+                .into()
+        })
+    }
+
     /// Get effective batch size (topic-specific or global)
     pub fn effective_batch_size(&self, global: usize) -> usize {
         self.batch_size.unwrap_or(global)
@@ -174,63 +260,18 @@ fn default_true() -> bool {
 }
 
 impl DeltaLakeSinkConfig {
-    /// Load configuration from TOML file
-    pub fn from_file(path: &str) -> ConnectorResult<Self> {
-        let contents = fs::read_to_string(path).map_err(|e| {
-            ConnectorError::config(format!("Failed to read config file '{}': {}", path, e))
-        })?;
-
-        let config: Self = toml::from_str(&contents).map_err(|e| {
-            ConnectorError::config(format!("Failed to parse config file '{}': {}", path, e))
-        })?;
-
-        config.validate()?;
-        Ok(config)
-    }
-
-    /// Load configuration from environment variable CONNECTOR_CONFIG_PATH
+    /// Load configuration from the TOML file at `CONNECTOR_CONFIG_PATH`
+    ///
+    /// Supports `${VAR}` interpolation, `DANUBE_CONNECT__SECTION__KEY` overrides and
+    /// `*_secret_file` secrets (see `danube_connect_core::load_config`). The former
+    /// variables such as `S3_ENDPOINT` are deprecated aliases of the overrides.
     pub fn load() -> ConnectorResult<Self> {
-        let config_path = env::var("CONNECTOR_CONFIG_PATH").map_err(|_| {
-            ConnectorError::config(
-                "CONNECTOR_CONFIG_PATH environment variable not set. \
-                 Please set it to the path of your connector.toml file.",
-            )
-        })?;
-
-        let mut config = Self::from_file(&config_path)?;
-        config.apply_env_overrides();
-        Ok(config)
-    }
-
-    /// Apply environment variable overrides
-    fn apply_env_overrides(&mut self) {
-        // Core overrides (mandatory)
-        if let Ok(url) = env::var("DANUBE_SERVICE_URL") {
-            tracing::info!("Overriding danube_service_url from environment");
-            self.core.danube_service_url = url;
-        }
-
-        if let Ok(name) = env::var("CONNECTOR_NAME") {
-            tracing::info!("Overriding connector_name from environment");
-            self.core.connector_name = name;
-        }
-
-        // Cloud-specific overrides (credentials should come from env)
-        if let Ok(region) = env::var("AWS_REGION") {
-            self.deltalake.s3_region = Some(region);
-        }
-
-        if let Ok(endpoint) = env::var("S3_ENDPOINT") {
-            self.deltalake.s3_endpoint = Some(endpoint);
-        }
-
-        if let Ok(account) = env::var("AZURE_STORAGE_ACCOUNT") {
-            self.deltalake.azure_storage_account = Some(account);
-        }
-
-        if let Ok(project) = env::var("GCP_PROJECT_ID") {
-            self.deltalake.gcp_project_id = Some(project);
+        let mut config: Self = load_config_with_aliases(DEPRECATED_ENV_VARS)?;
+        for mapping in &mut config.deltalake.topic_mappings {
+            mapping.load_schema()?;
         }
+        config.validate()?;
+        Ok(config)
     }
 
     /// Validate configuration
@@ -285,7 +326,7 @@ impl DeltaLakeSinkConfig {
             }
             if mapping.schema.is_empty() {
                 return Err(ConnectorError::config(format!(
-                    "Schema cannot be empty for topic '{}'. Please define at least one field.",
+                    "Schema cannot be empty for topic '{}'. Please define at least one field or use an Avro schema_file.",
                     mapping.topic
                 )));
             }
@@ -300,6 +341,65 @@ impl DeltaLakeSinkConfig {
     }
 }
 
+/// Table schema fields for the fields of the top-level Avro record
+///
+/// Nullable unions with a single other branch take the type of that branch. Strings,
+/// enums, bytes, fixed, decimals, other unions, named type references and nested values
+/// are stored as `Utf8`.
+#[cfg(feature = "avro")]
+fn schema_fields_from_avro(avro: &AvroSchema) -> ConnectorResult<Vec<SchemaField>> {
+    let AvroSchema::Record(record) = avro else {
+        return Err(ConnectorError::config(
+            "Avro schema must be a record to derive the table schema",
+        ));
+    };
+
+    Ok(record
+        .fields
+        .iter()
+        .map(|field| {
+            let (data_type, nullable) = match &field.schema {
+                AvroSchema::Union(union) => {
+                    let mut values = union
+                        .variants()
+                        .iter()
+                        .filter(|variant| !matches!(variant, AvroSchema::Null));
+                    match (values.next(), values.next()) {
+                        (Some(value), None) => (avro_arrow_type(value), union.is_nullable()),
+                        _ => ("Utf8", true),
+                    }
+                }
+                avro_type => (avro_arrow_type(avro_type), false),
+            };
+            SchemaField {
+                name: field.name.clone(),
+                data_type: data_type.to_string(),
+                nullable,
+            }
+        })
+        .collect())
+}
+
+/// Arrow data type of the decoded values of an Avro type
+#[cfg(feature = "avro")]
+fn avro_arrow_type(avro_type: &AvroSchema) -> &'static str {
+    match avro_type {
+        AvroSchema::Boolean => "Boolean",
+        AvroSchema::Int | AvroSchema::TimeMillis => "Int32",
+        AvroSchema::Long | AvroSchema::TimeMicros => "Int64",
+        AvroSchema::Float => "Float32",
+        AvroSchema::Double => "Float64",
+        AvroSchema::Date => "Date32",
+        AvroSchema::TimestampMillis
+        | AvroSchema::TimestampMicros
+        | AvroSchema::TimestampNanos
+        | AvroSchema::LocalTimestampMillis
+        | AvroSchema::LocalTimestampMicros
+        | AvroSchema::LocalTimestampNanos => "Timestamp",
+        _ => "Utf8",
+    }
+}
+
 /// Validate Arrow data type string
 fn validate_arrow_type(data_type: &str) -> ConnectorResult<()> {
     let valid_types = [
@@ -345,4 +445,87 @@ mod tests {
         assert!(validate_arrow_type("Timestamp").is_ok());
         assert!(validate_arrow_type("InvalidType").is_err());
     }
+
+    #[test]
+    #[cfg(feature = "avro")]
+    fn test_schema_fields_from_avro() {
+        let avro = AvroSchema::parse_str(
+            r#"{
+                "type": "record",
+                "name": "Payment",
+                "fields": [
+                    {"name": "payment_id", "type": "string"},
+                    {"name": "amount", "type": "double"},
+                    {"name": "note", "type": ["null", "string"]},
+                    {"name": "paid_at", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+                    {"name": "tags", "type": {"type": "array", "items": "string"}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let fields: Vec<_> = schema_fields_from_avro(&avro)
+            .unwrap()
+            .into_iter()
+            .map(|field| (field.name, field.data_type, field.nullable))
+            .collect();
+        let expected = [
+            ("payment_id", "Utf8", false),
+            ("amount", "Float64", false),
+            ("note", "Utf8", true),
+            ("paid_at", "Timestamp", false),
+            ("tags", "Utf8", false),
+        ];
+        assert_eq!(fields.len(), expected.len());
+        for (field, (name, data_type, nullable)) in fields.iter().zip(expected) {
+            assert_eq!((field.0.as_str(), field.1.as_str(), field.2), (name, data_type, nullable));
+        }
+
+        let not_a_record = AvroSchema::parse_str(r#""string""#).unwrap();
+        assert!(schema_fields_from_avro(&not_a_record).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "avro")]
+    fn test_load_mapping_with_schema_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let avsc = dir.path().join("payment.avsc");
+        std::fs::write(
+            &avsc,
+            r#"{"type": "record", "name": "Payment", "fields": [{"name": "id", "type": "string"}]}"#,
+        )
+        .unwrap();
+        let config_path = dir.path().join("connector.toml");
+        std::fs::write(
+            &config_path,
+            format!(
+                r#"
+                connector_name = "deltalake-sink"
+                danube_service_url = "http://localhost:6650"
+
+                [deltalake]
+                storage_backend = "s3"
+                s3_region = "us-east-1"
+
+                [[deltalake.topic_mappings]]
+                topic = "/events/payments"
+                subscription = "deltalake-payments"
+                delta_table_path = "s3://bucket/payments"
+                schema_type = "Avro"
+                schema_file = {:?}
+                "#,
+                avsc.display().to_string()
+            ),
+        )
+        .unwrap();
+
+        let mut config: DeltaLakeSinkConfig =
+            danube_connect_core::load_config_file(&config_path).unwrap();
+        let mapping = &mut config.deltalake.topic_mappings[0];
+        assert_eq!(mapping.schema_file.as_deref(), Some(avsc.to_str().unwrap()));
+        mapping.load_schema().unwrap();
+        assert_eq!(mapping.schema.len(), 1);
+        assert_eq!(mapping.schema[0].name, "id");
+        assert!(mapping.loaded_schema.is_some());
+    }
 }
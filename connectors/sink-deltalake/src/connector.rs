@@ -7,8 +7,9 @@ use crate::config::{DeltaLakeSinkConfig, StorageBackend, TopicMapping};
 use crate::record::to_record_batch;
 use async_trait::async_trait;
 use danube_connect_core::{
-    ConnectorConfig, ConnectorError, ConnectorResult, ConsumerConfig, SinkConnector, SinkRecord,
-    SubscriptionType,
+    utils::{find_topic_match, is_topic_pattern},
+    AckMode, ConnectorConfig, ConnectorError, ConnectorResult, ConsumerConfig, Offset,
+    SinkConnector, SinkRecord, SubscriptionType,
 };
 use deltalake::operations::create::CreateBuilder;
 use deltalake::writer::{DeltaWriter, RecordBatchWriter};
@@ -33,6 +34,9 @@ pub struct DeltaLakeSinkConnector {
 
     /// Last flush time per topic (for interval-based flushing)
     last_flush_time: HashMap<String, Instant>,
+
+    /// Highest offset per topic committed to Delta Lake and not yet reported to the runtime
+    flushed_offsets: HashMap<String, u64>,
 }
 
 impl DeltaLakeSinkConnector {
@@ -43,9 +47,16 @@ impl DeltaLakeSinkConnector {
             tables: HashMap::new(),
             buffers: HashMap::new(),
             last_flush_time: HashMap::new(),
+            flushed_offsets: HashMap::new(),
         }
     }
 
+    /// Find the mapping of a topic, either by name or through a topic pattern
+    fn topic_mapping(&self, topic: &str) -> ConnectorResult<&TopicMapping> {
+        find_topic_match(&self.config.deltalake.topic_mappings, topic, |m| &m.topic)
+            .ok_or_else(|| ConnectorError::fatal(format!("No mapping found for topic: {}", topic)))
+    }
+
     /// Get or create a Delta table
     async fn get_or_create_table(
         &mut self,
@@ -183,7 +194,7 @@ impl DeltaLakeSinkConnector {
     async fn write_batch(
         &mut self,
         mapping: &TopicMapping,
-        records: Vec<SinkRecord>,
+        records: &[SinkRecord],
     ) -> ConnectorResult<()> {
         if records.is_empty() {
             return Ok(());
@@ -196,7 +207,7 @@ impl DeltaLakeSinkConnector {
         );
 
         // Convert records to Arrow RecordBatch
-        let record_batch = to_record_batch(&records, mapping)?;
+        let record_batch = to_record_batch(records, mapping)?;
 
         // Get or create the table
         let table = self.get_or_create_table(mapping).await?;
@@ -274,15 +285,9 @@ impl DeltaLakeSinkConnector {
             }
             
             // Get mapping and check flush interval
-            let mapping = match self
-                .config
-                .deltalake
-                .topic_mappings
-                .iter()
-                .find(|m| m.topic == topic)
-            {
-                Some(m) => m,
-                None => continue,
+            let mapping = match self.topic_mapping(&topic) {
+                Ok(m) => m,
+                Err(_) => continue,
             };
             
             let flush_interval_ms = mapping.effective_flush_interval_ms(self.config.deltalake.flush_interval_ms);
@@ -317,19 +322,30 @@ impl DeltaLakeSinkConnector {
             }
 
             // Find the mapping for this topic (clone to avoid borrow issues)
-            let mapping = self
-                .config
-                .deltalake
-                .topic_mappings
+            let mapping = match self.topic_mapping(topic) {
+                Ok(mapping) => mapping.clone(),
+                Err(e) => {
+                    self.buffers.insert(topic.to_string(), records);
+                    return Err(e);
+                }
+            };
+
+            if let Err(e) = self.write_batch(&mapping, &records).await {
+                // Keep the records buffered so the next flush retries them
+                self.buffers.insert(topic.to_string(), records);
+                return Err(e);
+            }
+
+            // A message is written once its last record is
+            if let Some(offset) = records
                 .iter()
-                .find(|m| m.topic == topic)
-                .cloned()
-                .ok_or_else(|| {
-                    ConnectorError::fatal(format!("No mapping found for topic: {}", topic))
-                })?;
+                .filter(|r| r.is_last_split())
+                .map(|r| r.offset())
+                .max()
+            {
+                self.flushed_offsets.insert(topic.to_string(), offset);
+            }
 
-            self.write_batch(&mapping, records).await?;
-            
             // Update last flush time
             self.last_flush_time.insert(topic.to_string(), Instant::now());
         }
@@ -371,8 +387,11 @@ impl SinkConnector for DeltaLakeSinkConnector {
             );
         }
 
-        // Initialize buffers
+        // Initialize buffers; topics matched by a pattern get theirs on their first record
         for mapping in &self.config.deltalake.topic_mappings {
+            if is_topic_pattern(&mapping.topic) {
+                continue;
+            }
             self.buffers.insert(mapping.topic.clone(), Vec::new());
         }
 
@@ -394,6 +413,10 @@ impl SinkConnector for DeltaLakeSinkConnector {
                     self.config.core.connector_name, mapping.subscription
                 ),
                 subscription_type: SubscriptionType::Shared,
+                dead_letter_topic: mapping.dead_letter_topic.clone(),
+                failure_policy: mapping.failure_policy,
+                start_position: mapping.start_position,
+                json_schema: mapping.json_schema.clone(),
             })
             .collect();
 
@@ -407,33 +430,33 @@ impl SinkConnector for DeltaLakeSinkConnector {
             record.offset()
         );
         
-        // Add to buffer
         let topic = record.topic().to_string();
-        let buffer = self.buffers.entry(topic.clone()).or_insert_with(Vec::new);
+        let mapping = self.topic_mapping(&topic)?;
+        let batch_size = mapping.effective_batch_size(self.config.deltalake.batch_size);
+        let flush_interval_ms = mapping.effective_flush_interval_ms(self.config.deltalake.flush_interval_ms);
+        let flush_interval = Duration::from_millis(flush_interval_ms);
+
+        // Add to buffer
+        let buffer = self.buffers.entry(topic.clone()).or_default();
         
         // Initialize last flush time if this is the first message for this topic
         let now = Instant::now();
         self.last_flush_time.entry(topic.clone()).or_insert(now);
-        
-        buffer.push(record);
+
+        // A record retried after a failed flush is still in the buffer
+        if buffer.last().is_some_and(|last| record.position() <= last.position()) {
+            debug!(
+                "Message from topic {} offset {} is already buffered, retrying flush",
+                topic,
+                record.offset()
+            );
+        } else {
+            buffer.push(record);
+        }
         
         debug!("Buffer size for topic {}: {}", topic, buffer.len());
 
         // Check if we should flush
-        let mapping = self
-            .config
-            .deltalake
-            .topic_mappings
-            .iter()
-            .find(|m| m.topic == topic)
-            .ok_or_else(|| {
-                ConnectorError::fatal(format!("No mapping found for topic: {}", topic))
-            })?;
-
-        let batch_size = mapping.effective_batch_size(self.config.deltalake.batch_size);
-        let flush_interval_ms = mapping.effective_flush_interval_ms(self.config.deltalake.flush_interval_ms);
-        let flush_interval = Duration::from_millis(flush_interval_ms);
-        
         let last_flush = self.last_flush_time.get(&topic).unwrap();
         let time_since_flush = now.duration_since(*last_flush);
         
@@ -473,25 +496,17 @@ impl SinkConnector for DeltaLakeSinkConnector {
         let mut by_topic: HashMap<String, Vec<SinkRecord>> = HashMap::new();
         for record in records {
             let topic = record.topic().to_string();
-            by_topic.entry(topic).or_insert_with(Vec::new).push(record);
+            by_topic.entry(topic).or_default().push(record);
         }
 
         // Add to buffers and flush if batch size reached
         for (topic, topic_records) in by_topic {
-            let buffer = self.buffers.entry(topic.clone()).or_insert_with(Vec::new);
-            buffer.extend(topic_records);
+            let batch_size = self
+                .topic_mapping(&topic)?
+                .effective_batch_size(self.config.deltalake.batch_size);
 
-            let mapping = self
-                .config
-                .deltalake
-                .topic_mappings
-                .iter()
-                .find(|m| m.topic == topic)
-                .ok_or_else(|| {
-                    ConnectorError::fatal(format!("No mapping found for topic: {}", topic))
-                })?;
-
-            let batch_size = mapping.effective_batch_size(self.config.deltalake.batch_size);
+            let buffer = self.buffers.entry(topic.clone()).or_default();
+            buffer.extend(topic_records);
 
             // Flush if batch size reached
             if buffer.len() >= batch_size {
@@ -507,6 +522,19 @@ impl SinkConnector for DeltaLakeSinkConnector {
         Ok(())
     }
 
+    fn ack_mode(&self) -> AckMode {
+        // Records are buffered per topic until they are committed to the Delta table
+        AckMode::Deferred
+    }
+
+    async fn flushed_offsets(&mut self) -> ConnectorResult<Vec<Offset>> {
+        Ok(self
+            .flushed_offsets
+            .drain()
+            .map(|(topic, offset)| Offset::new(topic, offset))
+            .collect())
+    }
+
     async fn shutdown(&mut self) -> ConnectorResult<()> {
         info!("Shutting down Delta Lake Sink Connector");
 
@@ -556,3 +584,78 @@ fn arrow_to_delta_type(arrow_type: &arrow::datatypes::DataType) -> deltalake::ke
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use danube_core::message::{MessageID, StreamMessage};
+
+    fn connector() -> DeltaLakeSinkConnector {
+        let config: DeltaLakeSinkConfig = toml::from_str(
+            r#"
+            connector_name = "deltalake-sink"
+            danube_service_url = "http://localhost:6650"
+
+            [deltalake]
+            storage_backend = "s3"
+            s3_region = "us-east-1"
+            batch_size = 100
+            flush_interval_ms = 60000
+
+            [[deltalake.topic_mappings]]
+            topic = "/events/*"
+            subscription = "deltalake-events"
+            delta_table_path = "s3://bucket/events"
+
+            [[deltalake.topic_mappings]]
+            topic = "/events/orders"
+            subscription = "deltalake-orders"
+            delta_table_path = "s3://bucket/orders"
+            batch_size = 1
+            "#,
+        )
+        .unwrap();
+        DeltaLakeSinkConnector::with_config(config)
+    }
+
+    fn record(topic: &str, offset: u64) -> SinkRecord {
+        let message = StreamMessage {
+            request_id: 1,
+            msg_id: MessageID {
+                producer_id: 1,
+                topic_name: topic.to_string(),
+                broker_addr: "localhost:6650".to_string(),
+                topic_offset: offset,
+            },
+            payload: br#"{"id": 1}"#.to_vec(),
+            publish_time: 0,
+            producer_name: "producer".to_string(),
+            subscription_name: Some("deltalake-events".to_string()),
+            attributes: HashMap::new(),
+        };
+        SinkRecord::from_stream_message(message, None)
+    }
+
+    #[tokio::test]
+    async fn test_records_of_pattern_mapping_buffered_per_topic() {
+        let mut connector = connector();
+        connector.initialize(connector.config.core.clone()).await.unwrap();
+        assert_eq!(connector.buffers.keys().collect::<Vec<_>>(), ["/events/orders"]);
+
+        connector.process(record("/events/payments", 7)).await.unwrap();
+        connector.process(record("/events/refunds", 3)).await.unwrap();
+        assert_eq!(connector.buffers["/events/payments"][0].offset(), 7);
+        assert_eq!(connector.buffers["/events/refunds"][0].offset(), 3);
+        assert_eq!(
+            connector.topic_mapping("/events/payments").unwrap().delta_table_path,
+            "s3://bucket/events"
+        );
+
+        // The exact mapping wins over the pattern
+        assert_eq!(connector.topic_mapping("/events/orders").unwrap().batch_size, Some(1));
+
+        // Topics no mapping matches are rejected without being buffered
+        assert!(connector.process(record("/audit/logins", 1)).await.is_err());
+        assert!(!connector.buffers.contains_key("/audit/logins"));
+    }
+}
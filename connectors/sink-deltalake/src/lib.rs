@@ -7,7 +7,7 @@
 //!
 //! - **Multi-Cloud Support**: S3, Azure Blob Storage, Google Cloud Storage
 //! - **ACID Transactions**: Guaranteed consistency with Delta Lake transaction log
-//! - **User-Defined Schemas**: Full control over table schemas
+//! - **User-Defined Schemas**: Full control over table schemas, or derived from Avro schemas
 //! - **Batching**: Configurable batch sizes for optimal performance
 //! - **Metadata**: Optional Danube metadata as JSON column
 //! - **MinIO Compatible**: Test locally with MinIO S3-compatible storage
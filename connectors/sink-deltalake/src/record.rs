@@ -3,18 +3,18 @@
 //! This module handles conversion from Danube SinkRecords to Arrow RecordBatches,
 //! which are then written to Delta Lake as Parquet files.
 //!
-//! Supports all Danube schema types (Json, String, Int64) and includes optional
-//! Danube metadata as a JSON column.
+//! Supports all Danube schema types (Json, String, Int64, Avro, Protobuf) and includes
+//! optional Danube metadata as a JSON column.
 
 use crate::config::TopicMapping;
 use arrow::array::{
-    ArrayRef, BooleanArray, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array,
+    ArrayRef, BooleanArray, Date32Array, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array,
     Int8Array, StringArray, TimestampMicrosecondArray, UInt16Array, UInt32Array, UInt64Array,
     UInt8Array,
 };
 use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
 use arrow::record_batch::RecordBatch;
-use chrono::Utc;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
 use danube_connect_core::{ConnectorError, ConnectorResult, SinkRecord};
 use serde_json::Value;
 use std::sync::Arc;
@@ -22,7 +22,7 @@ use std::sync::Arc;
 /// Convert a batch of Danube SinkRecords into an Arrow RecordBatch
 ///
 /// This function:
-/// 1. Deserializes each record using payload_deserialized() (handles Json/String/Int64)
+/// 1. Deserializes each record using payload_deserialized() (handles all schema types)
 /// 2. Extracts fields according to user-defined schema
 /// 3. Optionally adds Danube metadata as a JSON column
 /// 4. Builds Arrow arrays for each column
@@ -41,7 +41,7 @@ pub fn to_record_batch(
     // Deserialize all records
     let deserialized: Vec<Value> = records
         .iter()
-        .map(|r| r.payload_deserialized(mapping.schema_type))
+        .map(|r| r.payload_deserialized(mapping.payload_schema(r.schema_type())))
         .collect::<ConnectorResult<Vec<_>>>()?;
 
     // Build arrays for each field in the schema
@@ -101,6 +101,7 @@ fn parse_arrow_type(type_str: &str) -> ConnectorResult<DataType> {
         "Float64" => DataType::Float64,
         "Boolean" => DataType::Boolean,
         "Timestamp" => DataType::Timestamp(TimeUnit::Microsecond, None),
+        "Date32" => DataType::Date32,
         "Binary" => DataType::Binary,
         _ => {
             return Err(ConnectorError::fatal(format!(
@@ -211,6 +212,13 @@ fn build_array_for_field(
                 .collect();
             Ok(Arc::new(array))
         }
+        "Date32" => {
+            let array: Date32Array = values
+                .iter()
+                .map(|v| extract_date_field(v, field_name))
+                .collect();
+            Ok(Arc::new(array))
+        }
         _ => Err(ConnectorError::fatal(format!(
             "Unsupported data type for field '{}': {}",
             field_name, data_type
@@ -218,10 +226,14 @@ fn build_array_for_field(
     }
 }
 
-/// Extract string field from JSON value
+/// Extract string field from JSON value (other non-null values as JSON text)
 fn extract_string_field(value: &Value, field_name: &str) -> Option<String> {
     match value {
-        Value::Object(map) => map.get(field_name).and_then(|v| v.as_str()).map(String::from),
+        Value::Object(map) => match map.get(field_name)? {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        },
         _ => None,
     }
 }
@@ -272,6 +284,12 @@ fn extract_timestamp_field(value: &Value, field_name: &str) -> Option<i64> {
                     if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(timestamp_str) {
                         return Some(dt.timestamp_micros());
                     }
+                    // Timestamps without offset (Avro local timestamps) are taken as UTC
+                    if let Ok(dt) =
+                        NaiveDateTime::parse_from_str(timestamp_str, "%Y-%m-%dT%H:%M:%S%.f")
+                    {
+                        return Some(dt.and_utc().timestamp_micros());
+                    }
                 }
             }
             None
@@ -280,6 +298,20 @@ fn extract_timestamp_field(value: &Value, field_name: &str) -> Option<i64> {
     }
 }
 
+/// Extract date field from JSON value (expects `YYYY-MM-DD` string or days since epoch)
+fn extract_date_field(value: &Value, field_name: &str) -> Option<i32> {
+    let field_value = match value {
+        Value::Object(map) => map.get(field_name)?,
+        _ => return None,
+    };
+    if let Some(days) = field_value.as_i64() {
+        return i32::try_from(days).ok();
+    }
+    let date = NaiveDate::parse_from_str(field_value.as_str()?, "%Y-%m-%d").ok()?;
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1)?;
+    i32::try_from((date - epoch).num_days()).ok()
+}
+
 /// Build metadata array with Danube message metadata as JSON
 fn build_metadata_array(records: &[SinkRecord]) -> ConnectorResult<ArrayRef> {
     let metadata_strings: Vec<String> = records
@@ -290,7 +322,7 @@ fn build_metadata_array(records: &[SinkRecord]) -> ConnectorResult<ArrayRef> {
                 "offset": record.offset(),
                 "timestamp": record.publish_time(),
                 "timestamp_iso": Utc::now().to_rfc3339(),
-                "partition": record.partition.as_ref().map(|s| s.as_str()).unwrap_or("none"),
+                "partition": record.partition.as_deref().unwrap_or("none"),
             });
             metadata.to_string()
         })
@@ -310,6 +342,18 @@ mod tests {
         let value = json!({"name": "Alice", "age": 30});
         assert_eq!(extract_string_field(&value, "name"), Some("Alice".to_string()));
         assert_eq!(extract_string_field(&value, "missing"), None);
+
+        let value = json!({"tags": ["a", "b"], "note": null});
+        assert_eq!(extract_string_field(&value, "tags"), Some(r#"["a","b"]"#.to_string()));
+        assert_eq!(extract_string_field(&value, "note"), None);
+    }
+
+    #[test]
+    fn test_extract_date_field() {
+        let value = json!({"day": "2024-03-01", "days": 19783, "bad": "March"});
+        assert_eq!(extract_date_field(&value, "day"), Some(19783));
+        assert_eq!(extract_date_field(&value, "days"), Some(19783));
+        assert_eq!(extract_date_field(&value, "bad"), None);
     }
 
     #[test]
@@ -325,6 +369,7 @@ mod tests {
         assert!(matches!(parse_arrow_type("Int64"), Ok(DataType::Int64)));
         assert!(matches!(parse_arrow_type("Float64"), Ok(DataType::Float64)));
         assert!(matches!(parse_arrow_type("Boolean"), Ok(DataType::Boolean)));
+        assert!(matches!(parse_arrow_type("Date32"), Ok(DataType::Date32)));
         assert!(parse_arrow_type("InvalidType").is_err());
     }
 }
@@ -1,8 +1,18 @@
 //! Configuration for the Qdrant Sink Connector
 
-use danube_connect_core::{ConnectorConfig, ConnectorResult, SubscriptionType};
+use danube_connect_core::{
+    load_config_with_aliases, parse_config_with_aliases, ConnectorConfig, ConnectorResult,
+    FailurePolicy, StartPosition, SubscriptionType,
+};
 use serde::{Deserialize, Serialize};
-use std::env;
+
+/// Former environment variables, still accepted as aliases of the generic overrides
+const DEPRECATED_ENV_VARS: &[(&str, &str)] = &[
+    ("DANUBE_SERVICE_URL", "DANUBE_CONNECT__DANUBE_SERVICE_URL"),
+    ("CONNECTOR_NAME", "DANUBE_CONNECT__CONNECTOR_NAME"),
+    ("QDRANT_URL", "DANUBE_CONNECT__QDRANT__URL"),
+    ("QDRANT_API_KEY", "DANUBE_CONNECT__QDRANT__API_KEY"),
+];
 
 /// Unified configuration for Qdrant Sink Connector
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,66 +26,18 @@ pub struct QdrantSinkConfig {
 }
 
 impl QdrantSinkConfig {
-    /// Load configuration from TOML file
-    /// 
-    /// The config file path must be specified via CONNECTOR_CONFIG_PATH environment variable.
-    /// Environment variables can override secrets (API key) and URLs.
+    /// Load configuration from the TOML file at `CONNECTOR_CONFIG_PATH`
+    ///
+    /// Supports `${VAR}` interpolation, `DANUBE_CONNECT__SECTION__KEY` overrides and
+    /// `*_secret_file` secrets (see `danube_connect_core::load_config`). The former
+    /// variables such as `QDRANT_API_KEY` are deprecated aliases of the overrides.
     pub fn load() -> ConnectorResult<Self> {
-        let config_path = env::var("CONNECTOR_CONFIG_PATH")
-            .map_err(|_| danube_connect_core::ConnectorError::config(
-                "CONNECTOR_CONFIG_PATH environment variable must be set to the path of the TOML configuration file"
-            ))?;
-        
-        Self::from_file(&config_path)
-    }
-
-    /// Load configuration from a TOML file
-    pub fn from_file(path: &str) -> ConnectorResult<Self> {
-        let content = std::fs::read_to_string(path).map_err(|e| {
-            danube_connect_core::ConnectorError::config(format!(
-                "Failed to read config file {}: {}",
-                path, e
-            ))
-        })?;
-
-        let mut config: Self = toml::from_str(&content).map_err(|e| {
-            danube_connect_core::ConnectorError::config(format!(
-                "Failed to parse config file {}: {}",
-                path, e
-            ))
-        })?;
-
-        // Apply environment variable overrides for secrets and URLs
-        config.apply_env_overrides();
-
-        Ok(config)
+        load_config_with_aliases(DEPRECATED_ENV_VARS)
     }
 
-    /// Apply environment variable overrides for secrets and connection details
-    /// 
-    /// Only overrides sensitive data that shouldn't be in config files:
-    /// - API key (secret)
-    /// - Connection URLs (for different environments)
-    /// - Connector name (for different deployments)
-    fn apply_env_overrides(&mut self) {
-        // Override core Danube settings (mandatory fields from danube-connect-core)
-        if let Ok(danube_url) = env::var("DANUBE_SERVICE_URL") {
-            self.core.danube_service_url = danube_url;
-        }
-        
-        if let Ok(connector_name) = env::var("CONNECTOR_NAME") {
-            self.core.connector_name = connector_name;
-        }
-
-        // Override Qdrant URL (for different environments: dev/staging/prod)
-        if let Ok(url) = env::var("QDRANT_URL") {
-            self.qdrant.url = url;
-        }
-
-        // Override API key (secret should not be in config files)
-        if let Ok(api_key) = env::var("QDRANT_API_KEY") {
-            self.qdrant.api_key = Some(api_key);
-        }
+    /// Parse configuration from TOML content, e.g. a reloaded config file
+    pub fn from_toml(content: &str) -> ConnectorResult<Self> {
+        parse_config_with_aliases(content, DEPRECATED_ENV_VARS)
     }
 
     /// Validate all configuration
@@ -152,6 +114,24 @@ pub struct TopicMapping {
     /// Topic-specific batch timeout (overrides global)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub batch_timeout_ms: Option<u64>,
+
+    /// Dead-letter topic for records that cannot be converted or upserted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead_letter_topic: Option<String>,
+
+    /// What happens to records that still fail after the retries
+    /// (default: `retry.failure_policy`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_policy: Option<FailurePolicy>,
+
+    /// Position to start consuming from: `"earliest"`, `"latest"`, `{ offset = n }` or
+    /// `{ publish_time = ms }` (default: where the broker places the subscription)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_position: Option<StartPosition>,
+
+    /// JSON Schema file the payloads must match; records that don't are dead-lettered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_schema: Option<String>,
 }
 
 impl TopicMapping {
@@ -208,7 +188,7 @@ pub enum Distance {
 }
 
 impl Distance {
-    pub fn to_qdrant(&self) -> qdrant_client::qdrant::Distance {
+    pub fn to_qdrant(self) -> qdrant_client::qdrant::Distance {
         match self {
             Distance::Cosine => qdrant_client::qdrant::Distance::Cosine,
             Distance::Euclid => qdrant_client::qdrant::Distance::Euclid,
@@ -303,6 +283,10 @@ mod tests {
                 include_danube_metadata: true,
                 batch_size: None,
                 batch_timeout_ms: None,
+                dead_letter_topic: None,
+                failure_policy: None,
+                start_position: None,
+                json_schema: None,
             }],
             batch_size: 100,
             batch_timeout_ms: 1000,
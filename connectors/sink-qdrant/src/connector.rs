@@ -1,10 +1,12 @@
 //! Qdrant sink connector implementation
 
-use crate::config::{QdrantConfig, TopicMapping};
+use crate::config::{QdrantConfig, QdrantSinkConfig, TopicMapping};
 use crate::transform::transform_to_point;
 use async_trait::async_trait;
+use danube_connect_core::utils::{find_topic_match, is_topic_pattern};
 use danube_connect_core::{
-    ConnectorConfig, ConnectorError, ConnectorResult, ConsumerConfig, SinkConnector, SinkRecord,
+    AckMode, ConnectorConfig, ConnectorError, ConnectorResult, ConsumerConfig, Offset,
+    SinkConnector, SinkRecord,
 };
 use qdrant_client::qdrant::PointStruct;
 use qdrant_client::qdrant::{CreateCollectionBuilder, UpsertPointsBuilder};
@@ -28,6 +30,12 @@ struct CollectionContext {
     effective_batch_size: usize,
     /// Effective batch timeout (topic-specific or global)
     effective_batch_timeout_ms: u64,
+    /// Position of the last record added to the batch buffer
+    buffered_position: Option<(u64, usize)>,
+    /// Highest Danube offset with all its records in the batch buffer
+    buffered_offset: Option<u64>,
+    /// Highest Danube offset written to Qdrant and not yet reported to the runtime
+    flushed_offset: Option<u64>,
     /// Statistics
     points_inserted: u64,
     batches_flushed: u64,
@@ -44,6 +52,9 @@ impl CollectionContext {
             last_flush: Instant::now(),
             effective_batch_size,
             effective_batch_timeout_ms,
+            buffered_position: None,
+            buffered_offset: None,
+            flushed_offset: None,
             points_inserted: 0,
             batches_flushed: 0,
         }
@@ -63,12 +74,19 @@ impl CollectionContext {
         let elapsed = self.last_flush.elapsed().as_millis() as u64;
         elapsed >= self.effective_batch_timeout_ms
     }
+
+    /// Check if the record at `position` is already in the batch buffer
+    fn is_buffered(&self, position: (u64, usize)) -> bool {
+        self.buffered_position
+            .is_some_and(|buffered| position <= buffered)
+    }
 }
 
 pub struct QdrantSinkConnector {
     config: QdrantConfig,
     client: Option<Qdrant>,
-    /// Collection contexts keyed by Danube topic
+    /// Collection contexts keyed by Danube topic (one per topic mapping, or per topic
+    /// matching a topic pattern)
     collections: HashMap<String, CollectionContext>,
 }
 
@@ -98,6 +116,37 @@ impl QdrantSinkConnector {
         }
     }
 
+    /// The collection context of a record's topic
+    ///
+    /// Topics consumed through a topic pattern get their own context on first use, so
+    /// their batches and flushed offsets are tracked per topic.
+    fn collection_context(&mut self, topic: &str) -> ConnectorResult<&mut CollectionContext> {
+        if !self.collections.contains_key(topic) {
+            let mapping =
+                find_topic_match(&self.config.topic_mappings, topic, |mapping| &mapping.topic)
+                    .ok_or_else(|| {
+                        ConnectorError::invalid_data(
+                            format!("No collection configured for topic: {}", topic),
+                            vec![],
+                        )
+                    })?;
+            info!(
+                "Topic '{}' matches pattern '{}', writing to collection '{}'",
+                topic, mapping.topic, mapping.collection_name
+            );
+            let context = CollectionContext::new(
+                mapping.clone(),
+                self.config.batch_size,
+                self.config.batch_timeout_ms,
+            );
+            self.collections.insert(topic.to_string(), context);
+        }
+        Ok(self
+            .collections
+            .get_mut(topic)
+            .expect("context inserted above"))
+    }
+
     /// Flush batch for a specific collection
     async fn flush_batch(&mut self, topic: &str) -> ConnectorResult<()> {
         let context = self.collections.get_mut(topic).ok_or_else(|| {
@@ -113,7 +162,8 @@ impl QdrantSinkConnector {
             .as_ref()
             .ok_or_else(|| ConnectorError::fatal("Qdrant client not initialized"))?;
 
-        let points_to_insert = std::mem::take(&mut context.batch_buffer);
+        // Keep the buffer until the upsert succeeds so failed points are not lost
+        let points_to_insert = context.batch_buffer.clone();
         let count = points_to_insert.len();
 
         info!(
@@ -132,6 +182,11 @@ impl QdrantSinkConnector {
                 ConnectorError::retryable(format!("Failed to upsert points to Qdrant: {}", e))
             })?;
 
+        context.batch_buffer.clear();
+        context.buffered_position = None;
+        if let Some(offset) = context.buffered_offset.take() {
+            context.flushed_offset = Some(offset);
+        }
         context.points_inserted += count as u64;
         context.batches_flushed += 1;
         context.last_flush = Instant::now();
@@ -147,6 +202,22 @@ impl QdrantSinkConnector {
         Ok(())
     }
 
+    /// Flush all collections whose batch size or timeout has been reached
+    async fn flush_pending(&mut self) -> ConnectorResult<()> {
+        let topics_to_flush: Vec<String> = self
+            .collections
+            .iter()
+            .filter(|(_, context)| context.should_flush())
+            .map(|(topic, _)| topic.clone())
+            .collect();
+
+        for topic in topics_to_flush {
+            self.flush_batch(&topic).await?;
+        }
+
+        Ok(())
+    }
+
     /// Ensure collection exists for a specific mapping, create if needed
     async fn ensure_collection(&self, mapping: &TopicMapping) -> ConnectorResult<()> {
         let client = self
@@ -259,6 +330,11 @@ impl SinkConnector for QdrantSinkConnector {
             // Ensure collection exists
             self.ensure_collection(mapping).await?;
 
+            // Topics matching a pattern get their context on first use
+            if is_topic_pattern(&mapping.topic) {
+                continue;
+            }
+
             // Create collection context
             let context = CollectionContext::new(
                 mapping.clone(),
@@ -287,6 +363,10 @@ impl SinkConnector for QdrantSinkConnector {
                 consumer_name: format!("qdrant-sink-{}", mapping.collection_name),
                 subscription: mapping.subscription.clone(),
                 subscription_type: mapping.subscription_type.clone(),
+                dead_letter_topic: mapping.dead_letter_topic.clone(),
+                failure_policy: mapping.failure_policy,
+                start_position: mapping.start_position,
+                json_schema: mapping.json_schema.clone(),
             })
             .collect();
 
@@ -297,29 +377,37 @@ impl SinkConnector for QdrantSinkConnector {
         let topic = record.topic();
 
         // Get collection context for this topic
-        let context = self.collections.get_mut(topic).ok_or_else(|| {
-            ConnectorError::invalid_data(
-                format!("No collection configured for topic: {}", topic),
-                vec![],
-            )
-        })?;
-
-        // Transform Danube message to Qdrant point
-        let point = transform_to_point(
-            &record,
-            context.mapping.vector_dimension,
-            context.mapping.include_danube_metadata,
-        )?;
-
-        debug!(
-            "Transformed message from topic {} offset {} into Qdrant point for collection '{}'",
-            record.topic(),
-            record.offset(),
-            context.mapping.collection_name
-        );
+        let context = self.collection_context(topic)?;
+
+        // A record retried after a failed flush is still in the buffer
+        if context.is_buffered(record.position()) {
+            debug!(
+                "Message from topic {} offset {} is already buffered, retrying flush",
+                record.topic(),
+                record.offset()
+            );
+        } else {
+            // Transform Danube message to Qdrant point
+            let point = transform_to_point(
+                &record,
+                context.mapping.vector_dimension,
+                context.mapping.include_danube_metadata,
+            )?;
+
+            debug!(
+                "Transformed message from topic {} offset {} into Qdrant point for collection '{}'",
+                record.topic(),
+                record.offset(),
+                context.mapping.collection_name
+            );
 
-        // Add to batch buffer
-        context.batch_buffer.push(point);
+            // Add to batch buffer
+            context.batch_buffer.push(point);
+            context.buffered_position = Some(record.position());
+            if record.is_last_split() {
+                context.buffered_offset = Some(record.offset());
+            }
+        }
 
         // Flush if batch is ready
         if context.should_flush() {
@@ -336,6 +424,98 @@ impl SinkConnector for QdrantSinkConnector {
             self.process(record).await?;
         }
 
+        // Flush collections whose batch timeout elapsed (also serves the runtime's
+        // periodic flush check, which calls this with an empty batch)
+        self.flush_pending().await
+    }
+
+    fn ack_mode(&self) -> AckMode {
+        // Points are buffered until the batch is upserted
+        AckMode::Deferred
+    }
+
+    async fn flushed_offsets(&mut self) -> ConnectorResult<Vec<Offset>> {
+        Ok(self
+            .collections
+            .iter_mut()
+            .filter_map(|(topic, context)| {
+                context
+                    .flushed_offset
+                    .take()
+                    .map(|offset| Offset::new(topic.clone(), offset))
+            })
+            .collect())
+    }
+
+    async fn reconfigure(&mut self, config: &str) -> ConnectorResult<()> {
+        let config = QdrantSinkConfig::from_toml(config)?.qdrant;
+        config.validate()?;
+
+        if config.url != self.config.url
+            || config.api_key != self.config.api_key
+            || config.timeout_secs != self.config.timeout_secs
+        {
+            return Err(ConnectorError::config(
+                "Changing the Qdrant connection requires a restart",
+            ));
+        }
+
+        // Create missing collections before anything changes
+        for mapping in &config.topic_mappings {
+            let unchanged = self
+                .collections
+                .get(&mapping.topic)
+                .is_some_and(|context| context.mapping.collection_name == mapping.collection_name);
+            if !unchanged {
+                self.ensure_collection(mapping).await?;
+            }
+        }
+
+        // Write buffered points with the mappings they were transformed for
+        let topics: Vec<String> = self.collections.keys().cloned().collect();
+        for topic in &topics {
+            self.flush_batch(topic).await?;
+        }
+
+        // Topics keep their context if a mapping still matches them
+        let mut collections = HashMap::new();
+        for (topic, previous) in self.collections.drain() {
+            let Some(mapping) =
+                find_topic_match(&config.topic_mappings, &topic, |mapping| &mapping.topic)
+            else {
+                info!(
+                    "Removed collection '{}' for topic '{}'",
+                    previous.mapping.collection_name, topic
+                );
+                continue;
+            };
+            let mut context =
+                CollectionContext::new(mapping.clone(), config.batch_size, config.batch_timeout_ms);
+            // Keep the statistics and the offsets not yet reported to the runtime
+            context.flushed_offset = previous.flushed_offset;
+            context.points_inserted = previous.points_inserted;
+            context.batches_flushed = previous.batches_flushed;
+            collections.insert(topic, context);
+        }
+        for mapping in &config.topic_mappings {
+            if is_topic_pattern(&mapping.topic) || collections.contains_key(&mapping.topic) {
+                continue;
+            }
+            info!(
+                "Added collection '{}' for topic '{}'",
+                mapping.collection_name, mapping.topic
+            );
+            let context =
+                CollectionContext::new(mapping.clone(), config.batch_size, config.batch_timeout_ms);
+            collections.insert(mapping.topic.clone(), context);
+        }
+
+        self.collections = collections;
+        self.config = config;
+        info!(
+            "Qdrant Sink Connector reconfigured with {} collection(s)",
+            self.collections.len()
+        );
         Ok(())
     }
 
@@ -404,6 +584,7 @@ mod tests {
     use super::*;
     use crate::config::Distance;
     use danube_connect_core::SubscriptionType;
+    use danube_core::message::{MessageID, StreamMessage};
     use std::collections::HashMap;
 
     #[test]
@@ -426,6 +607,10 @@ mod tests {
             include_danube_metadata: true,
             batch_size: Some(3),
             batch_timeout_ms: None,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
         };
 
         let mut context = CollectionContext::new(mapping, 100, 1000);
@@ -449,6 +634,192 @@ mod tests {
         assert!(context.should_flush()); // Now should flush
     }
 
+    #[tokio::test]
+    async fn test_flushed_offsets_reported_once() {
+        let mapping = TopicMapping {
+            topic: "/default/test".to_string(),
+            subscription: "test-sub".to_string(),
+            subscription_type: SubscriptionType::Exclusive,
+            collection_name: "test_collection".to_string(),
+            vector_dimension: 384,
+            distance: Distance::Cosine,
+            auto_create_collection: true,
+            include_danube_metadata: true,
+            batch_size: None,
+            batch_timeout_ms: None,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
+        };
+
+        let mut context = CollectionContext::new(mapping, 100, 1000);
+        context.buffered_position = Some((7, 0));
+        context.buffered_offset = Some(7);
+        assert!(context.is_buffered((7, 0)));
+        assert!(!context.is_buffered((8, 0)));
+
+        // Simulate a successful upsert of the buffered points
+        context.flushed_offset = context.buffered_offset.take();
+
+        let mut connector = QdrantSinkConnector::new();
+        connector
+            .collections
+            .insert("/default/test".to_string(), context);
+
+        assert_eq!(connector.ack_mode(), AckMode::Deferred);
+        assert_eq!(
+            connector.flushed_offsets().await.unwrap(),
+            vec![Offset::new("/default/test", 7)]
+        );
+        assert!(connector.flushed_offsets().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_split_records_are_all_buffered() {
+        let mapping = TopicMapping {
+            topic: "/default/test".to_string(),
+            subscription: "test-sub".to_string(),
+            subscription_type: SubscriptionType::Exclusive,
+            collection_name: "test_collection".to_string(),
+            vector_dimension: 2,
+            distance: Distance::Cosine,
+            auto_create_collection: true,
+            include_danube_metadata: false,
+            batch_size: None,
+            batch_timeout_ms: None,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
+        };
+        let mut connector = QdrantSinkConnector::new();
+        connector.collections.insert(
+            "/default/test".to_string(),
+            CollectionContext::new(mapping, 100, 60_000),
+        );
+
+        // A script split the message at offset 7 into two records
+        let split = |split_index: usize, vector: [f32; 2]| {
+            let message = StreamMessage {
+                request_id: 1,
+                msg_id: MessageID {
+                    producer_id: 1,
+                    topic_name: "/default/test".to_string(),
+                    broker_addr: "localhost:6650".to_string(),
+                    topic_offset: 7,
+                },
+                payload: serde_json::to_vec(&serde_json::json!({ "vector": vector })).unwrap(),
+                publish_time: 0,
+                producer_name: "producer".to_string(),
+                subscription_name: Some("test-sub".to_string()),
+                attributes: HashMap::new(),
+            };
+            let mut record = SinkRecord::from_stream_message(message, None);
+            record.danube_metadata.split_index = split_index;
+            record.danube_metadata.split_count = 2;
+            record
+        };
+
+        connector.process(split(0, [0.1, 0.2])).await.unwrap();
+        let context = &connector.collections["/default/test"];
+        assert_eq!(context.batch_buffer.len(), 1);
+        // The message is not written until its last record is
+        assert_eq!(context.buffered_offset, None);
+
+        connector.process(split(1, [0.3, 0.4])).await.unwrap();
+        // A retried record is not buffered twice
+        connector.process(split(1, [0.3, 0.4])).await.unwrap();
+        let context = &connector.collections["/default/test"];
+        assert_eq!(context.batch_buffer.len(), 2);
+        assert_ne!(context.batch_buffer[0].id, context.batch_buffer[1].id);
+        assert_eq!(context.buffered_offset, Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_records_of_pattern_mapping_buffered_per_topic() {
+        let mapping = |topic: &str, collection_name: &str| TopicMapping {
+            topic: topic.to_string(),
+            subscription: "test-sub".to_string(),
+            subscription_type: SubscriptionType::Exclusive,
+            collection_name: collection_name.to_string(),
+            vector_dimension: 2,
+            distance: Distance::Cosine,
+            auto_create_collection: true,
+            include_danube_metadata: false,
+            batch_size: None,
+            batch_timeout_ms: Some(60_000),
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
+        };
+        let mut connector = QdrantSinkConnector::with_config(QdrantConfig {
+            url: "http://localhost:6334".to_string(),
+            api_key: None,
+            topic_mappings: vec![
+                mapping("/embeddings/*", "embeddings"),
+                mapping("/embeddings/docs", "docs"),
+            ],
+            batch_size: 100,
+            batch_timeout_ms: 60_000,
+            timeout_secs: 30,
+        });
+        let record = |topic: &str, offset: u64| {
+            let message = StreamMessage {
+                request_id: 1,
+                msg_id: MessageID {
+                    producer_id: 1,
+                    topic_name: topic.to_string(),
+                    broker_addr: "localhost:6650".to_string(),
+                    topic_offset: offset,
+                },
+                payload: serde_json::to_vec(&serde_json::json!({ "vector": [0.1, 0.2] })).unwrap(),
+                publish_time: 0,
+                producer_name: "producer".to_string(),
+                subscription_name: Some("test-sub".to_string()),
+                attributes: HashMap::new(),
+            };
+            SinkRecord::from_stream_message(message, None)
+        };
+
+        connector
+            .process(record("/embeddings/faq", 3))
+            .await
+            .unwrap();
+        connector
+            .process(record("/embeddings/docs", 5))
+            .await
+            .unwrap();
+
+        // The topic matching the pattern gets its own context, the exact mapping wins
+        let faq = &connector.collections["/embeddings/faq"];
+        assert_eq!(faq.mapping.collection_name, "embeddings");
+        assert_eq!(faq.buffered_offset, Some(3));
+        let docs = &connector.collections["/embeddings/docs"];
+        assert_eq!(docs.mapping.collection_name, "docs");
+        assert_eq!(docs.buffered_offset, Some(5));
+
+        let err = connector
+            .process(record("/default/other", 1))
+            .await
+            .unwrap_err();
+        assert!(err.is_invalid_data());
+    }
+
+    #[tokio::test]
+    async fn test_reconfigure_rejects_connection_change() {
+        let content = include_str!("../config/connector.toml");
+        let mut config = QdrantSinkConfig::from_toml(content).unwrap().qdrant;
+        config.url = "http://qdrant-old:6334".to_string();
+        let mut connector = QdrantSinkConnector::with_config(config);
+
+        assert!(connector.reconfigure("qdrant = ").await.is_err());
+        let err = connector.reconfigure(content).await.unwrap_err();
+        assert!(err.to_string().contains("requires a restart"));
+        assert_eq!(connector.config.url, "http://qdrant-old:6334");
+    }
+
     #[test]
     fn test_topic_mapping_effective_values() {
         let mapping = TopicMapping {
@@ -462,6 +833,10 @@ mod tests {
             include_danube_metadata: true,
             batch_size: Some(50),
             batch_timeout_ms: Some(500),
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
         };
 
         // Uses topic-specific values
@@ -479,6 +854,10 @@ mod tests {
             include_danube_metadata: false,
             batch_size: None,
             batch_timeout_ms: None,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
         };
 
         // Uses global values
@@ -9,7 +9,7 @@ mod transform;
 
 use config::QdrantSinkConfig;
 use connector::QdrantSinkConnector;
-use danube_connect_core::{ConnectorResult, SinkRuntime};
+use danube_connect_core::{ConnectorResult, SinkRuntime, CONFIG_PATH_ENV};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -65,6 +65,11 @@ async fn main() -> ConnectorResult<()> {
     // Create and run the runtime
     let mut runtime = SinkRuntime::new(connector, config.core).await?;
 
+    // Apply changes of the config file without a restart
+    if let Ok(config_path) = std::env::var(CONFIG_PATH_ENV) {
+        runtime = runtime.with_config_reload(config_path);
+    }
+
     // Run until shutdown signal
     runtime.run().await?;
 
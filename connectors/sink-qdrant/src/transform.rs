@@ -74,8 +74,12 @@ fn generate_point_id(message: &VectorMessage, record: &SinkRecord) -> u64 {
         return hash_string_to_u64(id);
     }
 
-    // Generate ID from topic + offset to ensure uniqueness across topics
-    let composite_key = format!("{}:{}", record.topic(), record.offset());
+    // Generate ID from topic + offset to ensure uniqueness across topics, plus the split
+    // index for the records a transform split a message into
+    let composite_key = match record.position() {
+        (offset, 0) => format!("{}:{}", record.topic(), offset),
+        (offset, split_index) => format!("{}:{}:{}", record.topic(), offset, split_index),
+    };
     hash_string_to_u64(&composite_key)
 }
 
@@ -160,10 +164,8 @@ fn add_json_to_payload(payload: &mut HashMap<String, Value>, prefix: &str, value
                     serde_json::Value::Number(n) => {
                         if let Some(i) = n.as_i64() {
                             Some(Value::from(i))
-                        } else if let Some(f) = n.as_f64() {
-                            Some(Value::from(f))
                         } else {
-                            None
+                            n.as_f64().map(Value::from)
                         }
                     }
                     serde_json::Value::Bool(b) => Some(Value::from(b)),
@@ -236,7 +238,7 @@ mod tests {
 
         // Verify point was created successfully
         assert!(point.id.is_some());
-        assert!(point.payload.len() > 0);  // Should have payload with metadata
+        assert!(!point.payload.is_empty());  // Should have payload with metadata
     }
 
     #[test]
@@ -6,16 +6,27 @@
 //! - Batch processing and performance tuning
 //! - Environment variable overrides
 
-use danube_connect_core::{ConnectorConfig, ConnectorError, ConnectorResult, SchemaType};
+use danube_connect_core::{
+    load_config_with_aliases, ConnectorConfig, ConnectorError, ConnectorResult, FailurePolicy, PayloadSchema,
+    SchemaType, StartPosition,
+};
 use serde::{Deserialize, Serialize};
-use std::env;
-use std::fs;
+
+/// Former environment variables, still accepted as aliases of the generic overrides
+const DEPRECATED_ENV_VARS: &[(&str, &str)] = &[
+    ("DANUBE_SERVICE_URL", "DANUBE_CONNECT__DANUBE_SERVICE_URL"),
+    ("CONNECTOR_NAME", "DANUBE_CONNECT__CONNECTOR_NAME"),
+    ("SURREALDB_URL", "DANUBE_CONNECT__SURREALDB__URL"),
+    ("SURREALDB_USERNAME", "DANUBE_CONNECT__SURREALDB__USERNAME"),
+    ("SURREALDB_PASSWORD", "DANUBE_CONNECT__SURREALDB__PASSWORD"),
+];
 
 /// Storage mode for SurrealDB records
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "PascalCase")]
 pub enum StorageMode {
     /// Store as regular documents (default)
+    #[default]
     Document,
     /// Store as time-series data with timestamp optimization
     TimeSeries,
@@ -97,14 +108,80 @@ pub struct TopicMapping {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub flush_interval_ms: Option<u64>,
 
-    /// Schema type for this topic (must match Danube topic schema)
-    /// Determines how to interpret and insert the payload
+    /// Schema type for this topic, determines how to interpret and insert the payload
+    /// (default: the schema type registered for the topic, `Bytes` if it has none)
     #[serde(default)]
-    pub schema_type: SchemaType,
+    pub schema_type: Option<SchemaType>,
+
+    /// Avro schema (`.avsc`) or Protobuf descriptor set of `Avro` and `Protobuf` payloads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_file: Option<String>,
+
+    /// Fully qualified Protobuf message type of `Protobuf` payloads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_type: Option<String>,
+
+    /// Schema definition loaded from `schema_file` by `load_schema()`
+    #[serde(skip)]
+    pub loaded_schema: Option<PayloadSchema>,
 
     /// Storage mode: Document or TimeSeries
     #[serde(default)]
     pub storage_mode: StorageMode,
+
+    /// Dead-letter topic for records that cannot be converted or inserted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead_letter_topic: Option<String>,
+
+    /// What happens to records that still fail after the retries
+    /// (default: `retry.failure_policy`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_policy: Option<FailurePolicy>,
+
+    /// Position to start consuming from: `"earliest"`, `"latest"`, `{ offset = n }` or
+    /// `{ publish_time = ms }` (default: where the broker places the subscription)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_position: Option<StartPosition>,
+
+    /// JSON Schema file the payloads must match; records that don't are dead-lettered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_schema: Option<String>,
+}
+
+impl TopicMapping {
+    /// Load the schema definition of `Avro` and `Protobuf` payloads from `schema_file`
+    pub fn load_schema(&mut self) -> ConnectorResult<()> {
+        let Some(schema_type) = self.schema_type else {
+            if self.schema_file.is_some() {
+                return Err(ConnectorError::config(format!(
+                    "Topic '{}': schema_file requires schema_type Avro or Protobuf",
+                    self.topic
+                )));
+            }
+            return Ok(());
+        };
+        let schema = PayloadSchema::load(
+            schema_type,
+            self.schema_file.as_deref(),
+            self.message_type.as_deref(),
+        )
+        .map_err(|e| ConnectorError::config(format!("Topic '{}': {}", self.topic, e)))?;
+        self.loaded_schema = Some(schema);
+        Ok(())
+    }
+
+    /// Schema the payloads of this topic are deserialized with
+    ///
+    /// Without a configured `schema_type`, the schema type `registered` for the topic is
+    /// used, `Bytes` if it has none.
+    pub fn payload_schema(&self, registered: Option<SchemaType>) -> PayloadSchema {
+        self.loaded_schema.clone().unwrap_or_else(|| {
+            self.schema_type
+                .or(registered)
+                .unwrap_or_default()
+                .into()
+        })
+    }
 }
 
 // Default value functions
@@ -128,71 +205,18 @@ fn default_include_metadata() -> bool {
     true
 }
 
-impl Default for StorageMode {
-    fn default() -> Self {
-        StorageMode::Document
-    }
-}
-
 impl SurrealDBSinkConfig {
-    /// Load configuration from TOML file
-    /// 
-    /// The config file path must be specified via CONNECTOR_CONFIG_PATH environment variable.
-    /// Environment variables can override secrets (username, password) and URLs.
+    /// Load configuration from the TOML file at `CONNECTOR_CONFIG_PATH`
+    ///
+    /// Supports `${VAR}` interpolation, `DANUBE_CONNECT__SECTION__KEY` overrides and
+    /// `*_secret_file` secrets (see `danube_connect_core::load_config`). The former
+    /// variables such as `SURREALDB_PASSWORD` are deprecated aliases of the overrides.
     pub fn load() -> ConnectorResult<Self> {
-        let config_path = env::var("CONNECTOR_CONFIG_PATH")
-            .map_err(|_| ConnectorError::config(
-                "CONNECTOR_CONFIG_PATH environment variable must be set to the path of the TOML configuration file"
-            ))?;
-        
-        Self::from_file(&config_path)
-    }
-
-    /// Load configuration from a TOML file
-    pub fn from_file(path: &str) -> ConnectorResult<Self> {
-        let contents = fs::read_to_string(path).map_err(|e| {
-            ConnectorError::config(format!("Failed to read config file '{}': {}", path, e))
-        })?;
-
-        let mut config: Self = toml::from_str(&contents)
-            .map_err(|e| ConnectorError::config(format!("Failed to parse TOML config: {}", e)))?;
-
-        // Apply environment variable overrides
-        config.apply_env_overrides()?;
-
-        Ok(config)
-    }
-
-    /// Apply environment variable overrides for secrets and connection details
-    /// 
-    /// Only overrides sensitive data that shouldn't be in config files:
-    /// - Credentials (username, password)
-    /// - Connection URLs (for different environments)
-    /// - Connector name (for different deployments)
-    fn apply_env_overrides(&mut self) -> ConnectorResult<()> {
-        // Override core Danube settings (mandatory fields from danube-connect-core)
-        if let Ok(danube_url) = env::var("DANUBE_SERVICE_URL") {
-            self.core.danube_service_url = danube_url;
-        }
-        
-        if let Ok(connector_name) = env::var("CONNECTOR_NAME") {
-            self.core.connector_name = connector_name;
+        let mut config: Self = load_config_with_aliases(DEPRECATED_ENV_VARS)?;
+        for mapping in &mut config.surrealdb.topic_mappings {
+            mapping.load_schema()?;
         }
-
-        // Override SurrealDB connection URL (for different environments: dev/staging/prod)
-        if let Ok(url) = env::var("SURREALDB_URL") {
-            self.surrealdb.url = url;
-        }
-
-        // Override credentials (secrets should not be in config files)
-        if let Ok(username) = env::var("SURREALDB_USERNAME") {
-            self.surrealdb.username = Some(username);
-        }
-        if let Ok(password) = env::var("SURREALDB_PASSWORD") {
-            self.surrealdb.password = Some(password);
-        }
-
-        Ok(())
+        Ok(config)
     }
 
     /// Validate configuration
@@ -251,7 +275,10 @@ mod tests {
                 connector_name: "test".to_string(),
                 danube_service_url: "http://localhost:6650".to_string(),
                 retry: Default::default(),
+                circuit_breaker: Default::default(),
                 processing: Default::default(),
+                offset_store: Default::default(),
+                transforms: Vec::new(),
             },
             surrealdb: SurrealDBConfig {
                 url: "ws://localhost:8000".to_string(),
@@ -268,8 +295,15 @@ mod tests {
                     include_danube_metadata: true,
                     batch_size: None,
                     flush_interval_ms: None,
-                    schema_type: SchemaType::Json,
+                    schema_type: Some(SchemaType::Json),
+                    schema_file: None,
+                    message_type: None,
+                    loaded_schema: None,
                     storage_mode: StorageMode::Document,
+                    dead_letter_topic: None,
+                    failure_policy: None,
+                    start_position: None,
+                    json_schema: None,
                 }],
                 batch_size: 100,
                 flush_interval_ms: 1000,
@@ -295,7 +329,10 @@ mod tests {
                 connector_name: "test".to_string(),
                 danube_service_url: "http://localhost:6650".to_string(),
                 retry: Default::default(),
+                circuit_breaker: Default::default(),
                 processing: Default::default(),
+                offset_store: Default::default(),
+                transforms: Vec::new(),
             },
             surrealdb: SurrealDBConfig {
                 url: "ws://localhost:8000".to_string(),
@@ -313,8 +350,15 @@ mod tests {
                         include_danube_metadata: true,
                         batch_size: None,
                         flush_interval_ms: None,
-                        schema_type: SchemaType::Json,
+                        schema_type: Some(SchemaType::Json),
+                        schema_file: None,
+                        message_type: None,
+                        loaded_schema: None,
                         storage_mode: StorageMode::Document,
+                        dead_letter_topic: None,
+                        failure_policy: None,
+                        start_position: None,
+                        json_schema: None,
                     },
                     TopicMapping {
                         topic: "/test/timeseries".to_string(),
@@ -323,8 +367,15 @@ mod tests {
                         include_danube_metadata: true,
                         batch_size: None,
                         flush_interval_ms: None,
-                        schema_type: SchemaType::Json,
+                        schema_type: Some(SchemaType::Json),
+                        schema_file: None,
+                        message_type: None,
+                        loaded_schema: None,
                         storage_mode: StorageMode::TimeSeries,
+                        dead_letter_topic: None,
+                        failure_policy: None,
+                        start_position: None,
+                        json_schema: None,
                     },
                 ],
                 batch_size: 100,
@@ -345,4 +396,47 @@ mod tests {
         assert!(default_include_metadata());
         assert_eq!(StorageMode::default(), StorageMode::Document);
     }
+
+    #[test]
+    #[cfg(feature = "avro")]
+    fn test_load_mapping_with_schema_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let avsc = dir.path().join("reading.avsc");
+        std::fs::write(
+            &avsc,
+            r#"{"type": "record", "name": "Reading", "fields": [{"name": "value", "type": "double"}]}"#,
+        )
+        .unwrap();
+        let config_path = dir.path().join("connector.toml");
+        std::fs::write(
+            &config_path,
+            format!(
+                r#"
+                connector_name = "surrealdb-sink"
+                danube_service_url = "http://localhost:6650"
+
+                [surrealdb]
+                url = "ws://localhost:8000"
+                namespace = "test"
+                database = "test"
+
+                [[surrealdb.topic_mappings]]
+                topic = "/iot/readings"
+                subscription = "surrealdb-readings"
+                table_name = "readings"
+                schema_type = "Avro"
+                schema_file = {:?}
+                "#,
+                avsc.display().to_string()
+            ),
+        )
+        .unwrap();
+
+        let mut config: SurrealDBSinkConfig =
+            danube_connect_core::load_config_file(&config_path).unwrap();
+        let mapping = &mut config.surrealdb.topic_mappings[0];
+        assert_eq!(mapping.schema_file.as_deref(), Some(avsc.to_str().unwrap()));
+        mapping.load_schema().unwrap();
+        assert!(mapping.loaded_schema.is_some());
+    }
 }
@@ -10,9 +10,10 @@
 use crate::config::{SurrealDBSinkConfig, TopicMapping};
 use crate::record::{to_surrealdb_record, SurrealDBRecord};
 use async_trait::async_trait;
+use danube_connect_core::utils::{find_topic_match, is_topic_pattern};
 use danube_connect_core::{
-    ConnectorConfig, ConnectorError, ConnectorResult, ConsumerConfig, SinkConnector, SinkRecord,
-    SubscriptionType,
+    AckMode, ConnectorConfig, ConnectorError, ConnectorResult, ConsumerConfig, Offset,
+    SinkConnector, SinkRecord, SubscriptionType,
 };
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -39,6 +40,15 @@ struct TableContext {
     /// Effective flush interval for this table
     flush_interval: Duration,
 
+    /// Position of the last record added to the batch buffer
+    buffered_position: Option<(u64, usize)>,
+
+    /// Highest Danube offset with all its records in the batch buffer
+    buffered_offset: Option<u64>,
+
+    /// Highest Danube offset written to SurrealDB and not yet reported to the runtime
+    flushed_offset: Option<u64>,
+
     /// Statistics
     records_inserted: u64,
     batches_flushed: u64,
@@ -60,6 +70,9 @@ impl TableContext {
             last_flush: Instant::now(),
             batch_size,
             flush_interval,
+            buffered_position: None,
+            buffered_offset: None,
+            flushed_offset: None,
             records_inserted: 0,
             batches_flushed: 0,
             last_error: None,
@@ -70,6 +83,12 @@ impl TableContext {
         self.batch_buffer.len() >= self.batch_size
             || (!self.batch_buffer.is_empty() && self.last_flush.elapsed() >= self.flush_interval)
     }
+
+    /// Check if the record at `position` is already in the batch buffer
+    fn is_buffered(&self, position: (u64, usize)) -> bool {
+        self.buffered_position
+            .is_some_and(|buffered| position <= buffered)
+    }
 }
 
 /// SurrealDB Sink Connector
@@ -80,7 +99,8 @@ pub struct SurrealDBSinkConnector {
     /// SurrealDB client connection
     client: Option<Surreal<Client>>,
 
-    /// Table contexts (one per topic mapping)
+    /// Table contexts by Danube topic (one per topic mapping, or per topic matching a
+    /// topic pattern)
     tables: HashMap<String, TableContext>,
 }
 
@@ -91,6 +111,7 @@ impl SurrealDBSinkConnector {
             .surrealdb
             .topic_mappings
             .iter()
+            .filter(|mapping| !is_topic_pattern(&mapping.topic))
             .map(|mapping| {
                 let context = TableContext::new(
                     mapping.clone(),
@@ -114,6 +135,33 @@ impl SurrealDBSinkConnector {
         Ok(Self::with_config(config))
     }
 
+    /// The table context of a record's topic
+    ///
+    /// Topics consumed through a topic pattern get their own context on first use, so
+    /// their batches and flushed offsets are tracked per topic.
+    fn table_context(&mut self, topic: &str) -> ConnectorResult<&mut TableContext> {
+        if !self.tables.contains_key(topic) {
+            let mapping =
+                find_topic_match(&self.config.surrealdb.topic_mappings, topic, |mapping| {
+                    &mapping.topic
+                })
+                .ok_or_else(|| {
+                    ConnectorError::fatal(format!("No mapping configured for topic: {}", topic))
+                })?;
+            info!(
+                "Topic '{}' matches pattern '{}' → Table '{}'",
+                topic, mapping.topic, mapping.table_name
+            );
+            let context = TableContext::new(
+                mapping.clone(),
+                self.config.surrealdb.batch_size,
+                self.config.surrealdb.flush_interval_ms,
+            );
+            self.tables.insert(topic.to_string(), context);
+        }
+        Ok(self.tables.get_mut(topic).expect("context inserted above"))
+    }
+
     /// Flush a specific table's batch to SurrealDB
     async fn flush_table(&mut self, topic: &str) -> ConnectorResult<()> {
         let context = self
@@ -138,22 +186,23 @@ impl SurrealDBSinkConnector {
             .as_ref()
             .ok_or_else(|| ConnectorError::fatal("SurrealDB client not initialized"))?;
 
-        // Insert records in batch
-        let records: Vec<_> = context.batch_buffer.drain(..).collect();
+        // Insert records in batch; the buffer is only drained once records are written
+        for idx in 0..batch_size {
+            let record = &context.batch_buffer[idx];
 
-        for record in records {
             // SurrealDB 2.x has serialization issues with serde_json::Value enums
             // Workaround: Use query parameters with cloned data
             // Clone is necessary because .bind() requires 'static lifetime
             let data = record.data.clone();
-            
+
             let result = match &record.id {
                 Some(id) => {
                     // Insert with specific record ID using query parameters
                     let thing = format!("{}:{}", table_name, id);
                     let query = format!("CREATE {} CONTENT $data", thing);
                     // Bind the data as a parameter - SurrealDB handles the serialization
-                    client.query(query)
+                    client
+                        .query(query)
                         .bind(("data", data))
                         .await
                         .map(|_| ())
@@ -163,7 +212,8 @@ impl SurrealDBSinkConnector {
                     // Auto-generate ID using query parameters
                     let query = format!("CREATE {} CONTENT $data", table_name);
                     // Bind the data as a parameter - SurrealDB handles the serialization
-                    client.query(query)
+                    client
+                        .query(query)
                         .bind(("data", data))
                         .await
                         .map(|_| ())
@@ -174,12 +224,20 @@ impl SurrealDBSinkConnector {
             if let Err((id, e)) = result {
                 error!("Failed to insert record with ID '{}': {}", id, e);
                 context.last_error = Some(format!("Insert error: {}", e));
+                // Drop the records already written, keep the rest for the next flush.
+                // They are acknowledged once the whole buffer has been written.
+                context.batch_buffer.drain(..idx);
                 return Err(ConnectorError::retryable(format!(
                     "Failed to insert record: {}",
                     e
                 )));
             }
         }
+        context.batch_buffer.clear();
+        context.buffered_position = None;
+        if let Some(offset) = context.buffered_offset.take() {
+            context.flushed_offset = Some(offset);
+        }
 
         // Update statistics
         context.records_inserted += batch_size as u64;
@@ -281,6 +339,10 @@ impl SinkConnector for SurrealDBSinkConnector {
                 ),
                 subscription: mapping.subscription.clone(),
                 subscription_type: SubscriptionType::Shared,
+                dead_letter_topic: mapping.dead_letter_topic.clone(),
+                failure_policy: mapping.failure_policy,
+                start_position: mapping.start_position,
+                json_schema: mapping.json_schema.clone(),
             })
             .collect();
 
@@ -291,15 +353,26 @@ impl SinkConnector for SurrealDBSinkConnector {
         let topic = record.topic();
 
         // Get the table context for this topic
-        let context = self.tables.get_mut(topic).ok_or_else(|| {
-            ConnectorError::fatal(format!("No mapping configured for topic: {}", topic))
-        })?;
+        let context = self.table_context(topic)?;
 
-        // Convert message to SurrealDB record based on schema type
-        let surrealdb_record = to_surrealdb_record(&record, &context.mapping)?;
-
-        // Add to batch buffer
-        context.batch_buffer.push(surrealdb_record);
+        // A record retried after a failed flush is still in the buffer
+        if context.is_buffered(record.position()) {
+            debug!(
+                "Message from topic {} offset {} is already buffered, retrying flush",
+                topic,
+                record.offset()
+            );
+        } else {
+            // Convert message to SurrealDB record based on schema type
+            let surrealdb_record = to_surrealdb_record(&record, &context.mapping)?;
+
+            // Add to batch buffer
+            context.batch_buffer.push(surrealdb_record);
+            context.buffered_position = Some(record.position());
+            if record.is_last_split() {
+                context.buffered_offset = Some(record.offset());
+            }
+        }
 
         // Flush if necessary
         if context.should_flush() {
@@ -320,6 +393,31 @@ impl SinkConnector for SurrealDBSinkConnector {
         Ok(())
     }
 
+    fn ack_mode(&self) -> AckMode {
+        // Records are buffered per table until the batch is flushed
+        AckMode::Deferred
+    }
+
+    async fn flushed_offsets(&mut self) -> ConnectorResult<Vec<Offset>> {
+        Ok(self
+            .tables
+            .iter_mut()
+            .filter_map(|(topic, context)| {
+                context
+                    .flushed_offset
+                    .take()
+                    .map(|offset| Offset::new(topic.clone(), offset))
+            })
+            .collect())
+    }
+
+    fn fork(&self) -> Option<Self> {
+        // Workers share the connection, each one buffers the tables of its own topics
+        let mut connector = Self::with_config(self.config.clone());
+        connector.client = self.client.clone();
+        Some(connector)
+    }
+
     async fn shutdown(&mut self) -> ConnectorResult<()> {
         info!("Shutting down SurrealDB Sink Connector");
 
@@ -375,6 +473,7 @@ mod tests {
     use super::*;
     use crate::config::StorageMode;
     use danube_connect_core::SchemaType;
+    use danube_core::message::{MessageID, StreamMessage};
     use serde_json::Value;
 
     #[test]
@@ -386,8 +485,15 @@ mod tests {
             include_danube_metadata: false,
             batch_size: Some(10),
             flush_interval_ms: Some(5000),
-            schema_type: SchemaType::Json,
+            schema_type: Some(SchemaType::Json),
+            schema_file: None,
+            message_type: None,
+            loaded_schema: None,
             storage_mode: StorageMode::Document,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
         };
 
         let mut context = TableContext::new(mapping, 100, 1000);
@@ -419,7 +525,10 @@ mod tests {
                 connector_name: "test".to_string(),
                 danube_service_url: "http://localhost:6650".to_string(),
                 retry: Default::default(),
+                circuit_breaker: Default::default(),
                 processing: Default::default(),
+                offset_store: Default::default(),
+                transforms: Vec::new(),
             },
             surrealdb: crate::config::SurrealDBConfig {
                 url: "ws://localhost:8000".to_string(),
@@ -436,8 +545,15 @@ mod tests {
                     include_danube_metadata: true,
                     batch_size: None,
                     flush_interval_ms: None,
-                    schema_type: SchemaType::Json,
+                    schema_type: Some(SchemaType::Json),
+                    schema_file: None,
+                    message_type: None,
+                    loaded_schema: None,
                     storage_mode: StorageMode::Document,
+                    dead_letter_topic: None,
+                    failure_policy: None,
+                    start_position: None,
+                    json_schema: None,
                 }],
                 batch_size: 100,
                 flush_interval_ms: 1000,
@@ -448,4 +564,137 @@ mod tests {
         assert_eq!(connector.tables.len(), 1);
         assert!(connector.client.is_none());
     }
+
+    fn mapping(topic: &str) -> TopicMapping {
+        TopicMapping {
+            topic: topic.to_string(),
+            subscription: "test-sub".to_string(),
+            table_name: "events".to_string(),
+            include_danube_metadata: false,
+            batch_size: None,
+            flush_interval_ms: Some(60_000),
+            schema_type: Some(SchemaType::Json),
+            schema_file: None,
+            message_type: None,
+            loaded_schema: None,
+            storage_mode: StorageMode::Document,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
+        }
+    }
+
+    fn connector(topic_mappings: Vec<TopicMapping>) -> SurrealDBSinkConnector {
+        SurrealDBSinkConnector::with_config(SurrealDBSinkConfig {
+            core: ConnectorConfig {
+                connector_name: "test".to_string(),
+                danube_service_url: "http://localhost:6650".to_string(),
+                retry: Default::default(),
+                circuit_breaker: Default::default(),
+                processing: Default::default(),
+                offset_store: Default::default(),
+                transforms: Vec::new(),
+            },
+            surrealdb: crate::config::SurrealDBConfig {
+                url: "ws://localhost:8000".to_string(),
+                namespace: "test".to_string(),
+                database: "test".to_string(),
+                username: None,
+                password: None,
+                connection_timeout_secs: 30,
+                request_timeout_secs: 30,
+                topic_mappings,
+                batch_size: 100,
+                flush_interval_ms: 60_000,
+            },
+        })
+    }
+
+    fn record(topic: &str, offset: u64, payload: Value) -> SinkRecord {
+        let message = StreamMessage {
+            request_id: 1,
+            msg_id: MessageID {
+                producer_id: 1,
+                topic_name: topic.to_string(),
+                broker_addr: "localhost:6650".to_string(),
+                topic_offset: offset,
+            },
+            payload: serde_json::to_vec(&payload).unwrap(),
+            publish_time: 0,
+            producer_name: "producer".to_string(),
+            subscription_name: Some("test-sub".to_string()),
+            attributes: HashMap::new(),
+        };
+        SinkRecord::from_stream_message(message, None)
+    }
+
+    #[tokio::test]
+    async fn test_split_records_are_all_buffered() {
+        let mut connector = connector(vec![mapping("/test/topic")]);
+
+        // A script split the message at offset 42 into two records
+        let split = |split_index: usize| {
+            let mut record = record(
+                "/test/topic",
+                42,
+                serde_json::json!({ "part": split_index }),
+            );
+            record.danube_metadata.split_index = split_index;
+            record.danube_metadata.split_count = 2;
+            record
+        };
+
+        connector.process(split(0)).await.unwrap();
+        assert_eq!(connector.tables["/test/topic"].buffered_offset, None);
+
+        connector.process(split(1)).await.unwrap();
+        // A retried record is not buffered twice
+        connector.process(split(1)).await.unwrap();
+        let context = &connector.tables["/test/topic"];
+        assert_eq!(context.batch_buffer.len(), 2);
+        assert_eq!(context.batch_buffer[1].data["part"], 1);
+        assert_eq!(context.buffered_offset, Some(42));
+    }
+
+    #[tokio::test]
+    async fn test_records_of_pattern_mapping_buffered_per_topic() {
+        let mut connector = connector(vec![mapping("/events/*"), mapping("/events/orders")]);
+        assert_eq!(connector.tables.len(), 1);
+
+        let json = serde_json::json!({ "id": 1 });
+        connector
+            .process(record("/events/payments", 7, json.clone()))
+            .await
+            .unwrap();
+        connector
+            .process(record("/events/refunds", 3, json.clone()))
+            .await
+            .unwrap();
+        connector
+            .process(record("/events/orders", 5, json))
+            .await
+            .unwrap();
+
+        // Topics matching the pattern get their own table context
+        assert_eq!(
+            connector.tables["/events/payments"].mapping.topic,
+            "/events/*"
+        );
+        assert_eq!(
+            connector.tables["/events/payments"].buffered_offset,
+            Some(7)
+        );
+        assert_eq!(connector.tables["/events/refunds"].buffered_offset, Some(3));
+        assert_eq!(
+            connector.tables["/events/orders"].mapping.topic,
+            "/events/orders"
+        );
+        assert_eq!(connector.tables["/events/orders"].batch_buffer.len(), 1);
+
+        assert!(connector
+            .process(record("/default/other", 1, serde_json::json!({})))
+            .await
+            .is_err());
+    }
 }
@@ -38,7 +38,7 @@ pub fn to_surrealdb_record(
     let id = record.get_attribute("record_id").map(|s| s.to_string());
 
     // Deserialize payload using core library (handles all schema types)
-    let mut data = record.payload_deserialized(mapping.schema_type)?;
+    let mut data = record.payload_deserialized(mapping.payload_schema(record.schema_type()))?;
 
     // Add timestamp for time-series mode
     if mapping.storage_mode == StorageMode::TimeSeries {
@@ -67,7 +67,7 @@ fn add_timestamp(
     let publish_time_nanos = ((publish_time_micros % 1_000_000) * 1000) as u32;
     
     let timestamp = DateTime::from_timestamp(publish_time_secs, publish_time_nanos)
-        .unwrap_or_else(|| Utc::now());
+        .unwrap_or_else(Utc::now);
 
     // Add timestamp to data
     if let Value::Object(map) = data {
@@ -83,7 +83,7 @@ fn add_metadata(data: &mut Value, record: &SinkRecord) {
     let publish_time_secs = record.publish_time() / 1_000_000;
     let publish_time_nanos = ((record.publish_time() % 1_000_000) * 1000) as u32;
     let datetime = DateTime::from_timestamp(publish_time_secs as i64, publish_time_nanos)
-        .unwrap_or_else(|| Utc::now());
+        .unwrap_or_else(Utc::now);
 
     let metadata = json!({
         "danube_topic": record.topic(),
@@ -142,8 +142,15 @@ mod tests {
             include_danube_metadata: false,
             batch_size: None,
             flush_interval_ms: None,
-            schema_type: SchemaType::Json,
+            schema_type: Some(SchemaType::Json),
+            schema_file: None,
+            message_type: None,
+            loaded_schema: None,
             storage_mode: StorageMode::Document,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
         };
 
         let result = to_surrealdb_record(&record, &mapping).unwrap();
@@ -153,6 +160,39 @@ mod tests {
         assert_eq!(result.data["id"], "user-123"); // ID stays in payload
     }
 
+    #[test]
+    fn test_registered_schema_type() {
+        let payload = serde_json::to_vec(&json!({"name": "John Doe"})).unwrap();
+        let mut record = create_test_record(payload);
+        record.danube_metadata.schema_type = Some(SchemaType::Json);
+        let mut mapping = TopicMapping {
+            topic: "/test/topic".to_string(),
+            subscription: "test-sub".to_string(),
+            table_name: "users".to_string(),
+            include_danube_metadata: false,
+            batch_size: None,
+            flush_interval_ms: None,
+            schema_type: None,
+            schema_file: None,
+            message_type: None,
+            loaded_schema: None,
+            storage_mode: StorageMode::Document,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
+        };
+
+        // Without a configured schema type the topic's registered one is used
+        let result = to_surrealdb_record(&record, &mapping).unwrap();
+        assert_eq!(result.data["name"], "John Doe");
+
+        // A configured schema type takes precedence
+        mapping.schema_type = Some(SchemaType::String);
+        let result = to_surrealdb_record(&record, &mapping).unwrap();
+        assert_eq!(result.data, r#"{"name":"John Doe"}"#);
+    }
+
     #[test]
     fn test_string_record() {
         let payload = b"Hello, SurrealDB!".to_vec();
@@ -164,8 +204,15 @@ mod tests {
             include_danube_metadata: false,
             batch_size: None,
             flush_interval_ms: None,
-            schema_type: SchemaType::String,
+            schema_type: Some(SchemaType::String),
+            schema_file: None,
+            message_type: None,
+            loaded_schema: None,
             storage_mode: StorageMode::Document,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
         };
 
         let result = to_surrealdb_record(&record, &mapping).unwrap();
@@ -186,8 +233,15 @@ mod tests {
             include_danube_metadata: false,
             batch_size: None,
             flush_interval_ms: None,
-            schema_type: SchemaType::Int64,
+            schema_type: Some(SchemaType::Int64),
+            schema_file: None,
+            message_type: None,
+            loaded_schema: None,
             storage_mode: StorageMode::Document,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
         };
 
         let result = to_surrealdb_record(&record, &mapping).unwrap();
@@ -207,8 +261,15 @@ mod tests {
             include_danube_metadata: false,
             batch_size: None,
             flush_interval_ms: None,
-            schema_type: SchemaType::Bytes,
+            schema_type: Some(SchemaType::Bytes),
+            schema_file: None,
+            message_type: None,
+            loaded_schema: None,
             storage_mode: StorageMode::Document,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
         };
 
         let result = to_surrealdb_record(&record, &mapping).unwrap();
@@ -231,8 +292,15 @@ mod tests {
             include_danube_metadata: true,
             batch_size: None,
             flush_interval_ms: None,
-            schema_type: SchemaType::Json,
+            schema_type: Some(SchemaType::Json),
+            schema_file: None,
+            message_type: None,
+            loaded_schema: None,
             storage_mode: StorageMode::Document,
+            dead_letter_topic: None,
+            failure_policy: None,
+            start_position: None,
+            json_schema: None,
         };
 
         let result = to_surrealdb_record(&record, &mapping).unwrap();
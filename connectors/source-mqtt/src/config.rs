@@ -1,10 +1,23 @@
 //! Configuration for the MQTT Source Connector
 
-use danube_connect_core::{ConnectorConfig, ConnectorResult};
+use danube_connect_core::{
+    load_config_with_aliases, ConnectorConfig, ConnectorResult, SchemaType, TopicSchema,
+};
 use serde::{Deserialize, Serialize};
-use std::env;
 use std::time::Duration;
 
+/// Former environment variables, still accepted as aliases of the generic overrides
+const DEPRECATED_ENV_VARS: &[(&str, &str)] = &[
+    ("DANUBE_SERVICE_URL", "DANUBE_CONNECT__DANUBE_SERVICE_URL"),
+    ("CONNECTOR_NAME", "DANUBE_CONNECT__CONNECTOR_NAME"),
+    ("MQTT_BROKER_HOST", "DANUBE_CONNECT__MQTT__BROKER_HOST"),
+    ("MQTT_BROKER_PORT", "DANUBE_CONNECT__MQTT__BROKER_PORT"),
+    ("MQTT_CLIENT_ID", "DANUBE_CONNECT__MQTT__CLIENT_ID"),
+    ("MQTT_USERNAME", "DANUBE_CONNECT__MQTT__USERNAME"),
+    ("MQTT_PASSWORD", "DANUBE_CONNECT__MQTT__PASSWORD"),
+    ("MQTT_USE_TLS", "DANUBE_CONNECT__MQTT__USE_TLS"),
+];
+
 /// Unified configuration for MQTT Source Connector
 ///
 /// This struct combines core Danube configuration with MQTT-specific settings
@@ -23,7 +36,9 @@ impl MqttSourceConfig {
     /// Load configuration from TOML file
     ///
     /// The config file path must be specified via CONNECTOR_CONFIG_PATH environment variable.
-    /// Environment variables can override secrets (username, password) and connection URLs.
+    /// The file supports `${VAR}` interpolation, `DANUBE_CONNECT__SECTION__KEY` overrides
+    /// and `*_secret_file` secrets (see `danube_connect_core::load_config`). The former
+    /// variables such as `MQTT_PASSWORD` are deprecated aliases of the overrides.
     ///
     /// # Example
     ///
@@ -37,81 +52,7 @@ impl MqttSourceConfig {
     /// # ... mqtt settings
     /// ```
     pub fn load() -> ConnectorResult<Self> {
-        let config_path = env::var("CONNECTOR_CONFIG_PATH")
-            .map_err(|_| danube_connect_core::ConnectorError::config(
-                "CONNECTOR_CONFIG_PATH environment variable must be set to the path of the TOML configuration file"
-            ))?;
-        
-        let mut config = Self::from_file(&config_path)?;
-
-        // Apply environment variable overrides for secrets and connection details
-        config.apply_env_overrides();
-
-        Ok(config)
-    }
-
-    /// Load configuration from a TOML file
-    pub fn from_file(path: &str) -> ConnectorResult<Self> {
-        let content = std::fs::read_to_string(path).map_err(|e| {
-            danube_connect_core::ConnectorError::config(format!(
-                "Failed to read config file {}: {}",
-                path, e
-            ))
-        })?;
-
-        toml::from_str(&content).map_err(|e| {
-            danube_connect_core::ConnectorError::config(format!(
-                "Failed to parse config file {}: {}",
-                path, e
-            ))
-        })
-    }
-
-    /// Apply environment variable overrides for secrets and connection details
-    /// 
-    /// Only overrides sensitive data that shouldn't be in config files:
-    /// - Credentials (username, password)
-    /// - Connection URLs (for different environments)
-    /// - Connector name (for different deployments)
-    fn apply_env_overrides(&mut self) {
-        // Override core Danube settings (mandatory fields from danube-connect-core)
-        if let Ok(danube_url) = env::var("DANUBE_SERVICE_URL") {
-            self.core.danube_service_url = danube_url;
-        }
-        
-        if let Ok(connector_name) = env::var("CONNECTOR_NAME") {
-            self.core.connector_name = connector_name;
-        }
-
-        // Override MQTT connection settings
-        if let Ok(host) = env::var("MQTT_BROKER_HOST") {
-            self.mqtt.broker_host = host;
-        }
-        
-        if let Ok(port) = env::var("MQTT_BROKER_PORT") {
-            if let Ok(p) = port.parse() {
-                self.mqtt.broker_port = p;
-            }
-        }
-        
-        if let Ok(client_id) = env::var("MQTT_CLIENT_ID") {
-            self.mqtt.client_id = client_id;
-        }
-
-        // Override credentials (secrets should not be in config files)
-        if let Ok(username) = env::var("MQTT_USERNAME") {
-            self.mqtt.username = Some(username);
-        }
-        
-        if let Ok(password) = env::var("MQTT_PASSWORD") {
-            self.mqtt.password = Some(password);
-        }
-        
-        if let Ok(use_tls) = env::var("MQTT_USE_TLS") {
-            if let Ok(b) = use_tls.parse() {
-                self.mqtt.use_tls = b;
-            }
-        }
+        load_config_with_aliases(DEPRECATED_ENV_VARS)
     }
 
     /// Validate all configuration
@@ -306,6 +247,15 @@ pub struct TopicMapping {
     /// - QoS 1/2 (AtLeastOnce/ExactlyOnce) → reliable (default: true)
     #[serde(default)]
     pub reliable_dispatch: Option<bool>,
+
+    /// JSON Schema file the payloads must match; messages that don't are skipped
+    #[serde(default)]
+    pub json_schema: Option<String>,
+
+    /// Schema type registered for the Danube topic, so sinks can pick it up
+    /// (`Json` topics are registered with `json_schema` as definition)
+    #[serde(default)]
+    pub schema_type: Option<SchemaType>,
 }
 
 impl TopicMapping {
@@ -316,6 +266,19 @@ impl TopicMapping {
             self.qos != QoS::AtMostOnce
         })
     }
+
+    /// Schema registered for the Danube topic, if `schema_type` is set
+    pub fn topic_schema(&self) -> ConnectorResult<Option<TopicSchema>> {
+        self.schema_type
+            .map(|schema_type| {
+                let definition = match schema_type {
+                    SchemaType::Json => self.json_schema.as_deref(),
+                    _ => None,
+                };
+                TopicSchema::load(schema_type, definition)
+            })
+            .transpose()
+    }
 }
 
 fn default_qos() -> QoS {
@@ -344,6 +307,8 @@ mod tests {
                 qos: QoS::AtLeastOnce,
                 partitions: 0,
                 reliable_dispatch: None,
+                json_schema: None,
+                schema_type: None,
             }],
             clean_session: true,
             include_metadata: true,
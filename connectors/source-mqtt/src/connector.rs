@@ -182,7 +182,9 @@ impl MqttSourceConnector {
     ) -> SourceRecord {
         let payload = publish.payload.to_vec();
 
-        let mut record = SourceRecord::new(&mapping.danube_topic, payload);
+        // The packet identifier is the MQTT message's position (0 for QoS 0)
+        let mut record = SourceRecord::new(&mapping.danube_topic, payload)
+            .with_offset(Offset::new(&publish.topic, publish.pkid as u64));
 
         // Add MQTT metadata as attributes
         if include_metadata {
@@ -292,16 +294,20 @@ impl SourceConnector for MqttSourceConnector {
     async fn producer_configs(&self) -> ConnectorResult<Vec<danube_connect_core::ProducerConfig>> {
         // Extract all unique Danube topics from the topic mappings
         // and create producer configurations for each
-        let producer_configs: Vec<_> = self
+        let producer_configs = self
             .config
             .topic_mappings
             .iter()
-            .map(|mapping| danube_connect_core::ProducerConfig {
-                topic: mapping.danube_topic.clone(),
-                partitions: mapping.partitions,
-                reliable_dispatch: mapping.effective_reliable_dispatch(),
+            .map(|mapping| {
+                Ok(danube_connect_core::ProducerConfig {
+                    topic: mapping.danube_topic.clone(),
+                    partitions: mapping.partitions,
+                    reliable_dispatch: mapping.effective_reliable_dispatch(),
+                    json_schema: mapping.json_schema.clone(),
+                    schema: mapping.topic_schema()?,
+                })
             })
-            .collect();
+            .collect::<ConnectorResult<Vec<_>>>()?;
 
         if producer_configs.is_empty() {
             return Err(ConnectorError::config(
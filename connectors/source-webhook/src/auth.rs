@@ -182,13 +182,9 @@ fn verify_jwt(config: &AuthConfig, headers: &HeaderMap) -> Result<(), AuthError>
         .ok_or_else(|| AuthError::Missing("Missing Authorization header".to_string()))?;
 
     // Extract token (format: "Bearer <token>")
-    let token = if auth_header.starts_with("Bearer ") {
-        &auth_header[7..]
-    } else {
-        return Err(AuthError::Invalid(
-            "Invalid Authorization header format".to_string(),
-        ));
-    };
+    let token = auth_header.strip_prefix("Bearer ").ok_or_else(|| {
+        AuthError::Invalid("Invalid Authorization header format".to_string())
+    })?;
 
     // Get the public key or secret
     let secret_env = config.secret_env.as_ref().ok_or_else(|| {
@@ -4,16 +4,28 @@
 //! with environment variable overrides for secrets.
 
 use anyhow::{Context, Result};
+use danube_connect_core::{
+    load_config_file_with_aliases, parse_config_with_aliases, ConnectorConfig, JsonSchema, SchemaType, TopicSchema,
+};
 use serde::{Deserialize, Serialize};
 use std::env;
-use std::fs;
 use std::path::Path;
+use toml::{Table, Value};
+
+/// Former environment variables, still accepted as aliases of the generic overrides
+const DEPRECATED_ENV_VARS: &[(&str, &str)] = &[
+    ("DANUBE_SERVICE_URL", "DANUBE_CONNECT__DANUBE_SERVICE_URL"),
+    ("CONNECTOR_NAME", "DANUBE_CONNECT__CONNECTOR_NAME"),
+    ("SERVER_HOST", "DANUBE_CONNECT__SERVER__HOST"),
+    ("SERVER_PORT", "DANUBE_CONNECT__SERVER__PORT"),
+];
 
 /// Root configuration for the webhook source connector
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WebhookSourceConfig {
-    /// Core Danube connection settings
-    pub core: CoreConfig,
+    /// Core Danube Connect configuration (flattened at root level)
+    #[serde(flatten)]
+    pub core: ConnectorConfig,
     /// HTTP server settings
     pub server: ServerConfig,
     /// Platform-wide authentication (applies to all endpoints)
@@ -25,22 +37,6 @@ pub struct WebhookSourceConfig {
     pub endpoints: Vec<EndpointConfig>,
 }
 
-/// Core Danube connection configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct CoreConfig {
-    /// Danube broker service URL
-    pub danube_service_url: String,
-    /// Unique connector name
-    pub connector_name: String,
-    /// Optional metrics port (default: 9090)
-    #[serde(default = "default_metrics_port")]
-    pub metrics_port: u16,
-}
-
-fn default_metrics_port() -> u16 {
-    9090
-}
-
 /// HTTP server configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ServerConfig {
@@ -62,6 +58,34 @@ pub struct ServerConfig {
     pub max_body_size: usize,
 }
 
+/// Move the keys of the `[core]` table of older configurations to where they are now
+///
+/// `danube_service_url` and `connector_name` moved to the root, `metrics_port` and
+/// `max_in_flight` to `[processing]`. Keys set at their new place (or by an override) win.
+fn migrate_core_table(table: &mut Table, core: Value) -> Result<()> {
+    let Value::Table(core) = core else {
+        anyhow::bail!("core must be a table");
+    };
+    tracing::warn!(
+        "The [core] section is deprecated, move danube_service_url and connector_name to the \
+         top of the file, metrics_port and max_in_flight to [processing]"
+    );
+    for (key, value) in core {
+        let target = match key.as_str() {
+            "metrics_port" | "max_in_flight" => match table
+                .entry("processing")
+                .or_insert_with(|| Value::Table(Table::new()))
+            {
+                Value::Table(processing) => processing,
+                _ => anyhow::bail!("processing must be a table"),
+            },
+            _ => &mut *table,
+        };
+        target.entry(key).or_insert(value);
+    }
+    Ok(())
+}
+
 fn default_host() -> String {
     "0.0.0.0".to_string()
 }
@@ -137,54 +161,73 @@ pub struct EndpointConfig {
     pub reliable_dispatch: bool,
     /// Optional per-endpoint rate limiting (overrides platform-wide)
     pub rate_limit: Option<RateLimitConfig>,
+    /// Optional JSON Schema file the payloads must match (others are rejected with 400)
+    #[serde(default)]
+    pub json_schema: Option<String>,
+    /// Schema loaded from `json_schema`
+    #[serde(skip)]
+    pub schema: Option<JsonSchema>,
+    /// Optional schema type registered for the topic (`Json` registers `json_schema`)
+    #[serde(default)]
+    pub schema_type: Option<SchemaType>,
+    /// Schema registered for the topic, loaded from `schema_type` and `json_schema`
+    #[serde(skip)]
+    pub topic_schema: Option<TopicSchema>,
 }
 
 impl WebhookSourceConfig {
     /// Load configuration from a TOML file
+    ///
+    /// Supports `${VAR}` interpolation, `DANUBE_CONNECT__SECTION__KEY` overrides and
+    /// `*_secret_file` secrets (see `danube_connect_core::load_config`). The former
+    /// variables such as `SERVER_PORT` are deprecated aliases of the overrides.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let content = fs::read_to_string(path.as_ref())
-            .with_context(|| format!("Failed to read config file: {:?}", path.as_ref()))?;
-
-        let mut config: WebhookSourceConfig = toml::from_str(&content)
-            .with_context(|| "Failed to parse TOML configuration")?;
+        let table = load_config_file_with_aliases(path.as_ref(), DEPRECATED_ENV_VARS)
+            .with_context(|| "Failed to load configuration file")?;
+        Self::prepare(table)
+    }
 
-        // Apply environment variable overrides
-        config.apply_env_overrides()?;
+    /// Load configuration from TOML content, e.g. a reloaded config file
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let table = parse_config_with_aliases(content, DEPRECATED_ENV_VARS)
+            .with_context(|| "Failed to parse configuration")?;
+        Self::prepare(table)
+    }
 
-        // Validate configuration
+    /// Move the settings of a deprecated `[core]` table to the root, validate and load the
+    /// endpoint schemas
+    fn prepare(mut table: Table) -> Result<Self> {
+        if let Some(core) = table.remove("core") {
+            migrate_core_table(&mut table, core)?;
+        }
+        let mut config: Self = Value::Table(table)
+            .try_into()
+            .with_context(|| "Invalid configuration")?;
         config.validate()?;
-
+        config.load_schemas()?;
         Ok(config)
     }
 
-    /// Apply environment variable overrides
-    fn apply_env_overrides(&mut self) -> Result<()> {
-        // Override Danube service URL
-        if let Ok(url) = env::var("DANUBE_SERVICE_URL") {
-            tracing::info!("Overriding danube_service_url from environment");
-            self.core.danube_service_url = url;
-        }
-
-        // Override connector name
-        if let Ok(name) = env::var("CONNECTOR_NAME") {
-            tracing::info!("Overriding connector_name from environment");
-            self.core.connector_name = name;
-        }
-
-        // Override server host
-        if let Ok(host) = env::var("SERVER_HOST") {
-            tracing::info!("Overriding server host from environment");
-            self.server.host = host;
-        }
-
-        // Override server port
-        if let Ok(port) = env::var("SERVER_PORT") {
-            let port: u16 = port.parse()
-                .with_context(|| "Invalid SERVER_PORT value")?;
-            tracing::info!("Overriding server port from environment: {}", port);
-            self.server.port = port;
+    /// Load the JSON schemas and registered topic schemas of the endpoints
+    fn load_schemas(&mut self) -> Result<()> {
+        for endpoint in &mut self.endpoints {
+            if let Some(path) = &endpoint.json_schema {
+                let schema = JsonSchema::load(path).with_context(|| {
+                    format!("Invalid json_schema for endpoint: {}", endpoint.path)
+                })?;
+                endpoint.schema = Some(schema);
+            }
+            if let Some(schema_type) = endpoint.schema_type {
+                let definition = match schema_type {
+                    SchemaType::Json => endpoint.json_schema.as_deref(),
+                    _ => None,
+                };
+                let schema = TopicSchema::load(schema_type, definition).with_context(|| {
+                    format!("Invalid schema for endpoint: {}", endpoint.path)
+                })?;
+                endpoint.topic_schema = Some(schema);
+            }
         }
-
         Ok(())
     }
 
@@ -293,13 +336,6 @@ mod tests {
 
     #[test]
     fn test_default_values() {
-        let config = CoreConfig {
-            danube_service_url: "http://localhost:6650".to_string(),
-            connector_name: "test".to_string(),
-            metrics_port: default_metrics_port(),
-        };
-        assert_eq!(config.metrics_port, 9090);
-
         let server = ServerConfig {
             host: default_host(),
             port: default_port(),
@@ -314,6 +350,146 @@ mod tests {
         assert_eq!(server.max_body_size, 1024 * 1024);
     }
 
+    #[test]
+    fn test_core_settings_at_root() {
+        let config: WebhookSourceConfig = danube_connect_core::parse_config(
+            r#"
+            danube_service_url = "http://localhost:6650"
+            connector_name = "webhook-test"
+
+            [processing]
+            metrics_port = 9191
+
+            [server]
+
+            [auth]
+            type = "none"
+
+            [[endpoints]]
+            path = "/webhooks/test"
+            danube_topic = "/default/test"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.core.connector_name, "webhook-test");
+        assert_eq!(config.core.processing.metrics_port, 9191);
+        assert_eq!(config.core.processing.max_in_flight, 1);
+    }
+
+    #[test]
+    fn test_deprecated_core_table() {
+        let config = WebhookSourceConfig::from_toml(
+            r#"
+            [core]
+            danube_service_url = "http://localhost:6650"
+            connector_name = "webhook-legacy"
+            metrics_port = 9191
+
+            [server]
+
+            [auth]
+            type = "none"
+
+            [[endpoints]]
+            path = "/webhooks/test"
+            danube_topic = "/default/test"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.core.danube_service_url, "http://localhost:6650");
+        assert_eq!(config.core.connector_name, "webhook-legacy");
+        assert_eq!(config.core.processing.metrics_port, 9191);
+
+        // Settings at their new place win over the [core] table
+        let config = WebhookSourceConfig::from_toml(
+            r#"
+            connector_name = "webhook-new"
+
+            [core]
+            danube_service_url = "http://localhost:6650"
+            connector_name = "webhook-legacy"
+
+            [server]
+
+            [auth]
+            type = "none"
+
+            [[endpoints]]
+            path = "/webhooks/test"
+            danube_topic = "/default/test"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.core.connector_name, "webhook-new");
+    }
+
+    #[test]
+    fn test_endpoint_json_schema() {
+        let schema_path = env::temp_dir().join("webhook-test-payment.schema.json");
+        std::fs::write(
+            &schema_path,
+            r#"{"type": "object", "required": ["id"], "properties": {"id": {"type": "string"}}}"#,
+        )
+        .unwrap();
+
+        let config = WebhookSourceConfig::from_toml(&format!(
+            r#"
+            danube_service_url = "http://localhost:6650"
+            connector_name = "webhook-test"
+
+            [server]
+
+            [auth]
+            type = "none"
+
+            [[endpoints]]
+            path = "/webhooks/payments"
+            danube_topic = "/default/payments"
+            json_schema = "{}"
+
+            schema_type = "Json"
+
+            [[endpoints]]
+            path = "/webhooks/other"
+            danube_topic = "/default/other"
+            "#,
+            schema_path.display()
+        ))
+        .unwrap();
+        std::fs::remove_file(&schema_path).unwrap();
+
+        let schema = config.endpoints[0].schema.as_ref().unwrap();
+        assert!(schema.validate(br#"{"id": "p-1"}"#).is_ok());
+        let err = schema.validate(br#"{"id": 1}"#).unwrap_err();
+        assert!(err.to_string().contains("/id: 1 is not of type \"string\""));
+        assert!(config.endpoints[1].schema.is_none());
+
+        // The JSON schema is registered as the definition of the topic's Json schema
+        let topic_schema = config.endpoints[0].topic_schema.as_ref().unwrap();
+        assert_eq!(topic_schema.schema_type, SchemaType::Json);
+        assert!(topic_schema.definition.as_ref().unwrap().contains("\"required\""));
+        assert!(config.endpoints[1].topic_schema.is_none());
+
+        // A missing schema file fails the configuration
+        assert!(WebhookSourceConfig::from_toml(
+            r#"
+            danube_service_url = "http://localhost:6650"
+            connector_name = "webhook-test"
+
+            [server]
+
+            [auth]
+            type = "none"
+
+            [[endpoints]]
+            path = "/webhooks/payments"
+            danube_topic = "/default/payments"
+            json_schema = "/nonexistent/payment.schema.json"
+            "#,
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_auth_type_deserialization() {
         let json = r#"{"type": "none"}"#;
@@ -4,13 +4,13 @@ use async_trait::async_trait;
 use chrono::Utc;
 use danube_connect_core::{
     ConnectorConfig, ConnectorError, ConnectorResult, Offset, ProducerConfig, SourceConnector,
-    SourceRecord,
+    SourceRecord, TopicSchema,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio::sync::RwLock;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::config::{EndpointConfig, WebhookSourceConfig};
 
@@ -102,6 +102,10 @@ impl WebhookConnector {
             topic: endpoint_config.danube_topic.clone(),
             partitions: endpoint_config.partitions as usize,
             reliable_dispatch: endpoint_config.reliable_dispatch,
+            // Payloads are validated by the HTTP handler, schemas registered through
+            // producer_configs()
+            json_schema: None,
+            schema: None,
         });
 
         record
@@ -163,7 +167,7 @@ impl SourceConnector for WebhookConnector {
     async fn producer_configs(&self) -> ConnectorResult<Vec<ProducerConfig>> {
         // Extract all unique Danube topics from endpoints
         // Use HashMap to deduplicate topics (multiple endpoints can use same topic)
-        let mut topics: HashMap<String, (usize, bool)> = HashMap::new();
+        let mut topics: HashMap<String, (usize, bool, Option<TopicSchema>)> = HashMap::new();
 
         for endpoint in &self.config.endpoints {
             // Use partitions from config directly
@@ -176,17 +180,21 @@ impl SourceConnector for WebhookConnector {
 
             topics.insert(
                 endpoint.danube_topic.clone(),
-                (partitions, reliable_dispatch),
+                (partitions, reliable_dispatch, endpoint.topic_schema.clone()),
             );
         }
 
         let producer_configs: Vec<_> = topics
             .into_iter()
-            .map(|(topic, (partitions, reliable_dispatch))| ProducerConfig {
-                topic,
-                partitions,
-                reliable_dispatch,
-            })
+            .map(
+                |(topic, (partitions, reliable_dispatch, schema))| ProducerConfig {
+                    topic,
+                    partitions,
+                    reliable_dispatch,
+                    json_schema: None,
+                    schema,
+                },
+            )
             .collect();
 
         if producer_configs.is_empty() {
@@ -229,6 +237,34 @@ impl SourceConnector for WebhookConnector {
         Ok(records)
     }
 
+    async fn reconfigure(&mut self, config: &str) -> ConnectorResult<()> {
+        let config = WebhookSourceConfig::from_toml(config).map_err(|e| {
+            ConnectorError::config(format!("Configuration validation failed: {:#}", e))
+        })?;
+
+        // The running server keeps its address, authentication and rate limiters
+        if config.bind_address() != self.config.bind_address() {
+            warn!("Server address changes apply after a restart, reloading endpoints only");
+        }
+
+        let endpoints = config
+            .endpoints
+            .iter()
+            .map(|endpoint| (endpoint.path.clone(), endpoint.clone()))
+            .collect();
+        *self.endpoints.write().await = endpoints;
+        self.config.endpoints = config.endpoints;
+
+        for endpoint in &self.config.endpoints {
+            info!(
+                "Endpoint: {} -> {} (Partitions: {})",
+                endpoint.path, endpoint.danube_topic, endpoint.partitions
+            );
+        }
+        info!("Reloaded {} webhook endpoint(s)", self.config.endpoints.len());
+        Ok(())
+    }
+
     async fn commit(&mut self, _offsets: Vec<Offset>) -> ConnectorResult<()> {
         // Webhooks don't require offset commits
         // Messages are acknowledged via HTTP response
@@ -10,7 +10,7 @@ mod rate_limit;
 mod server;
 
 use anyhow::{Context, Result};
-use danube_connect_core::{ConnectorConfig, SourceRuntime};
+use danube_connect_core::{SourceRuntime, CONFIG_PATH_ENV};
 use std::env;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -25,7 +25,7 @@ async fn main() -> Result<()> {
     tracing::info!("Starting Danube HTTP/Webhook Source Connector");
 
     // Load configuration
-    let config_path = env::var("CONNECTOR_CONFIG_PATH")
+    let config_path = env::var(CONFIG_PATH_ENV)
         .unwrap_or_else(|_| "config/connector.toml".to_string());
 
     tracing::info!("Loading configuration from: {}", config_path);
@@ -58,19 +58,17 @@ async fn main() -> Result<()> {
         "Authentication configured"
     );
 
-    // Create core connector config for runtime
-    let core_config = ConnectorConfig {
-        connector_name: webhook_config.core.connector_name.clone(),
-        danube_service_url: webhook_config.core.danube_service_url.clone(),
-        ..Default::default()
-    };
+    // Core connector config for runtime
+    let core_config = webhook_config.core.clone();
 
     // Create webhook connector
     let connector = WebhookConnector::with_config(webhook_config);
 
     // Create and run the runtime (this will call initialize which starts the HTTP server)
     tracing::info!("Starting Danube runtime");
-    let mut runtime = SourceRuntime::new(connector, core_config).await?;
+    let mut runtime = SourceRuntime::new(connector, core_config)
+        .await?
+        .with_config_reload(&config_path);
 
     // Run runtime (this blocks until shutdown)
     runtime.run().await?;
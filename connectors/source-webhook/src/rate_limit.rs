@@ -25,12 +25,15 @@ use tokio::sync::RwLock;
 use crate::config::RateLimitConfig;
 use crate::server::AppState;
 
+/// Unkeyed in-memory rate limiter
+type DirectLimiter = GovernorRateLimiter<NotKeyed, InMemoryState, DefaultClock>;
+
 /// Rate limiter state
 pub struct RateLimiterState {
     /// Per-endpoint rate limiters
-    endpoint_limiters: Arc<RwLock<HashMap<String, Arc<GovernorRateLimiter<NotKeyed, InMemoryState, DefaultClock>>>>>,
+    endpoint_limiters: Arc<RwLock<HashMap<String, Arc<DirectLimiter>>>>,
     /// Per-IP rate limiters (if enabled)
-    ip_limiters: Arc<RwLock<HashMap<IpAddr, Arc<GovernorRateLimiter<NotKeyed, InMemoryState, DefaultClock>>>>>,
+    ip_limiters: Arc<RwLock<HashMap<IpAddr, Arc<DirectLimiter>>>>,
 }
 
 impl RateLimiterState {
@@ -47,7 +50,7 @@ impl RateLimiterState {
         &self,
         endpoint: &str,
         config: &RateLimitConfig,
-    ) -> Arc<GovernorRateLimiter<NotKeyed, InMemoryState, DefaultClock>> {
+    ) -> Arc<DirectLimiter> {
         let mut limiters = self.endpoint_limiters.write().await;
 
         limiters
@@ -68,7 +71,7 @@ impl RateLimiterState {
         &self,
         ip: IpAddr,
         config: &RateLimitConfig,
-    ) -> Arc<GovernorRateLimiter<NotKeyed, InMemoryState, DefaultClock>> {
+    ) -> Arc<DirectLimiter> {
         let mut limiters = self.ip_limiters.write().await;
 
         limiters
@@ -232,9 +235,7 @@ pub enum RateLimitError {
 
 impl IntoResponse for RateLimitError {
     fn into_response(self) -> Response {
-        let message = match self {
-            RateLimitError::Exceeded(msg) => msg,
-        };
+        let RateLimitError::Exceeded(message) = self;
 
         tracing::warn!(error = %message, "Rate limit exceeded");
 
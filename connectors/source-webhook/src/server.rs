@@ -134,6 +134,18 @@ async fn webhook_handler(
         )));
     }
 
+    // Reject payloads not matching the endpoint's JSON schema before they reach the topic
+    if let Some(schema) = &endpoint_config.schema {
+        schema.validate(&body).map_err(|e| {
+            tracing::warn!(
+                endpoint = %endpoint_path,
+                error = %e,
+                "Rejected payload not matching the JSON schema"
+            );
+            AppError::BadRequest(e.to_string())
+        })?;
+    }
+
     // Create SourceRecord from webhook data
     let source_record = WebhookConnector::create_source_record(
         &endpoint_config,
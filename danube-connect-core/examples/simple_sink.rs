@@ -15,8 +15,8 @@
 
 use async_trait::async_trait;
 use danube_connect_core::{
-    ConnectorConfig, ConnectorResult, ConsumerConfig, ProcessingSettings, RetrySettings,
-    SinkConnector, SinkRecord, SinkRuntime, SubscriptionType,
+    CircuitBreakerSettings, ConnectorConfig, ConnectorResult, ConsumerConfig, OffsetStoreSettings,
+    ProcessingSettings, RetrySettings, SinkConnector, SinkRecord, SinkRuntime, SubscriptionType,
 };
 
 /// A simple sink connector that prints messages
@@ -45,6 +45,7 @@ impl SinkConnector for SimpleSinkConnector {
             consumer_name: "simple-sink-consumer".to_string(),
             subscription: "simple-sink-sub".to_string(),
             subscription_type: SubscriptionType::Exclusive,
+            ..Default::default()
         }])
     }
 
@@ -101,7 +102,10 @@ async fn main() -> ConnectorResult<()> {
             danube_service_url: "http://localhost:6650".to_string(),
             connector_name: "simple-sink".to_string(),
             retry: RetrySettings::default(),
+            circuit_breaker: CircuitBreakerSettings::default(),
             processing: ProcessingSettings::default(),
+            offset_store: OffsetStoreSettings::default(),
+            transforms: Vec::new(),
         }
     });
 
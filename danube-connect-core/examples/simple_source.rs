@@ -13,8 +13,9 @@
 
 use async_trait::async_trait;
 use danube_connect_core::{
-    ConnectorConfig, ConnectorResult, ProcessingSettings, ProducerConfig, RetrySettings,
-    SourceConnector, SourceRecord, SourceRuntime,
+    CircuitBreakerSettings, ConnectorConfig, ConnectorResult, OffsetStoreSettings,
+    ProcessingSettings, ProducerConfig, RetrySettings, SourceConnector, SourceRecord,
+    SourceRuntime,
 };
 use std::time::Duration;
 
@@ -48,6 +49,7 @@ impl SourceConnector for SimpleSourceConnector {
             topic: "/default/test".to_string(),
             partitions: 0,           // Non-partitioned for simple example
             reliable_dispatch: true, // Reliable for testing
+            ..Default::default()
         }])
     }
 
@@ -112,7 +114,10 @@ async fn main() -> ConnectorResult<()> {
             danube_service_url: "http://localhost:6650".to_string(),
             connector_name: "simple-source".to_string(),
             retry: RetrySettings::default(),
+            circuit_breaker: CircuitBreakerSettings::default(),
             processing: ProcessingSettings::default(),
+            offset_store: OffsetStoreSettings::default(),
+            transforms: Vec::new(),
         }
     });
 
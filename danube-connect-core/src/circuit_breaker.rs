@@ -0,0 +1,260 @@
+//! Circuit breaker for calls to external systems.
+//!
+//! The runtimes record the outcome of every call to the connector. Once the share of
+//! retryable failures among the recent calls crosses a threshold the circuit opens and
+//! processing pauses, instead of exhausting the retries of every record while the
+//! external system is down. After `open_duration` a single probe call is let through
+//! (half-open): its success closes the circuit, its failure opens it again.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Configuration for circuit breaker behavior
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Share of failed calls (0.0 - 1.0) in the window that opens the circuit
+    pub failure_rate_threshold: f64,
+    /// Minimum number of calls in the window before the failure rate is evaluated
+    pub minimum_calls: usize,
+    /// Number of recent calls the failure rate is computed over
+    pub window_size: usize,
+    /// How long the circuit stays open before a probe call is let through
+    pub open_duration: Duration,
+}
+
+impl CircuitBreakerConfig {
+    /// Create a new circuit breaker configuration
+    pub fn new(
+        failure_rate_threshold: f64,
+        minimum_calls: usize,
+        window_size: usize,
+        open_duration_ms: u64,
+    ) -> Self {
+        Self {
+            failure_rate_threshold,
+            minimum_calls,
+            window_size,
+            open_duration: Duration::from_millis(open_duration_ms),
+        }
+    }
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self::new(0.5, 10, 20, 30000)
+    }
+}
+
+/// State of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls pass, outcomes are tracked
+    Closed,
+    /// Calls are paused until the open duration elapsed
+    Open,
+    /// A single probe call decides whether the circuit closes again
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    /// Outcomes of the recent calls while closed (`true` = failure)
+    outcomes: VecDeque<bool>,
+    opened_at: Instant,
+    /// Start of the probe call while half-open
+    probe_started: Option<Instant>,
+}
+
+/// Circuit breaker shared by everything calling the same external system
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    /// Create a new, closed circuit breaker
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                outcomes: VecDeque::with_capacity(config.window_size),
+                opened_at: Instant::now(),
+                probe_started: None,
+            }),
+            config,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Current state of the circuit
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Ask permission for a call
+    ///
+    /// Returns `Err` with the time to wait before asking again while the circuit is open
+    /// or another probe call is in flight.
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => {
+                let elapsed = inner.opened_at.elapsed();
+                if elapsed < self.config.open_duration {
+                    return Err(self.config.open_duration - elapsed);
+                }
+                info!("Circuit breaker half-open, probing the external system");
+                inner.state = CircuitState::HalfOpen;
+                inner.probe_started = Some(Instant::now());
+                Ok(())
+            }
+            CircuitState::HalfOpen => match inner.probe_started {
+                // A probe abandoned without outcome is replaced after the open duration
+                Some(started) if started.elapsed() < self.config.open_duration => {
+                    Err(self.config.open_duration - started.elapsed())
+                }
+                _ => {
+                    inner.probe_started = Some(Instant::now());
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    /// Wait until a call is permitted
+    pub async fn acquire(&self) {
+        while let Err(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Record a call that reached the external system
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::HalfOpen => {
+                info!("Circuit breaker closed, the external system recovered");
+                inner.state = CircuitState::Closed;
+                inner.probe_started = None;
+            }
+            _ => self.push_outcome(&mut inner, false),
+        }
+    }
+
+    /// Record a call that failed with a retryable error
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::HalfOpen => {
+                warn!("Circuit breaker probe failed, pausing calls again");
+                self.open(&mut inner);
+            }
+            _ => {
+                self.push_outcome(&mut inner, true);
+
+                let calls = inner.outcomes.len();
+                let failures = inner.outcomes.iter().filter(|&&failed| failed).count();
+                if calls >= self.config.minimum_calls
+                    && failures as f64 / calls as f64 >= self.config.failure_rate_threshold
+                {
+                    warn!(
+                        "Circuit breaker open after {} of the last {} calls failed, pausing calls for {:?}",
+                        failures, calls, self.config.open_duration
+                    );
+                    self.open(&mut inner);
+                }
+            }
+        }
+    }
+
+    fn push_outcome(&self, inner: &mut Inner, failed: bool) {
+        if inner.outcomes.len() >= self.config.window_size {
+            inner.outcomes.pop_front();
+        }
+        inner.outcomes.push_back(failed);
+    }
+
+    fn open(&self, inner: &mut Inner) {
+        inner.state = CircuitState::Open;
+        inner.opened_at = Instant::now();
+        inner.probe_started = None;
+        inner.outcomes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(open_duration_ms: u64) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig::new(0.5, 4, 10, open_duration_ms))
+    }
+
+    #[test]
+    fn test_opens_on_failure_rate() {
+        let breaker = breaker(60_000);
+
+        // Below the minimum number of calls the circuit stays closed
+        breaker.record_failure();
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.try_acquire().is_ok());
+
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.try_acquire().is_err());
+    }
+
+    #[test]
+    fn test_stays_closed_below_threshold() {
+        let breaker = breaker(60_000);
+        for _ in 0..10 {
+            breaker.record_success();
+            breaker.record_success();
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_probe() {
+        let breaker = breaker(0);
+        for _ in 0..4 {
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // One probe at a time
+        assert!(breaker.try_acquire().is_ok());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        // A failed probe opens the circuit again, a successful one closes it
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.try_acquire().is_ok());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_single_probe_in_flight() {
+        let breaker = breaker(20);
+        for _ in 0..4 {
+            breaker.record_failure();
+        }
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert!(breaker.try_acquire().is_ok());
+        assert!(breaker.try_acquire().is_err());
+    }
+}
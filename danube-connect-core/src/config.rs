@@ -3,13 +3,15 @@
 use crate::{ConnectorError, ConnectorResult};
 use danube_client::SubType;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 
 /// Main configuration for connectors
 ///
 /// # Structure
 /// - **Mandatory fields** (from environment): `danube_service_url`, `connector_name`
-/// - **Optional fields** (from config file or defaults): `retry`, `processing`
+/// - **Optional fields** (from config file or defaults): `retry`, `circuit_breaker`,
+///   `processing`, `offset_store`, `transforms`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectorConfig {
     /// Danube broker service URL (mandatory, from DANUBE_SERVICE_URL env var)
@@ -22,9 +24,21 @@ pub struct ConnectorConfig {
     #[serde(default)]
     pub retry: RetrySettings,
 
+    /// Circuit breaker settings (optional, disabled by default)
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerSettings,
+
     /// Processing and runtime settings (optional, from config file or defaults)
     #[serde(default)]
     pub processing: ProcessingSettings,
+
+    /// Where source connectors persist committed offsets (optional, default: none)
+    #[serde(default)]
+    pub offset_store: OffsetStoreSettings,
+
+    /// Single-message transforms applied to every record, in order (optional, default: none)
+    #[serde(default)]
+    pub transforms: Vec<TransformSettings>,
 }
 
 impl ConnectorConfig {
@@ -47,19 +61,19 @@ impl ConnectorConfig {
             danube_service_url,
             connector_name,
             retry: RetrySettings::default(),
+            circuit_breaker: CircuitBreakerSettings::default(),
             processing: ProcessingSettings::default(),
+            offset_store: OffsetStoreSettings::default(),
+            transforms: Vec::new(),
         })
     }
 
     /// Load configuration from a TOML file
+    ///
+    /// Supports `${VAR}` interpolation, `DANUBE_CONNECT__*` overrides and
+    /// `*_secret_file` secrets, see `load_config_file()`.
     pub fn from_file(path: &str) -> ConnectorResult<Self> {
-        let content = std::fs::read_to_string(path).map_err(|e| {
-            ConnectorError::config(format!("Failed to read config file {}: {}", path, e))
-        })?;
-
-        toml::from_str(&content).map_err(|e| {
-            ConnectorError::config(format!("Failed to parse config file {}: {}", path, e))
-        })
+        crate::load_config_file(path)
     }
 
     /// Apply environment variable overrides to mandatory fields only
@@ -89,10 +103,39 @@ impl ConnectorConfig {
             return Err(ConnectorError::config("max_retries too high (max 100)"));
         }
 
+        let breaker = &self.circuit_breaker;
+        if breaker.enabled
+            && !(breaker.failure_rate_threshold > 0.0 && breaker.failure_rate_threshold <= 1.0)
+        {
+            return Err(ConnectorError::config(
+                "circuit_breaker.failure_rate_threshold must be in (0, 1]",
+            ));
+        }
+
         if self.processing.batch_size == 0 {
             return Err(ConnectorError::config("batch_size must be > 0"));
         }
 
+        if self.processing.parallelism == 0 {
+            return Err(ConnectorError::config("parallelism must be > 0"));
+        }
+
+        if self.processing.max_in_flight == 0 {
+            return Err(ConnectorError::config("max_in_flight must be > 0"));
+        }
+
+        if self.processing.health_check_interval_ms == 0 {
+            return Err(ConnectorError::config(
+                "health_check_interval_ms must be > 0",
+            ));
+        }
+
+        if self.processing.topic_discovery_interval_ms == 0 {
+            return Err(ConnectorError::config(
+                "topic_discovery_interval_ms must be > 0",
+            ));
+        }
+
         Ok(())
     }
 }
@@ -103,7 +146,10 @@ impl Default for ConnectorConfig {
             danube_service_url: "http://localhost:6650".to_string(),
             connector_name: "default-connector".to_string(),
             retry: RetrySettings::default(),
+            circuit_breaker: CircuitBreakerSettings::default(),
             processing: ProcessingSettings::default(),
+            offset_store: OffsetStoreSettings::default(),
+            transforms: Vec::new(),
         }
     }
 }
@@ -122,6 +168,36 @@ pub struct RetrySettings {
     /// Maximum backoff duration in milliseconds
     #[serde(default = "default_max_backoff_ms")]
     pub max_backoff_ms: u64,
+
+    /// What sinks do with records that still fail after the retries
+    ///
+    /// Consumers can override it with `ConsumerConfig::failure_policy`.
+    #[serde(default)]
+    pub failure_policy: FailurePolicy,
+}
+
+/// What a sink does with a record that still fails after the retries
+///
+/// Invalid records are never retried: they go to the dead-letter topic if there is one
+/// and are skipped otherwise, whatever the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Acknowledge the record and continue
+    Skip,
+    /// Republish the record to the dead-letter topic and acknowledge it (default)
+    ///
+    /// Without a dead-letter topic the runtime stops, leaving the record unacknowledged so
+    /// it is redelivered after a restart.
+    #[default]
+    DeadLetter,
+    /// Retry retryable failures until they succeed, with backoff capped at `max_backoff_ms`
+    ///
+    /// Records failing with a fatal error cannot be retried: they go to the dead-letter
+    /// topic if there is one and stop the runtime otherwise.
+    RetryForever,
+    /// Stop the runtime with a fatal error, leaving the record unacknowledged
+    Stop,
 }
 
 fn default_max_retries() -> u32 {
@@ -140,6 +216,59 @@ impl Default for RetrySettings {
             max_retries: 3,
             retry_backoff_ms: 1000,
             max_backoff_ms: 30000,
+            failure_policy: FailurePolicy::default(),
+        }
+    }
+}
+
+/// Circuit breaker configuration settings
+///
+/// While open, sinks stop processing (and thereby consuming) messages and sources stop
+/// polling, instead of retrying every record against an external system that is down.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerSettings {
+    /// Whether calls to the connector go through a circuit breaker
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Share of retryable failures (0.0 - 1.0) among recent calls that opens the circuit
+    #[serde(default = "default_failure_rate_threshold")]
+    pub failure_rate_threshold: f64,
+
+    /// Minimum number of recent calls before the failure rate is evaluated
+    #[serde(default = "default_minimum_calls")]
+    pub minimum_calls: usize,
+
+    /// Number of recent calls the failure rate is computed over
+    #[serde(default = "default_window_size")]
+    pub window_size: usize,
+
+    /// Time in milliseconds the circuit stays open before a probe call is let through
+    #[serde(default = "default_open_duration_ms")]
+    pub open_duration_ms: u64,
+}
+
+fn default_failure_rate_threshold() -> f64 {
+    0.5
+}
+fn default_minimum_calls() -> usize {
+    10
+}
+fn default_window_size() -> usize {
+    20
+}
+fn default_open_duration_ms() -> u64 {
+    30000
+}
+
+impl Default for CircuitBreakerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            failure_rate_threshold: 0.5,
+            minimum_calls: 10,
+            window_size: 20,
+            open_duration_ms: 30000,
         }
     }
 }
@@ -155,17 +284,94 @@ pub struct ProcessingSettings {
     #[serde(default = "default_batch_timeout_ms")]
     pub batch_timeout_ms: u64,
 
+    /// Number of sink workers processing consumers concurrently
+    ///
+    /// Each worker needs its own connector instance from `SinkConnector::fork()`.
+    #[serde(default = "default_parallelism")]
+    pub parallelism: usize,
+
+    /// Ordering guarantee of sink processing, deciding how messages are spread over workers
+    #[serde(default)]
+    pub ordering: OrderingMode,
+
+    /// Message attribute used as routing key with `OrderingMode::Key`
+    ///
+    /// Messages are routed by producer name when not set or when a message lacks the
+    /// attribute.
+    #[serde(default)]
+    pub ordering_key: Option<String>,
+
     /// Poll interval in milliseconds for source connectors
     #[serde(default = "default_poll_interval_ms")]
     pub poll_interval_ms: u64,
 
-    /// Metrics export port
+    /// Topic patterns source connectors may publish to without declaring them in
+    /// `producer_configs()` (e.g. `/iot/*`, `/events/**`)
+    ///
+    /// Producers for such topics are created on first use from the record's
+    /// `producer_config`. An empty list allows any topic. Records for other topics are
+    /// skipped, the rest of their batch is still published.
+    #[serde(default)]
+    pub allowed_topics: Vec<String>,
+
+    /// Maximum number of unacknowledged sends per source topic
+    ///
+    /// Sends of a batch to different partitions of a topic overlap up to this window, while
+    /// offsets are still committed in record order. The client sends one message per
+    /// partition at a time, so a window above 1 gains nothing for non-partitioned topics;
+    /// the runtime warns when it creates the producer of such a topic.
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+
+    /// Metrics export port, also serving the `/health` and `/ready` probes
     #[serde(default = "default_metrics_port")]
     pub metrics_port: u16,
 
+    /// Interval in milliseconds between calls of the connector's `health_check()`
+    #[serde(default = "default_health_check_interval_ms")]
+    pub health_check_interval_ms: u64,
+
+    /// Failed health checks in a row after which the connector is reported unhealthy
+    #[serde(default = "default_health_failure_threshold")]
+    pub health_failure_threshold: usize,
+
     /// Log level
     #[serde(default = "default_log_level")]
     pub log_level: String,
+
+    /// Time in milliseconds the runtime may spend draining after SIGTERM/SIGINT
+    ///
+    /// Sinks deliver their buffered batches, shut down the connector and acknowledge what
+    /// was written; sources stop polling and finish in-flight publishes. The runtime gives
+    /// up and exits with an error once the timeout elapses.
+    #[serde(default = "default_shutdown_timeout_ms")]
+    pub shutdown_timeout_ms: u64,
+
+    /// Default dead-letter topic for sink consumers without their own
+    ///
+    /// Invalid and retry-exhausted records are republished to this Danube topic. Sources
+    /// republish the records failing the JSON schema of their topic to it.
+    #[serde(default)]
+    pub dead_letter_topic: Option<String>,
+
+    /// Replay mode for sink consumers: skip records published before this time
+    ///
+    /// Milliseconds since the Unix epoch, compared with the publish time of the records.
+    /// Skipped records are acknowledged. Applies to all consumers in addition to their
+    /// `ConsumerConfig::start_position`.
+    #[serde(default)]
+    pub replay_from_ms: Option<u64>,
+
+    /// Danube admin API URL (e.g. `http://localhost:50051`)
+    ///
+    /// Required by sink consumers with a topic pattern, whose topics are discovered through
+    /// the admin API.
+    #[serde(default)]
+    pub admin_service_url: Option<String>,
+
+    /// Interval in milliseconds between discoveries of the topics matching topic patterns
+    #[serde(default = "default_topic_discovery_interval_ms")]
+    pub topic_discovery_interval_ms: u64,
 }
 
 fn default_batch_size() -> usize {
@@ -174,31 +380,207 @@ fn default_batch_size() -> usize {
 fn default_batch_timeout_ms() -> u64 {
     1000
 }
+fn default_parallelism() -> usize {
+    1
+}
+fn default_max_in_flight() -> usize {
+    1
+}
 fn default_poll_interval_ms() -> u64 {
     100
 }
 fn default_metrics_port() -> u16 {
     9090
 }
+fn default_health_check_interval_ms() -> u64 {
+    10000
+}
+fn default_health_failure_threshold() -> usize {
+    3
+}
 fn default_log_level() -> String {
     "info".to_string()
 }
+fn default_shutdown_timeout_ms() -> u64 {
+    30000
+}
+fn default_topic_discovery_interval_ms() -> u64 {
+    30000
+}
 
 impl Default for ProcessingSettings {
     fn default() -> Self {
         Self {
             batch_size: 1000,
             batch_timeout_ms: 1000,
+            parallelism: 1,
+            ordering: OrderingMode::default(),
+            ordering_key: None,
             poll_interval_ms: 100,
+            allowed_topics: Vec::new(),
+            max_in_flight: 1,
             metrics_port: 9090,
+            health_check_interval_ms: 10000,
+            health_failure_threshold: 3,
             log_level: "info".to_string(),
+            shutdown_timeout_ms: 30000,
+            dead_letter_topic: None,
+            replay_from_ms: None,
+            admin_service_url: None,
+            topic_discovery_interval_ms: 30000,
+        }
+    }
+}
+
+/// Offset store settings for source connectors
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OffsetStoreSettings {
+    /// Offsets are not persisted by the runtime
+    #[default]
+    None,
+    /// Offsets are stored in a local JSON file
+    File {
+        /// Path of the offsets file
+        path: String,
+    },
+    /// Offsets are stored on a Danube topic
+    Topic {
+        /// Danube topic holding the offset snapshots (format: /{namespace}/{topic_name})
+        topic: String,
+    },
+}
+
+/// A single-message transform, configured as a `[[transforms]]` table
+///
+/// Field paths address the JSON payload with dots (`user.email`). Transforms changing the
+/// payload fail with invalid data for payloads that are not JSON.
+///
+/// ```toml
+/// [[transforms]]
+/// type = "filter"
+/// attribute = "env"
+/// equals = "prod"
+///
+/// [[transforms]]
+/// type = "mask_fields"
+/// fields = ["user.email"]
+/// mode = "hash"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransformSettings {
+    /// Keep only records whose attribute or payload field exists and, if `equals` is set,
+    /// has that value; with `negate` drop them instead
+    Filter {
+        #[serde(default)]
+        attribute: Option<String>,
+        #[serde(default)]
+        field: Option<String>,
+        #[serde(default)]
+        equals: Option<serde_json::Value>,
+        #[serde(default)]
+        negate: bool,
+    },
+    /// Remove payload fields
+    DropFields { fields: Vec<String> },
+    /// Move payload fields, from old to new path
+    RenameFields { fields: BTreeMap<String, String> },
+    /// Set a payload field to a fixed value
+    InsertField {
+        field: String,
+        value: serde_json::Value,
+    },
+    /// Copy attributes (all if none are listed) into the payload root, or into the object
+    /// at `field`
+    CopyAttributes {
+        #[serde(default)]
+        attributes: Vec<String>,
+        #[serde(default)]
+        field: Option<String>,
+    },
+    /// Replace payload fields with `replacement` or their SHA-256 hash
+    MaskFields {
+        fields: Vec<String>,
+        #[serde(default)]
+        mode: MaskMode,
+        #[serde(default = "default_mask_replacement")]
+        replacement: String,
+    },
+    /// Publish source records to a fixed topic (sources only)
+    SetTopic { topic: String },
+    /// Set the routing key of source records from an attribute or payload field (sources
+    /// only)
+    SetKey {
+        #[serde(default)]
+        attribute: Option<String>,
+        #[serde(default)]
+        field: Option<String>,
+    },
+    /// Run the `transform(record)` function of a Rhai script file (needs the `scripting`
+    /// feature)
+    ///
+    /// The script can modify, split, drop or reroute records. It runs sandboxed and is
+    /// aborted after `max_operations` operations per record.
+    Script {
+        path: String,
+        #[serde(default = "default_script_max_operations")]
+        max_operations: u64,
+    },
+}
+
+impl TransformSettings {
+    /// The `type` of the transform
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Filter { .. } => "filter",
+            Self::DropFields { .. } => "drop_fields",
+            Self::RenameFields { .. } => "rename_fields",
+            Self::InsertField { .. } => "insert_field",
+            Self::CopyAttributes { .. } => "copy_attributes",
+            Self::MaskFields { .. } => "mask_fields",
+            Self::SetTopic { .. } => "set_topic",
+            Self::SetKey { .. } => "set_key",
+            Self::Script { .. } => "script",
         }
     }
 }
 
+/// How `mask_fields` replaces a field
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MaskMode {
+    /// Replace the value with the replacement string
+    #[default]
+    Mask,
+    /// Replace the value with the hex SHA-256 hash of its string form, keeping equal
+    /// values joinable
+    Hash,
+}
+
+fn default_mask_replacement() -> String {
+    "****".to_string()
+}
+fn default_script_max_operations() -> u64 {
+    100_000
+}
+
+/// Ordering guarantee of sink processing
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderingMode {
+    /// Messages of a topic are processed in order by a single worker
+    #[default]
+    Topic,
+    /// Messages of a topic are spread over all workers by routing key and processed in
+    /// order per key (e.g. to use all cores for a `Shared` subscription)
+    Key,
+}
+
 /// Subscription type for configuration (mirrors SubType but with Serialize/Deserialize)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum SubscriptionType {
+    #[default]
     Exclusive,
     Shared,
     FailOver,
@@ -214,6 +596,26 @@ impl From<SubscriptionType> for SubType {
     }
 }
 
+/// Position a sink consumer starts consuming from
+///
+/// Danube subscriptions start where the broker places them, the subscribe request carries
+/// no position. The runtime implements the position by skipping (acknowledging) the
+/// records the broker delivers before it, so replaying history needs a subscription the
+/// broker starts before the position, such as a new subscription on a persistent topic.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StartPosition {
+    /// Deliver every record the broker dispatches to the subscription
+    Earliest,
+    /// Skip records published before the consumer subscribed, including the backlog of
+    /// an existing subscription
+    Latest,
+    /// Skip records below this offset of the topic (per partition for partitioned topics)
+    Offset(u64),
+    /// Skip records published before this time, in milliseconds since the Unix epoch
+    PublishTime(u64),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,6 +627,96 @@ mod tests {
         assert_eq!(config.connector_name, "default-connector");
         assert_eq!(config.retry.max_retries, 3);
         assert_eq!(config.processing.batch_size, 1000);
+        assert_eq!(config.processing.parallelism, 1);
+        assert_eq!(config.processing.ordering, OrderingMode::Topic);
+    }
+
+    #[test]
+    fn test_offset_store_from_toml() {
+        let config: ConnectorConfig = toml::from_str(
+            r#"
+            danube_service_url = "http://localhost:6650"
+            connector_name = "test-source"
+
+            [offset_store]
+            type = "file"
+            path = "/var/lib/connector/offsets.json"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.offset_store,
+            OffsetStoreSettings::File {
+                path: "/var/lib/connector/offsets.json".to_string()
+            }
+        );
+        assert_eq!(
+            ConnectorConfig::default().offset_store,
+            OffsetStoreSettings::None
+        );
+    }
+
+    #[test]
+    fn test_start_position_from_toml() {
+        #[derive(Deserialize)]
+        struct Mapping {
+            start_position: StartPosition,
+        }
+
+        let parse = |toml_str: &str| toml::from_str::<Mapping>(toml_str).unwrap().start_position;
+        assert_eq!(
+            parse(r#"start_position = "earliest""#),
+            StartPosition::Earliest
+        );
+        assert_eq!(
+            parse("start_position = { offset = 1000 }"),
+            StartPosition::Offset(1000)
+        );
+        assert_eq!(
+            parse("start_position = { publish_time = 1700000000000 }"),
+            StartPosition::PublishTime(1_700_000_000_000)
+        );
+    }
+
+    #[test]
+    fn test_failure_policy_from_toml() {
+        let config: ConnectorConfig = toml::from_str(
+            r#"
+            danube_service_url = "http://localhost:6650"
+            connector_name = "test-sink"
+
+            [retry]
+            failure_policy = "retry_forever"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.retry.failure_policy, FailurePolicy::RetryForever);
+        assert_eq!(
+            ConnectorConfig::default().retry.failure_policy,
+            FailurePolicy::DeadLetter
+        );
+    }
+
+    #[test]
+    fn test_key_ordering_from_toml() {
+        let config: ConnectorConfig = toml::from_str(
+            r#"
+            danube_service_url = "http://localhost:6650"
+            connector_name = "test-sink"
+
+            [processing]
+            parallelism = 4
+            ordering = "key"
+            ordering_key = "record_id"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.processing.parallelism, 4);
+        assert_eq!(config.processing.ordering, OrderingMode::Key);
+        assert_eq!(config.processing.ordering_key.as_deref(), Some("record_id"));
     }
 
     #[test]
@@ -238,5 +730,18 @@ mod tests {
         config.danube_service_url = "http://localhost:6650".to_string();
         config.processing.batch_size = 0;
         assert!(config.validate().is_err());
+
+        config.processing.batch_size = 1000;
+        config.processing.parallelism = 0;
+        assert!(config.validate().is_err());
+
+        config.processing.parallelism = 1;
+        config.processing.max_in_flight = 0;
+        assert!(config.validate().is_err());
+
+        config.processing.max_in_flight = 1;
+        config.circuit_breaker.enabled = true;
+        config.circuit_breaker.failure_rate_threshold = 1.5;
+        assert!(config.validate().is_err());
     }
 }
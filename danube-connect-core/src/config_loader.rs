@@ -0,0 +1,651 @@
+//! Loading of connector configuration files.
+//!
+//! Connectors declare their configuration as a serde struct, usually with the
+//! `ConnectorConfig` flattened at the root, and load it with `load_config()`. On top of
+//! plain TOML the loader supports:
+//!
+//! - `${VAR}` and `${VAR:-default}` anywhere in the file, replaced with the environment
+//!   variable before parsing (the default applies when the variable is unset or empty,
+//!   `$${` yields a literal `${`, comments are left alone). Values are escaped for the
+//!   string they are in; outside strings they must be TOML values, e.g. numbers
+//! - `DANUBE_CONNECT__SECTION__KEY=value` environment variables overriding any key, with
+//!   `__` separating the path segments (case-insensitive, array entries by index, e.g.
+//!   `DANUBE_CONNECT__QDRANT__TOPIC_MAPPINGS__0__COLLECTION_NAME`). Connectors can still
+//!   accept their former variables, e.g. `QDRANT_API_KEY`, as deprecated aliases of these.
+//!   Overrides of credentials (`password`, `api_key`, `token`, ...) are always strings
+//! - `*_secret_file` keys, replaced with the content of the file they point to, e.g.
+//!   `api_key_secret_file = "/run/secrets/qdrant_api_key"` sets `api_key` (other keys
+//!   ending in `_file`, such as schema files, are plain paths and left alone)
+
+use crate::{ConnectorError, ConnectorResult};
+use serde::de::DeserializeOwned;
+use std::env;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Environment variable with the path of the connector configuration file
+pub const CONFIG_PATH_ENV: &str = "CONNECTOR_CONFIG_PATH";
+
+/// Prefix of the environment variables overriding configuration keys
+pub const ENV_OVERRIDE_PREFIX: &str = "DANUBE_CONNECT__";
+
+/// Suffix of keys whose value is read from a file
+const SECRET_FILE_SUFFIX: &str = "_secret_file";
+
+/// Suffixes of credential keys, whose overrides are always strings
+const STRING_KEY_SUFFIXES: [&str; 6] = [
+    "password",
+    "api_key",
+    "token",
+    "secret",
+    "username",
+    "client_id",
+];
+
+/// Load the configuration file at `CONNECTOR_CONFIG_PATH`
+pub fn load_config<T: DeserializeOwned>() -> ConnectorResult<T> {
+    load_config_with_aliases(&[])
+}
+
+/// Load the configuration file at `CONNECTOR_CONFIG_PATH`, accepting deprecated
+/// environment variables (see `parse_config_with_aliases()`)
+pub fn load_config_with_aliases<T: DeserializeOwned>(
+    aliases: &[(&str, &str)],
+) -> ConnectorResult<T> {
+    let path = env::var(CONFIG_PATH_ENV).map_err(|_| {
+        ConnectorError::config(format!(
+            "{} environment variable must be set to the path of the TOML configuration file",
+            CONFIG_PATH_ENV
+        ))
+    })?;
+    load_config_file_with_aliases(path, aliases)
+}
+
+/// Load a configuration file
+pub fn load_config_file<T: DeserializeOwned>(path: impl AsRef<Path>) -> ConnectorResult<T> {
+    load_config_file_with_aliases(path, &[])
+}
+
+/// Load a configuration file, accepting deprecated environment variables (see
+/// `parse_config_with_aliases()`)
+pub fn load_config_file_with_aliases<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    aliases: &[(&str, &str)],
+) -> ConnectorResult<T> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        ConnectorError::config(format!(
+            "Failed to read config file {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+
+    parse_config_with_aliases(&content, aliases).map_err(|e| {
+        ConnectorError::config(format!(
+            "Failed to load config file {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Parse configuration content, e.g. of a reloaded configuration file
+pub fn parse_config<T: DeserializeOwned>(content: &str) -> ConnectorResult<T> {
+    parse_config_with_aliases(content, &[])
+}
+
+/// Parse configuration content, accepting deprecated environment variables
+///
+/// `aliases` pairs a deprecated variable with the `DANUBE_CONNECT__*` override replacing
+/// it, e.g. `("QDRANT_API_KEY", "DANUBE_CONNECT__QDRANT__API_KEY")`. A deprecated variable
+/// that is set applies as that override, with a warning, unless the override is set too.
+pub fn parse_config_with_aliases<T: DeserializeOwned>(
+    content: &str,
+    aliases: &[(&str, &str)],
+) -> ConnectorResult<T> {
+    parse_with_vars(content, aliases, env::vars().collect())
+}
+
+/// Parse configuration content with the environment variables `vars`
+fn parse_with_vars<T: DeserializeOwned>(
+    content: &str,
+    aliases: &[(&str, &str)],
+    vars: Vec<(String, String)>,
+) -> ConnectorResult<T> {
+    let content = interpolate(content, |name| {
+        vars.iter()
+            .find(|(var, _)| var == name)
+            .map(|(_, value)| value.clone())
+    })?;
+    let mut table: Table = toml::from_str(&content)
+        .map_err(|e| ConnectorError::config(format!("Invalid TOML: {}", e)))?;
+
+    let aliased = aliased_overrides(&vars, aliases);
+    apply_overrides(&mut table, aliased.into_iter().chain(vars))?;
+    resolve_secret_files(&mut table)?;
+
+    Value::Table(table)
+        .try_into()
+        .map_err(|e| ConnectorError::config(format!("Invalid configuration: {}", e)))
+}
+
+/// The overrides set through the deprecated variables of `aliases` among `vars`
+fn aliased_overrides(vars: &[(String, String)], aliases: &[(&str, &str)]) -> Vec<(String, String)> {
+    let is_set = |name: &str| vars.iter().any(|(var, _)| var == name);
+    aliases
+        .iter()
+        .filter_map(|&(deprecated, name)| {
+            let (_, value) = vars.iter().find(|(var, _)| var == deprecated)?;
+            if is_set(name) {
+                tracing::warn!("Ignoring deprecated {}, {} is set", deprecated, name);
+                return None;
+            }
+            tracing::warn!("{} is deprecated, use {} instead", deprecated, name);
+            Some((name.to_string(), value.clone()))
+        })
+        .collect()
+}
+
+/// Part of the TOML syntax a `${VAR}` reference is in, deciding how its value is escaped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    /// Outside strings, e.g. `batch_size = ${BATCH_SIZE}`
+    Bare,
+    /// `"..."` string
+    Basic,
+    /// `"""..."""` string
+    MultiBasic,
+    /// `'...'` string
+    Literal,
+    /// `'''...'''` string
+    MultiLiteral,
+}
+
+/// Replace `${VAR}` and `${VAR:-default}` with the values from `lookup`
+///
+/// Values are escaped for the string they are in, so quotes and backslashes in a secret
+/// cannot change the structure of the file. Outside strings the value must be a single TOML
+/// value such as a number or boolean.
+fn interpolate(content: &str, lookup: impl Fn(&str) -> Option<String>) -> ConnectorResult<String> {
+    let bytes = content.as_bytes();
+    let mut interpolated = String::with_capacity(content.len());
+    let mut context = Context::Bare;
+    // Start of the content not copied to `interpolated` yet
+    let mut copied = 0;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        // Only ASCII bytes are matched, `rest` is empty inside multi-byte chars
+        let rest = content.get(idx..).unwrap_or_default();
+        match (context, bytes[idx]) {
+            (Context::Bare, b'#') => {
+                // Comments are left alone
+                idx += rest.find('\n').unwrap_or(rest.len());
+            }
+            (Context::Bare, b'"') if rest.starts_with("\"\"\"") => {
+                context = Context::MultiBasic;
+                idx += 3;
+            }
+            (Context::Bare, b'"') => {
+                context = Context::Basic;
+                idx += 1;
+            }
+            (Context::Bare, b'\'') if rest.starts_with("'''") => {
+                context = Context::MultiLiteral;
+                idx += 3;
+            }
+            (Context::Bare, b'\'') => {
+                context = Context::Literal;
+                idx += 1;
+            }
+            (Context::Basic | Context::MultiBasic, b'\\') => {
+                // Skip the escaped char, e.g. `\"` doesn't end the string
+                idx += 1 + content[idx + 1..].chars().next().map_or(0, char::len_utf8);
+            }
+            (Context::MultiBasic, b'"') if rest.starts_with("\"\"\"") => {
+                context = Context::Bare;
+                idx += 3;
+            }
+            (Context::MultiLiteral, b'\'') if rest.starts_with("'''") => {
+                context = Context::Bare;
+                idx += 3;
+            }
+            (Context::Basic, b'"') | (Context::Literal, b'\'') => {
+                context = Context::Bare;
+                idx += 1;
+            }
+            (Context::Basic | Context::Literal, b'\n') => {
+                // Unterminated string, left for the TOML parser to report
+                context = Context::Bare;
+                idx += 1;
+            }
+            (_, b'$') if rest.starts_with("$${") => {
+                interpolated.push_str(&content[copied..idx]);
+                interpolated.push_str("${");
+                idx += 3;
+                copied = idx;
+            }
+            (_, b'$') if rest.starts_with("${") => {
+                let line = line_at(content, idx);
+                let expression = &content[idx + 2..];
+                let end = expression
+                    .find(['}', '\n'])
+                    .filter(|&end| expression.as_bytes()[end] == b'}')
+                    .ok_or_else(|| {
+                        ConnectorError::config(format!("Unterminated ${{ in line: {}", line))
+                    })?;
+
+                let (name, default) = match expression[..end].split_once(":-") {
+                    Some((name, default)) => (name, Some(default)),
+                    None => (&expression[..end], None),
+                };
+                if name.is_empty() {
+                    return Err(ConnectorError::config(format!(
+                        "Empty variable name in line: {}",
+                        line
+                    )));
+                }
+
+                let value = match (lookup(name), default) {
+                    (Some(value), Some(_)) if !value.is_empty() => value,
+                    (Some(value), None) => value,
+                    (_, Some(default)) => default.to_string(),
+                    (None, None) => {
+                        return Err(ConnectorError::config(format!(
+                            "Environment variable {} referenced in the config is not set",
+                            name
+                        )))
+                    }
+                };
+                interpolated.push_str(&content[copied..idx]);
+                interpolated.push_str(&escape_value(context, name, &value)?);
+                idx += 2 + end + 1;
+                copied = idx;
+            }
+            _ => idx += 1,
+        }
+    }
+    interpolated.push_str(&content[copied..]);
+    Ok(interpolated)
+}
+
+/// The line of `content` containing the byte at `idx`, for error messages
+fn line_at(content: &str, idx: usize) -> &str {
+    let start = content[..idx].rfind('\n').map_or(0, |pos| pos + 1);
+    let end = content[idx..]
+        .find('\n')
+        .map_or(content.len(), |pos| idx + pos);
+    content[start..end].trim_end()
+}
+
+/// Escape the value of environment variable `name` for the `context` it is substituted in
+fn escape_value(context: Context, name: &str, value: &str) -> ConnectorResult<String> {
+    match context {
+        Context::Basic | Context::MultiBasic => {
+            let mut escaped = String::with_capacity(value.len());
+            for c in value.chars() {
+                match c {
+                    '"' => escaped.push_str("\\\""),
+                    '\\' => escaped.push_str("\\\\"),
+                    '\n' => escaped.push_str("\\n"),
+                    '\r' => escaped.push_str("\\r"),
+                    '\t' => escaped.push_str("\\t"),
+                    c if c.is_control() => escaped.push_str(&format!("\\u{:04X}", c as u32)),
+                    c => escaped.push(c),
+                }
+            }
+            Ok(escaped)
+        }
+        // Literal strings have no escapes, values that would end them are refused
+        Context::Literal if value.contains(['\'', '\n']) => Err(ConnectorError::config(format!(
+            "Environment variable {} contains a quote or newline and cannot be used in a \
+             '...' string, use a \"...\" string",
+            name
+        ))),
+        Context::MultiLiteral if value.contains("'''") => Err(ConnectorError::config(format!(
+            "Environment variable {} contains ''' and cannot be used in a '''...''' string, \
+             use a \"\"\"...\"\"\" string",
+            name
+        ))),
+        Context::Literal | Context::MultiLiteral => Ok(value.to_string()),
+        Context::Bare => {
+            let is_value = !value.contains(['\n', '\r'])
+                && toml::from_str::<Table>(&format!("value = {}", value)).is_ok();
+            if !is_value {
+                return Err(ConnectorError::config(format!(
+                    "Environment variable {} is used outside a string but is not a TOML value, \
+                     quote the reference as \"${{{}}}\"",
+                    name, name
+                )));
+            }
+            Ok(value.to_string())
+        }
+    }
+}
+
+/// Apply the `DANUBE_CONNECT__SECTION__KEY` overrides among `vars`
+///
+/// Values are parsed as TOML literals (numbers, booleans, arrays) unless the overridden
+/// key holds a string or is a credential such as `password` or `api_key`, so secrets
+/// such as numeric passwords stay strings even when the file doesn't set them.
+fn apply_overrides(
+    table: &mut Table,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> ConnectorResult<()> {
+    for (name, raw) in vars {
+        let Some(path) = name.strip_prefix(ENV_OVERRIDE_PREFIX) else {
+            continue;
+        };
+        let segments: Vec<String> = path.split("__").map(str::to_lowercase).collect();
+        if segments.iter().any(String::is_empty) {
+            return Err(ConnectorError::config(format!(
+                "Invalid config override {}",
+                name
+            )));
+        }
+
+        set_path(table, &segments, raw).map_err(|reason| {
+            ConnectorError::config(format!("Invalid config override {}: {}", name, reason))
+        })?;
+    }
+    Ok(())
+}
+
+/// Set the value at a path of keys and array indices, creating missing tables
+fn set_path(table: &mut Table, segments: &[String], raw: String) -> Result<(), String> {
+    let (key, parents) = segments.split_last().ok_or("empty path")?;
+
+    let mut current = table;
+    let mut idx = 0;
+    while idx < parents.len() {
+        let segment = &parents[idx];
+        let mut child = current
+            .entry(segment.as_str())
+            .or_insert_with(|| Value::Table(Table::new()));
+        if let Value::Array(items) = child {
+            // Array entries are addressed by the index in the next segment
+            idx += 1;
+            let index = parents
+                .get(idx)
+                .and_then(|index| index.parse::<usize>().ok())
+                .ok_or_else(|| format!("{} is an array, expected an index", segment))?;
+            child = items
+                .get_mut(index)
+                .ok_or_else(|| format!("{} has no entry {}", segment, index))?;
+        }
+        current = match child {
+            Value::Table(table) => table,
+            _ => return Err(format!("{} is not a table", parents[idx])),
+        };
+        idx += 1;
+    }
+
+    let value = override_value(key, current.get(key.as_str()), raw);
+    current.insert(key.clone(), value);
+    Ok(())
+}
+
+fn override_value(key: &str, current: Option<&Value>, raw: String) -> Value {
+    let is_credential = STRING_KEY_SUFFIXES
+        .iter()
+        .any(|suffix| key.ends_with(suffix));
+    if is_credential || matches!(current, Some(Value::String(_))) {
+        return Value::String(raw);
+    }
+    toml::from_str::<Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut parsed| parsed.remove("value"))
+        .unwrap_or(Value::String(raw))
+}
+
+/// Replace `key_secret_file = "path"` entries with `key = "<content of path>"`
+fn resolve_secret_files(table: &mut Table) -> ConnectorResult<()> {
+    let secret_files: Vec<String> = table
+        .iter()
+        .filter(|(key, value)| key.ends_with(SECRET_FILE_SUFFIX) && value.is_str())
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    for file_key in secret_files {
+        let key = file_key.trim_end_matches(SECRET_FILE_SUFFIX).to_string();
+        if table.contains_key(&key) {
+            return Err(ConnectorError::config(format!(
+                "Both {} and {} are set, use only one",
+                key, file_key
+            )));
+        }
+
+        let Some(Value::String(path)) = table.remove(&file_key) else {
+            continue;
+        };
+        let secret = std::fs::read_to_string(&path).map_err(|e| {
+            ConnectorError::config(format!("Failed to read {} from {}: {}", file_key, path, e))
+        })?;
+        table.insert(
+            key,
+            Value::String(secret.trim_end_matches(['\r', '\n']).to_string()),
+        );
+    }
+
+    for (_, value) in table.iter_mut() {
+        match value {
+            Value::Table(child) => resolve_secret_files(child)?,
+            Value::Array(items) => {
+                for item in items {
+                    if let Value::Table(child) = item {
+                        resolve_secret_files(child)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConnectorConfig;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "BROKER" => Some("danube-broker".to_string()),
+            "EMPTY" => Some(String::new()),
+            "PASSWORD" => Some("pa\"ss\\word\nadmin = true".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let content = "url = \"http://${BROKER}:${PORT:-6650}\"\nname = \"${EMPTY:-sink}\"\n";
+        assert_eq!(
+            interpolate(content, lookup).unwrap(),
+            "url = \"http://danube-broker:6650\"\nname = \"sink\"\n"
+        );
+
+        // Escapes and comments are kept, unset variables without default are errors
+        assert_eq!(
+            interpolate("# ${MISSING}\nx = \"$${BROKER} $5\"", lookup).unwrap(),
+            "# ${MISSING}\nx = \"${BROKER} $5\""
+        );
+        assert!(interpolate("x = \"${MISSING}\"", lookup).is_err());
+        assert!(interpolate("x = \"${BROKER\"", lookup).is_err());
+    }
+
+    #[test]
+    fn test_interpolate_escapes_values() {
+        // Quotes, backslashes and newlines stay inside the string
+        let content = interpolate("password = \"${PASSWORD}\"\n", lookup).unwrap();
+        let table: Table = toml::from_str(&content).unwrap();
+        assert_eq!(
+            table["password"].as_str(),
+            Some("pa\"ss\\word\nadmin = true")
+        );
+        assert!(table.get("admin").is_none());
+
+        let content = interpolate("password = \"\"\"\n${PASSWORD}\"\"\"", lookup).unwrap();
+        let table: Table = toml::from_str(&content).unwrap();
+        assert_eq!(
+            table["password"].as_str(),
+            Some("pa\"ss\\word\nadmin = true")
+        );
+
+        // A `"` inside a literal string doesn't start a basic string
+        assert_eq!(
+            interpolate("url = 'say \"${BROKER}'", lookup).unwrap(),
+            "url = 'say \"danube-broker'"
+        );
+        assert!(interpolate("password = '${PASSWORD}'", lookup).is_err());
+
+        // Outside strings the value must be a TOML value
+        assert_eq!(
+            interpolate("batch_size = ${SIZE:-250}", lookup).unwrap(),
+            "batch_size = 250"
+        );
+        assert!(interpolate("password = ${PASSWORD}", lookup).is_err());
+        assert!(interpolate("name = ${BROKER}", lookup).is_err());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut table: Table = toml::from_str(
+            r#"
+            connector_name = "sink"
+            [qdrant]
+            api_key = "key"
+            "#,
+        )
+        .unwrap();
+
+        let vars = [
+            ("DANUBE_CONNECT__CONNECTOR_NAME", "sink-prod"),
+            ("DANUBE_CONNECT__PROCESSING__BATCH_SIZE", "500"),
+            ("DANUBE_CONNECT__QDRANT__API_KEY", "12345"),
+            ("OTHER__PROCESSING__BATCH_SIZE", "1"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        apply_overrides(&mut table, vars).unwrap();
+
+        assert_eq!(table["connector_name"].as_str(), Some("sink-prod"));
+        assert_eq!(table["processing"]["batch_size"].as_integer(), Some(500));
+        // Numeric values stay strings where the file has a string
+        assert_eq!(table["qdrant"]["api_key"].as_str(), Some("12345"));
+
+        let mut mappings: Table =
+            toml::from_str("[[topic_mappings]]\ntopic = \"/default/a\"").unwrap();
+        let vars = [(
+            "DANUBE_CONNECT__TOPIC_MAPPINGS__0__TOPIC".to_string(),
+            "/default/b".to_string(),
+        )];
+        apply_overrides(&mut mappings, vars).unwrap();
+        assert_eq!(
+            mappings["topic_mappings"][0]["topic"].as_str(),
+            Some("/default/b")
+        );
+
+        let invalid = [(
+            "DANUBE_CONNECT__CONNECTOR_NAME__X".to_string(),
+            "1".to_string(),
+        )];
+        assert!(apply_overrides(&mut table, invalid).is_err());
+    }
+
+    #[test]
+    fn test_aliased_overrides() {
+        let vars = [
+            ("QDRANT_API_KEY", "legacy"),
+            ("QDRANT_URL", "http://legacy:6334"),
+            ("DANUBE_CONNECT__QDRANT__URL", "http://qdrant:6334"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let aliases = [
+            ("QDRANT_API_KEY", "DANUBE_CONNECT__QDRANT__API_KEY"),
+            ("QDRANT_URL", "DANUBE_CONNECT__QDRANT__URL"),
+            ("CONNECTOR_NAME", "DANUBE_CONNECT__CONNECTOR_NAME"),
+        ];
+
+        // The override wins over its deprecated variable
+        assert_eq!(
+            aliased_overrides(&vars, &aliases),
+            vec![(
+                "DANUBE_CONNECT__QDRANT__API_KEY".to_string(),
+                "legacy".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_aliased_credentials_stay_strings() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Mqtt {
+            broker_port: u16,
+            use_tls: bool,
+            password: Option<String>,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        struct Config {
+            mqtt: Mqtt,
+        }
+
+        let vars = [
+            ("MQTT_BROKER_PORT", "8883"),
+            ("MQTT_USE_TLS", "true"),
+            ("MQTT_PASSWORD", "12345"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let aliases = [
+            ("MQTT_BROKER_PORT", "DANUBE_CONNECT__MQTT__BROKER_PORT"),
+            ("MQTT_USE_TLS", "DANUBE_CONNECT__MQTT__USE_TLS"),
+            ("MQTT_PASSWORD", "DANUBE_CONNECT__MQTT__PASSWORD"),
+        ];
+
+        // The file sets neither key: the port and flag are typed, the password is a string
+        let config: Config =
+            parse_with_vars("[mqtt]\nbroker_port = 1883\n", &aliases, vars.to_vec()).unwrap();
+        assert_eq!(config.mqtt.broker_port, 8883);
+        assert!(config.mqtt.use_tls);
+        assert_eq!(config.mqtt.password.as_deref(), Some("12345"));
+    }
+
+    #[test]
+    fn test_secret_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = dir.path().join("api_key");
+        std::fs::write(&secret, "s3cret\n").unwrap();
+
+        let mut table: Table = toml::from_str(&format!(
+            "[[mappings]]\ntopic = \"/default/a\"\napi_key_secret_file = {:?}\nschema_file = \"schemas/a.json\"\n",
+            secret.display().to_string()
+        ))
+        .unwrap();
+        resolve_secret_files(&mut table).unwrap();
+
+        let mapping = &table["mappings"][0];
+        assert_eq!(mapping["api_key"].as_str(), Some("s3cret"));
+        assert!(mapping.get("api_key_secret_file").is_none());
+        // Other `_file` keys are paths for the connector
+        assert_eq!(mapping["schema_file"].as_str(), Some("schemas/a.json"));
+        assert!(mapping.get("schema").is_none());
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config: ConnectorConfig = parse_config(
+            r#"
+            danube_service_url = "${DANUBE_CONNECT_TEST_UNSET:-http://localhost:6650}"
+            connector_name = "test-connector"
+
+            [processing]
+            batch_size = ${DANUBE_CONNECT_TEST_UNSET:-250}
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.danube_service_url, "http://localhost:6650");
+        assert_eq!(config.processing.batch_size, 250);
+
+        assert!(parse_config::<ConnectorConfig>("connector_name = 1").is_err());
+    }
+}
@@ -67,6 +67,19 @@ impl ConnectorError {
         matches!(self, ConnectorError::InvalidData { .. })
     }
 
+    /// Short, stable name of the error variant (used for labels and DLQ attributes)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ConnectorError::Retryable { .. } => "retryable",
+            ConnectorError::Fatal { .. } => "fatal",
+            ConnectorError::InvalidData { .. } => "invalid_data",
+            ConnectorError::Configuration(_) => "configuration",
+            ConnectorError::Danube(_) => "danube",
+            ConnectorError::Serialization(_) => "serialization",
+            ConnectorError::Io(_) => "io",
+        }
+    }
+
     /// Create a retryable error from a message
     pub fn retryable(message: impl Into<String>) -> Self {
         ConnectorError::Retryable {
@@ -142,6 +155,8 @@ mod tests {
 
         let invalid = ConnectorError::invalid_data("bad json", vec![1, 2, 3]);
         assert!(invalid.is_invalid_data());
+        assert_eq!(invalid.kind(), "invalid_data");
+        assert_eq!(retryable.kind(), "retryable");
     }
 
     #[test]
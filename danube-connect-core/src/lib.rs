@@ -34,6 +34,7 @@
 //!             consumer_name: "my-consumer".to_string(),
 //!             subscription: "my-sub".to_string(),
 //!             subscription_type: SubscriptionType::Exclusive,
+//!             ..Default::default()
 //!         }])
 //!     }
 //!     
@@ -50,28 +51,51 @@
 //! - **Automatic Lifecycle Management**: The runtime handles initialization, message loops, and shutdown
 //! - **Built-in Retry Logic**: Configurable exponential backoff for transient failures
 //! - **Message Transformation**: Helpers for JSON, binary, and schema-based transformations
+//! - **Avro and Protobuf**: Binary payloads decoded to JSON with a schema definition (`avro`
+//!   and `protobuf` features)
+//! - **JSON Schema Validation**: Per-topic `json_schema` files checked before records reach
+//!   the connector or Danube
+//! - **Single-Message Transforms**: Configurable `[[transforms]]` chain reshaping records
+//!   between Danube and the connector
 //! - **Observability**: Automatic metrics, structured logging, and health checks
 //! - **Configuration**: Standard environment variable and file-based configuration
 
+mod circuit_breaker;
 mod config;
+mod config_loader;
 mod error;
 mod message;
 mod metrics;
+mod offset_store;
 mod retry;
 mod runtime;
 mod schema;
 mod traits;
+mod transform;
 pub mod utils;
 
 // Re-export public API
-pub use config::{ConnectorConfig, ProcessingSettings, RetrySettings, SubscriptionType};
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+pub use config::{
+    CircuitBreakerSettings, ConnectorConfig, FailurePolicy, MaskMode, OffsetStoreSettings,
+    OrderingMode, ProcessingSettings, RetrySettings, StartPosition, SubscriptionType,
+    TransformSettings,
+};
+pub use config_loader::{
+    load_config, load_config_file, load_config_file_with_aliases, load_config_with_aliases,
+    parse_config, parse_config_with_aliases, CONFIG_PATH_ENV, ENV_OVERRIDE_PREFIX,
+};
 pub use error::{ConnectorError, ConnectorResult};
 pub use message::{DanubeMetadata, SinkRecord, SourceRecord};
 pub use metrics::ConnectorMetrics;
+pub use offset_store::{FileOffsetStore, OffsetStore, TopicOffsetStore};
 pub use retry::{RetryConfig, RetryStrategy};
 pub use runtime::{ConsumerConfig, ProducerConfig, SinkRuntime, SourceRuntime};
-pub use schema::SchemaType;
-pub use traits::{Offset, SinkConnector, SourceConnector};
+#[cfg(feature = "avro")]
+pub use schema::AvroSchema;
+pub use schema::{JsonSchema, PayloadSchema, SchemaType, TopicSchema};
+pub use traits::{AckMode, Offset, SinkConnector, SourceConnector};
+pub use transform::{Transform, TransformRecord};
 pub use utils::{Batcher, HealthChecker, HealthStatus};
 
 // Re-export commonly used types from danube-client
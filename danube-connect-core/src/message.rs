@@ -3,7 +3,7 @@
 //! This module provides helper types and methods for transforming messages between
 //! Danube's format and connector-specific formats.
 
-use crate::{ConnectorError, ConnectorResult, SchemaType};
+use crate::{ConnectorError, ConnectorResult, Offset, PayloadSchema, SchemaType};
 use danube_core::message::StreamMessage;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -39,6 +39,12 @@ pub struct DanubeMetadata {
     pub message_id: String,
     /// Producer name (for debugging)
     pub producer_name: String,
+    /// Schema type registered for the topic in Danube's schema registry, if any
+    pub schema_type: Option<SchemaType>,
+    /// Index of the record among those a transform split the message into, 0 if not split
+    pub split_index: usize,
+    /// Number of records the message was split into, 1 if not split
+    pub split_count: usize,
 }
 
 impl SinkRecord {
@@ -58,6 +64,9 @@ impl SinkRecord {
                 publish_time: message.publish_time,
                 message_id,
                 producer_name: message.producer_name,
+                schema_type: None,
+                split_index: 0,
+                split_count: 1,
             },
             partition,
         }
@@ -85,6 +94,8 @@ impl SinkRecord {
     /// - **String**: Deserializes to UTF-8 string as `Value::String`
     /// - **Int64**: Deserializes big-endian 8 bytes to i64 as `Value::Number`
     /// - **Bytes**: Encodes as base64, wrapped in `{data: "base64...", size: N}`
+    /// - **Avro** / **Protobuf**: Decodes with the schema definition of the [`PayloadSchema`]
+    ///   to `serde_json::Value` (see [`PayloadSchema::load`])
     ///
     /// # Design Rationale
     ///
@@ -100,14 +111,19 @@ impl SinkRecord {
     /// ```ignore
     /// // In any sink connector
     /// let data = record.payload_deserialized(mapping.schema_type)?;
-    /// 
+    ///
     /// // For Json: data is the original JSON structure
     /// // For String: data is Value::String("hello")
     /// // For Int64: data is Value::Number(42)
     /// // For Bytes: data is {"data": "AQIDBA==", "size": 4}
+    ///
+    /// // Avro and Protobuf need the schema definition
+    /// let schema = PayloadSchema::load(SchemaType::Avro, Some("reading.avsc"), None)?;
+    /// let data = record.payload_deserialized(&schema)?;
     /// ```
-    pub fn payload_deserialized(&self, schema_type: SchemaType) -> ConnectorResult<Value> {
-        match schema_type {
+    pub fn payload_deserialized(&self, schema: impl Into<PayloadSchema>) -> ConnectorResult<Value> {
+        let schema = schema.into();
+        match schema.schema_type() {
             SchemaType::Json => self.payload_json(),
             SchemaType::String => {
                 let string_value = self.payload_str()?.to_string();
@@ -124,6 +140,7 @@ impl SinkRecord {
                     "size": self.payload_size()
                 }))
             }
+            SchemaType::Avro | SchemaType::Protobuf => schema.decode(&self.payload),
         }
     }
 
@@ -193,6 +210,25 @@ impl SinkRecord {
         self.danube_metadata.offset
     }
 
+    /// Get the position of the record: its offset and split index
+    ///
+    /// The records a transform split a message into share its offset, their positions tell
+    /// them apart, e.g. to recognize a record that is already buffered when it is retried.
+    pub fn position(&self) -> (u64, usize) {
+        (
+            self.danube_metadata.offset,
+            self.danube_metadata.split_index,
+        )
+    }
+
+    /// Whether this is the last record of its message
+    ///
+    /// Deferred-ack connectors report a message's offset as flushed once its last record
+    /// is written.
+    pub fn is_last_split(&self) -> bool {
+        self.danube_metadata.split_index + 1 >= self.danube_metadata.split_count
+    }
+
     /// Get the publish timestamp (microseconds since epoch)
     pub fn publish_time(&self) -> u64 {
         self.danube_metadata.publish_time
@@ -203,6 +239,14 @@ impl SinkRecord {
         &self.danube_metadata.producer_name
     }
 
+    /// Get the schema type registered for the topic, resolved by the runtime when subscribing
+    ///
+    /// `None` if the topic has no registered schema. Connectors use it when a topic's
+    /// configuration doesn't set the schema type.
+    pub fn schema_type(&self) -> Option<SchemaType> {
+        self.danube_metadata.schema_type
+    }
+
     /// Get a formatted message ID string for logging
     pub fn message_id(&self) -> &str {
         &self.danube_metadata.message_id
@@ -218,11 +262,15 @@ pub struct SourceRecord {
     pub payload: Vec<u8>,
     /// Optional message attributes/headers
     pub attributes: HashMap<String, String>,
-    /// Optional routing key for partitioned topics (will be used when Danube supports it)
+    /// Optional routing key: records with the same key go to the same partition of a partitioned topic
     pub key: Option<String>,
     /// Optional producer configuration for this topic (partitions, reliable dispatch)
-    /// If not specified, runtime will use default configuration
+    /// Used when the runtime creates the producer of a topic not declared in
+    /// `producer_configs()`; if not specified, runtime will use default configuration
     pub producer_config: Option<crate::runtime::ProducerConfig>,
+    /// Optional position of this record in the source system
+    /// Passed back verbatim to `SourceConnector::commit()` once the record is published
+    pub offset: Option<Offset>,
 }
 
 impl SourceRecord {
@@ -234,6 +282,7 @@ impl SourceRecord {
             attributes: HashMap::new(),
             key: None,
             producer_config: None,
+            offset: None,
         }
     }
 
@@ -273,6 +322,12 @@ impl SourceRecord {
         self
     }
 
+    /// Set the source position of this record, committed once it is published
+    pub fn with_offset(mut self, offset: Offset) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
     /// Get the payload size in bytes
     pub fn size(&self) -> usize {
         self.payload.len()
@@ -406,6 +461,7 @@ mod tests {
         assert_eq!(record.size(), 4);
         assert!(record.attributes.is_empty());
         assert!(record.key.is_none());
+        assert!(record.offset.is_none());
     }
 
     #[test]
@@ -449,4 +505,15 @@ mod tests {
         assert_eq!(record.attributes.get("version"), Some(&"1.0".to_string()));
         assert_eq!(record.key, Some("user-123".to_string()));
     }
+
+    #[test]
+    fn test_source_record_offset() {
+        let record = SourceRecord::new("/default/events", b"test".to_vec())
+            .with_offset(Offset::with_metadata("events.log", 1024, "line 17"));
+
+        let offset = record.offset.unwrap();
+        assert_eq!(offset.partition, "events.log");
+        assert_eq!(offset.value, 1024);
+        assert_eq!(offset.metadata.as_deref(), Some("line 17"));
+    }
 }
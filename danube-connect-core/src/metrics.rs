@@ -1,32 +1,66 @@
 //! Metrics and observability for connectors.
 
-use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
+use metrics::{
+    counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Label,
+};
 use std::time::Duration;
 
 /// Metrics collector for connectors
+///
+/// Runtimes keep one collector per Danube topic (and subscription for sinks), plus a
+/// connector-wide collector without topic label for health and polling metrics.
 #[derive(Debug, Clone)]
 pub struct ConnectorMetrics {
     /// Connector name for labeling
     connector_name: String,
-    /// Topic name for labeling
-    topic: String,
+    /// Topic name for labeling (`None` for connector-wide metrics)
+    topic: Option<String>,
+    /// Subscription name for labeling (sink consumers only)
+    subscription: Option<String>,
 }
 
 impl ConnectorMetrics {
-    /// Create a new metrics collector
+    /// Create a new metrics collector for a topic
     pub fn new(connector_name: impl Into<String>, topic: impl Into<String>) -> Self {
-        let connector_name = connector_name.into();
-        let topic = topic.into();
-
         // Register metric descriptions
         Self::register_metrics();
 
         Self {
-            connector_name,
-            topic,
+            connector_name: connector_name.into(),
+            topic: Some(topic.into()),
+            subscription: None,
+        }
+    }
+
+    /// Create a metrics collector for connector-wide metrics (no topic label)
+    pub fn for_connector(connector_name: impl Into<String>) -> Self {
+        Self::register_metrics();
+
+        Self {
+            connector_name: connector_name.into(),
+            topic: None,
+            subscription: None,
         }
     }
 
+    /// Add a subscription label
+    pub fn with_subscription(mut self, subscription: impl Into<String>) -> Self {
+        self.subscription = Some(subscription.into());
+        self
+    }
+
+    /// Labels attached to every metric of this collector
+    fn labels(&self) -> Vec<Label> {
+        let mut labels = vec![Label::new("connector", self.connector_name.clone())];
+        if let Some(topic) = &self.topic {
+            labels.push(Label::new("topic", topic.clone()));
+        }
+        if let Some(subscription) = &self.subscription {
+            labels.push(Label::new("subscription", subscription.clone()));
+        }
+        labels
+    }
+
     /// Register metric descriptions
     fn register_metrics() {
         // Counters
@@ -46,11 +80,23 @@ impl ConnectorMetrics {
             "danube_connector_messages_retried_total",
             "Total number of message processing retries"
         );
+        describe_counter!(
+            "danube_connector_messages_dead_lettered_total",
+            "Total number of messages published to a dead-letter topic"
+        );
+        describe_counter!(
+            "danube_connector_messages_skipped_total",
+            "Total number of messages skipped before the start position of their consumer"
+        );
+        describe_counter!(
+            "danube_connector_messages_filtered_total",
+            "Total number of messages dropped by a transform"
+        );
 
         // Histograms
         describe_histogram!(
             "danube_connector_processing_duration_seconds",
-            "Time spent processing each message"
+            "Time spent processing each message batch"
         );
         describe_histogram!(
             "danube_connector_batch_size",
@@ -70,103 +116,77 @@ impl ConnectorMetrics {
 
     /// Record a message received
     pub fn record_received(&self) {
-        counter!(
-            "danube_connector_messages_received_total",
-            "connector" => self.connector_name.clone(),
-            "topic" => self.topic.clone(),
-        )
-        .increment(1);
+        counter!("danube_connector_messages_received_total", self.labels()).increment(1);
     }
 
     /// Record a message successfully processed
     pub fn record_success(&self) {
-        counter!(
-            "danube_connector_messages_processed_total",
-            "connector" => self.connector_name.clone(),
-            "topic" => self.topic.clone(),
-        )
-        .increment(1);
+        counter!("danube_connector_messages_processed_total", self.labels()).increment(1);
     }
 
     /// Record a message processing failure
     pub fn record_error(&self, error_type: &str) {
-        counter!(
-            "danube_connector_messages_failed_total",
-            "connector" => self.connector_name.clone(),
-            "topic" => self.topic.clone(),
-            "error_type" => error_type.to_string(),
-        )
-        .increment(1);
+        let mut labels = self.labels();
+        labels.push(Label::new("error_type", error_type.to_string()));
+        counter!("danube_connector_messages_failed_total", labels).increment(1);
     }
 
     /// Record a retry attempt
     pub fn record_retry(&self) {
+        counter!("danube_connector_messages_retried_total", self.labels()).increment(1);
+    }
+
+    /// Record a message published to a dead-letter topic
+    pub fn record_dead_letter(&self) {
         counter!(
-            "danube_connector_messages_retried_total",
-            "connector" => self.connector_name.clone(),
-            "topic" => self.topic.clone(),
+            "danube_connector_messages_dead_lettered_total",
+            self.labels()
         )
         .increment(1);
     }
 
+    /// Record a message skipped before the start position of its consumer
+    pub fn record_skipped(&self) {
+        counter!("danube_connector_messages_skipped_total", self.labels()).increment(1);
+    }
+
+    /// Record a message dropped by a transform
+    pub fn record_filtered(&self) {
+        counter!("danube_connector_messages_filtered_total", self.labels()).increment(1);
+    }
+
     /// Record processing duration
     pub fn record_processing_time(&self, duration: Duration) {
         histogram!(
             "danube_connector_processing_duration_seconds",
-            "connector" => self.connector_name.clone(),
-            "topic" => self.topic.clone(),
+            self.labels()
         )
         .record(duration.as_secs_f64());
     }
 
     /// Record batch size
     pub fn record_batch_size(&self, size: usize) {
-        histogram!(
-            "danube_connector_batch_size",
-            "connector" => self.connector_name.clone(),
-            "topic" => self.topic.clone(),
-        )
-        .record(size as f64);
+        histogram!("danube_connector_batch_size", self.labels()).record(size as f64);
     }
 
     /// Set inflight message count
     pub fn set_inflight(&self, count: usize) {
-        gauge!(
-            "danube_connector_inflight_messages",
-            "connector" => self.connector_name.clone(),
-            "topic" => self.topic.clone(),
-        )
-        .set(count as f64);
+        gauge!("danube_connector_inflight_messages", self.labels()).set(count as f64);
     }
 
     /// Increment inflight message count
     pub fn increment_inflight(&self) {
-        gauge!(
-            "danube_connector_inflight_messages",
-            "connector" => self.connector_name.clone(),
-            "topic" => self.topic.clone(),
-        )
-        .increment(1.0);
+        gauge!("danube_connector_inflight_messages", self.labels()).increment(1.0);
     }
 
     /// Decrement inflight message count
     pub fn decrement_inflight(&self) {
-        gauge!(
-            "danube_connector_inflight_messages",
-            "connector" => self.connector_name.clone(),
-            "topic" => self.topic.clone(),
-        )
-        .decrement(1.0);
+        gauge!("danube_connector_inflight_messages", self.labels()).decrement(1.0);
     }
 
     /// Set connector health status
     pub fn set_health(&self, healthy: bool) {
-        gauge!(
-            "danube_connector_health",
-            "connector" => self.connector_name.clone(),
-            "topic" => self.topic.clone(),
-        )
-        .set(if healthy { 1.0 } else { 0.0 });
+        gauge!("danube_connector_health", self.labels()).set(if healthy { 1.0 } else { 0.0 });
     }
 }
 
@@ -211,7 +231,29 @@ mod tests {
     fn test_metrics_creation() {
         let metrics = ConnectorMetrics::new("test-connector", "/default/test");
         assert_eq!(metrics.connector_name, "test-connector");
-        assert_eq!(metrics.topic, "/default/test");
+        assert_eq!(metrics.topic.as_deref(), Some("/default/test"));
+    }
+
+    #[test]
+    fn test_metrics_labels() {
+        let metrics =
+            ConnectorMetrics::new("test-connector", "/default/test").with_subscription("test-sub");
+        let labels: Vec<(String, String)> = metrics
+            .labels()
+            .iter()
+            .map(|l| (l.key().to_string(), l.value().to_string()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("connector".to_string(), "test-connector".to_string()),
+                ("topic".to_string(), "/default/test".to_string()),
+                ("subscription".to_string(), "test-sub".to_string()),
+            ]
+        );
+
+        let connector_wide = ConnectorMetrics::for_connector("test-connector");
+        assert_eq!(connector_wide.labels().len(), 1);
     }
 
     #[test]
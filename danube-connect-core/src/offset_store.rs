@@ -0,0 +1,310 @@
+//! Persistent offset storage for source connectors.
+//!
+//! The `SourceRuntime` loads the stored offsets before the connector is initialized, hands
+//! them to `SourceConnector::restore_offsets()` and saves the offsets of every successful
+//! `SourceConnector::commit()`, so sources restart where they left off.
+
+use crate::{ConnectorError, ConnectorResult, Offset};
+use async_trait::async_trait;
+use danube_client::{Consumer, DanubeClient, Producer, SubType};
+use danube_core::message::StreamMessage;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+/// How long loading from a topic waits for further snapshots before using the last one
+const TOPIC_LOAD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Storage for the committed offsets of a source connector
+///
+/// Stores hold the latest offset per partition (`Offset::partition`).
+#[async_trait]
+pub trait OffsetStore: Send + Sync {
+    /// Load the stored offsets, one per partition (empty on first start)
+    async fn load(&mut self) -> ConnectorResult<Vec<Offset>>;
+
+    /// Store committed offsets, replacing the stored offset of their partitions
+    async fn save(&mut self, offsets: &[Offset]) -> ConnectorResult<()>;
+}
+
+/// Merge committed offsets into the latest offset per partition
+fn merge_offsets(stored: &mut BTreeMap<String, Offset>, offsets: &[Offset]) {
+    for offset in offsets {
+        stored.insert(offset.partition.clone(), offset.clone());
+    }
+}
+
+/// Offset store backed by a local JSON file
+///
+/// Every save rewrites the file atomically: the offsets are written to a temporary file
+/// next to it, which then replaces the old file.
+pub struct FileOffsetStore {
+    path: PathBuf,
+    offsets: BTreeMap<String, Offset>,
+}
+
+impl FileOffsetStore {
+    /// Create a store for the given file, created on first save
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            offsets: BTreeMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl OffsetStore for FileOffsetStore {
+    async fn load(&mut self) -> ConnectorResult<Vec<Offset>> {
+        let content = match tokio::fs::read(&self.path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                info!("No offsets stored yet at {}", self.path.display());
+                return Ok(Vec::new());
+            }
+            Err(e) => {
+                return Err(ConnectorError::fatal_with_source(
+                    format!("Failed to read offsets from {}", self.path.display()),
+                    e,
+                ))
+            }
+        };
+
+        let offsets: Vec<Offset> = serde_json::from_slice(&content).map_err(|e| {
+            ConnectorError::fatal(format!(
+                "Invalid offsets file {}: {}",
+                self.path.display(),
+                e
+            ))
+        })?;
+
+        self.offsets.clear();
+        merge_offsets(&mut self.offsets, &offsets);
+        Ok(offsets)
+    }
+
+    async fn save(&mut self, offsets: &[Offset]) -> ConnectorResult<()> {
+        merge_offsets(&mut self.offsets, offsets);
+        let content = serde_json::to_vec_pretty(&self.offsets.values().collect::<Vec<_>>())
+            .map_err(|e| ConnectorError::Serialization(e.to_string()))?;
+
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+
+        // Write a temporary file and rename it, so a crash never leaves a partial file
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut file = tokio::fs::File::create(&tmp_path).await?;
+        file.write_all(&content).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+
+        debug!(
+            "Saved {} offset(s) to {}",
+            self.offsets.len(),
+            self.path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Offset store backed by a Danube topic
+///
+/// Every save publishes a snapshot of all offsets with reliable dispatch. The store
+/// consumes its own snapshots through an exclusive subscription and acknowledges a
+/// snapshot only once a newer one was received, so the subscription always retains the
+/// latest snapshot for the next start.
+pub struct TopicOffsetStore {
+    client: DanubeClient,
+    topic: String,
+    /// Producer, consumer and subscription name
+    name: String,
+    producer: Option<Producer>,
+    consumer: Option<(Consumer, mpsc::Receiver<StreamMessage>)>,
+    /// Latest received snapshot, acknowledged once a newer one arrives
+    latest: Option<StreamMessage>,
+    offsets: BTreeMap<String, Offset>,
+}
+
+impl TopicOffsetStore {
+    /// Create a store on `topic`, using `name` for its producer and subscription
+    pub fn new(client: DanubeClient, topic: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            client,
+            topic: topic.into(),
+            name: name.into(),
+            producer: None,
+            consumer: None,
+            latest: None,
+            offsets: BTreeMap::new(),
+        }
+    }
+
+    /// Subscribe to the offsets topic
+    async fn subscribe(&mut self) -> ConnectorResult<()> {
+        let mut consumer = self
+            .client
+            .new_consumer()
+            .with_topic(&self.topic)
+            .with_consumer_name(&self.name)
+            .with_subscription(&self.name)
+            .with_subscription_type(SubType::Exclusive)
+            .build();
+        consumer.subscribe().await.map_err(|e| {
+            ConnectorError::fatal_with_source(
+                format!("Failed to subscribe to offsets topic {}", self.topic),
+                e,
+            )
+        })?;
+        let stream = consumer.receive().await.map_err(|e| {
+            ConnectorError::fatal_with_source(
+                format!("Failed to read offsets topic {}", self.topic),
+                e,
+            )
+        })?;
+        self.consumer = Some((consumer, stream));
+        Ok(())
+    }
+
+    /// Keep `msg` as the latest snapshot and acknowledge the previous one
+    async fn receive_snapshot(&mut self, msg: StreamMessage) -> ConnectorResult<()> {
+        if let (Some(previous), Some((consumer, _))) = (self.latest.take(), &mut self.consumer) {
+            consumer.ack(&previous).await?;
+        }
+        self.latest = Some(msg);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl OffsetStore for TopicOffsetStore {
+    async fn load(&mut self) -> ConnectorResult<Vec<Offset>> {
+        if self.consumer.is_none() {
+            self.subscribe().await?;
+        }
+
+        // Read the retained snapshots, the last one is the latest
+        while let Some((_, stream)) = &mut self.consumer {
+            match tokio::time::timeout(TOPIC_LOAD_TIMEOUT, stream.recv()).await {
+                Ok(Some(msg)) => self.receive_snapshot(msg).await?,
+                _ => break,
+            }
+        }
+
+        let offsets: Vec<Offset> = match &self.latest {
+            Some(msg) => serde_json::from_slice(&msg.payload).map_err(|e| {
+                ConnectorError::fatal(format!(
+                    "Invalid offsets snapshot on topic {}: {}",
+                    self.topic, e
+                ))
+            })?,
+            None => {
+                info!("No offsets stored yet on topic {}", self.topic);
+                Vec::new()
+            }
+        };
+
+        self.offsets.clear();
+        merge_offsets(&mut self.offsets, &offsets);
+        Ok(offsets)
+    }
+
+    async fn save(&mut self, offsets: &[Offset]) -> ConnectorResult<()> {
+        merge_offsets(&mut self.offsets, offsets);
+        let content = serde_json::to_vec(&self.offsets.values().collect::<Vec<_>>())
+            .map_err(|e| ConnectorError::Serialization(e.to_string()))?;
+
+        if self.producer.is_none() {
+            let mut producer = self
+                .client
+                .new_producer()
+                .with_topic(&self.topic)
+                .with_name(&self.name)
+                .with_reliable_dispatch()
+                .build();
+            producer.create().await.map_err(|e| {
+                ConnectorError::retryable_with_source(
+                    format!("Failed to create producer for offsets topic {}", self.topic),
+                    e,
+                )
+            })?;
+            self.producer = Some(producer);
+        }
+        if let Some(producer) = &self.producer {
+            producer.send(content, None).await.map_err(|e| {
+                ConnectorError::retryable_with_source(
+                    format!("Failed to publish offsets to topic {}", self.topic),
+                    e,
+                )
+            })?;
+        }
+
+        // Acknowledge the snapshots superseded by the ones received since the last save
+        if self.consumer.is_none() {
+            self.subscribe().await?;
+        }
+        while let Some(msg) = self
+            .consumer
+            .as_mut()
+            .and_then(|(_, stream)| stream.try_recv().ok())
+        {
+            self.receive_snapshot(msg).await?;
+        }
+
+        debug!(
+            "Saved {} offset(s) to topic {}",
+            self.offsets.len(),
+            self.topic
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_offset_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("offsets").join("source.json");
+
+        let mut store = FileOffsetStore::new(&path);
+        assert!(store.load().await.unwrap().is_empty());
+
+        store
+            .save(&[Offset::new("a.log", 10), Offset::new("b.log", 5)])
+            .await
+            .unwrap();
+        store
+            .save(&[Offset::with_metadata("a.log", 20, "line 2")])
+            .await
+            .unwrap();
+
+        // A new store sees the latest offset per partition
+        let mut offsets = FileOffsetStore::new(&path).load().await.unwrap();
+        offsets.sort_by(|a, b| a.partition.cmp(&b.partition));
+        assert_eq!(
+            offsets,
+            vec![
+                Offset::with_metadata("a.log", 20, "line 2"),
+                Offset::new("b.log", 5)
+            ]
+        );
+        assert!(!dir.path().join("offsets").join("source.json.tmp").exists());
+    }
+
+    #[tokio::test]
+    async fn test_file_offset_store_invalid_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("source.json");
+        std::fs::write(&path, "not json").unwrap();
+
+        let err = FileOffsetStore::new(&path).load().await.unwrap_err();
+        assert!(err.is_fatal());
+    }
+}
@@ -10,10 +10,23 @@
 //! - Message processing loops
 //! - Retry logic
 //! - Health monitoring
-//! - Graceful shutdown
+//! - Prometheus metrics endpoint (with the `prometheus` feature)
+//! - Graceful shutdown on SIGTERM/SIGINT, with a bounded drain
+//! - Topic discovery for sink consumers with topic patterns
+//! - Hot reload of the connector configuration file
 
+mod config_reload;
+mod consumer_manager;
+mod dead_letter;
+mod health;
+mod keyed_producer;
+#[cfg(feature = "prometheus")]
+mod metrics_server;
+mod shutdown;
 mod sink_runtime;
+mod sink_worker;
 mod source_runtime;
+mod topic_discovery;
 
 pub use sink_runtime::{ConsumerConfig, SinkRuntime};
 pub use source_runtime::{ProducerConfig, SourceRuntime};
@@ -0,0 +1,125 @@
+//! Hot reload of the connector configuration file
+//!
+//! Runtimes created with `with_config_reload()` check the configuration file for changed
+//! content every few seconds and reload it on SIGHUP. The new content is handed to the
+//! connector's `reconfigure()`, which validates and applies it. Runtime settings such as
+//! `[processing]`, `[retry]` and `[[transforms]]` keep the values the runtime was
+//! started with.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+/// Interval of the checks for a changed configuration file
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Spawn a task sending the content of the configuration file to `reloads` when it
+/// changed or on SIGHUP, until shutdown
+pub(super) fn spawn_config_watcher(
+    path: PathBuf,
+    reloads: mpsc::Sender<String>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tokio::spawn(async move {
+        info!("Watching config file {} for changes", path.display());
+        let mut current = read_config(&path).await;
+        let mut hangup = Hangup::new();
+        let mut watch = tokio::time::interval(WATCH_INTERVAL);
+        watch.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            let forced = tokio::select! {
+                _ = shutdown.changed() => break,
+                _ = watch.tick() => false,
+                _ = hangup.recv() => true,
+            };
+
+            let Some(content) = read_config(&path).await else {
+                continue;
+            };
+            if !forced && current.as_ref() == Some(&content) {
+                continue;
+            }
+
+            info!("Reloading config file {}", path.display());
+            current = Some(content.clone());
+            if reloads.send(content).await.is_err() {
+                // Runtime stopped
+                break;
+            }
+        }
+    });
+}
+
+async fn read_config(path: &Path) -> Option<String> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => Some(content),
+        Err(e) => {
+            warn!("Failed to read config file {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// SIGHUP listener, never firing where the signal does not exist or cannot be handled
+struct Hangup {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl Hangup {
+    #[cfg(unix)]
+    fn new() -> Self {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let signal = signal(SignalKind::hangup())
+            .map_err(|e| warn!("Failed to listen for SIGHUP: {}", e))
+            .ok();
+        Self { signal }
+    }
+
+    #[cfg(not(unix))]
+    fn new() -> Self {
+        Self {}
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = self.signal.as_mut() {
+            if signal.recv().await.is_some() {
+                return;
+            }
+        }
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_watcher_sends_changed_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("connector.toml");
+        std::fs::write(&path, "batch_size = 1").unwrap();
+
+        let (shutdown, shutdown_receiver) = watch::channel(false);
+        let (sender, mut reloads) = mpsc::channel(1);
+        spawn_config_watcher(path.clone(), sender, shutdown_receiver);
+
+        // Unchanged content is not reloaded
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(reloads.try_recv().is_err());
+
+        std::fs::write(&path, "batch_size = 2").unwrap();
+        let content = tokio::time::timeout(WATCH_INTERVAL * 3, reloads.recv())
+            .await
+            .unwrap();
+        assert_eq!(content.as_deref(), Some("batch_size = 2"));
+
+        shutdown.send_replace(true);
+    }
+}
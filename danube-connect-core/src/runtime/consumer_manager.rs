@@ -0,0 +1,376 @@
+//! Consumers of a running sink
+//!
+//! The sink runtime hands its consumers to a `ConsumerManager` when consumers can change
+//! while processing: for topic patterns, whose topics are discovered periodically (see
+//! `topic_discovery`), and for reloaded configurations (see `config_reload`). The manager
+//! compares the consumers the configuration asks for with the attached ones, closes those
+//! no longer wanted or configured differently, and attaches new ones to the running
+//! workers. A consumer is detached from its workers before it is closed, delivering its
+//! buffered messages and dropping its slots.
+
+use super::sink_runtime::subscribe_consumer;
+use super::sink_worker::{
+    spawn_forwarder, ConsumerSlot, ConsumerStream, Route, WorkerContext, WorkerInput,
+};
+use super::topic_discovery::{is_topic_pattern, matching_topics, TopicLister};
+use crate::{ConnectorConfig, ConnectorError, ConnectorResult, ConsumerConfig, OrderingMode};
+use danube_client::{Consumer, DanubeClient};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+/// Channel of a running worker and the number of consumer slots assigned to it so far
+pub(super) struct WorkerChannel {
+    pub(super) sender: mpsc::Sender<WorkerInput>,
+    pub(super) slots: usize,
+}
+
+/// An attached consumer
+struct Attached {
+    consumer: Arc<Mutex<Consumer>>,
+    /// Configuration it was subscribed with, the topic is concrete for discovered topics
+    config: ConsumerConfig,
+    /// Whether its topic was discovered through a topic pattern
+    discovered: bool,
+    /// Slots of the consumer in the workers
+    routes: Vec<Route>,
+}
+
+/// Keeps the consumers of the running workers in line with the consumer configurations
+pub(super) struct ConsumerManager {
+    client: DanubeClient,
+    config: ConnectorConfig,
+    /// Consumer configurations with a concrete topic
+    statics: Vec<ConsumerConfig>,
+    /// Consumer configurations with a topic pattern, the first matching pattern wins
+    patterns: Vec<ConsumerConfig>,
+    /// Lister for the topics of the patterns, if `processing.admin_service_url` is set
+    lister: Option<TopicLister>,
+    /// Attached consumers by topic
+    consumers: HashMap<String, Attached>,
+    context: WorkerContext,
+    workers: Vec<WorkerChannel>,
+    /// Worker of the next consumer with `OrderingMode::Topic`
+    next_worker: usize,
+    /// Contents of the reloaded configuration file
+    reloads: Option<mpsc::Receiver<String>>,
+}
+
+impl ConsumerManager {
+    /// Create the manager of the `subscribed` consumers, already attached to `workers`
+    ///
+    /// With `OrderingMode::Topic` new consumers are attached round-robin, continuing after
+    /// the consumers already assigned.
+    pub(super) fn new(
+        client: DanubeClient,
+        config: ConnectorConfig,
+        subscribed: Vec<(ConsumerConfig, Arc<Mutex<Consumer>>, Vec<Route>)>,
+        patterns: Vec<ConsumerConfig>,
+        context: WorkerContext,
+        workers: Vec<WorkerChannel>,
+        reloads: Option<mpsc::Receiver<String>>,
+    ) -> ConnectorResult<Self> {
+        let lister = match config.processing.admin_service_url.as_deref() {
+            Some(admin_service_url) => Some(TopicLister::new(admin_service_url)?),
+            None if !patterns.is_empty() => {
+                return Err(ConnectorError::config(
+                    "Topic patterns require processing.admin_service_url",
+                ));
+            }
+            None => None,
+        };
+
+        let statics = subscribed.iter().map(|(cfg, _, _)| cfg.clone()).collect();
+        let next_worker = subscribed.len();
+        let consumers = subscribed
+            .into_iter()
+            .map(|(config, consumer, routes)| {
+                let topic = config.topic.clone();
+                let attached = Attached {
+                    consumer,
+                    config,
+                    discovered: false,
+                    routes,
+                };
+                (topic, attached)
+            })
+            .collect();
+
+        Ok(Self {
+            client,
+            statics,
+            patterns,
+            lister,
+            consumers,
+            context,
+            workers,
+            next_worker,
+            reloads,
+            config,
+        })
+    }
+
+    /// Discover topics every `processing.topic_discovery_interval_ms` and apply reloaded
+    /// configurations until shutdown
+    pub(super) async fn run(mut self, mut shutdown: watch::Receiver<bool>) {
+        let interval = Duration::from_millis(self.config.processing.topic_discovery_interval_ms);
+        if !self.patterns.is_empty() {
+            info!(
+                "Discovering topics for {} topic pattern(s) every {:?}",
+                self.patterns.len(),
+                interval
+            );
+        }
+
+        let mut discovery = tokio::time::interval(interval);
+        discovery.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = shutdown.changed() => break,
+                _ = discovery.tick(), if !self.patterns.is_empty() => self.sync().await,
+                Some(content) = recv_reload(&mut self.reloads) => {
+                    if !self.reload(content).await {
+                        // Workers stopped
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reconfigure the connector instance of every worker, then apply the new consumer
+    /// configurations
+    ///
+    /// A configuration the first worker rejects is dropped and everything keeps running as
+    /// before. Returns `false` once the workers stopped.
+    async fn reload(&mut self, content: String) -> bool {
+        let content: Arc<str> = content.into();
+        let mut consumer_configs = None;
+        for (id, worker) in self.workers.iter().enumerate() {
+            let (reply, result) = oneshot::channel();
+            let input = WorkerInput::Reconfigure(content.clone(), reply);
+            if worker.sender.send(input).await.is_err() {
+                return false;
+            }
+            match result.await {
+                Ok(Ok(configs)) if id == 0 => consumer_configs = Some(configs),
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) if id == 0 => {
+                    warn!("Reloaded config rejected, keeping the current one: {}", e);
+                    return true;
+                }
+                Ok(Err(e)) => warn!("Sink worker {} failed to apply reloaded config: {}", id, e),
+                Err(_) => return false,
+            }
+        }
+
+        let Some(consumer_configs) = consumer_configs else {
+            return true;
+        };
+        if consumer_configs.is_empty() {
+            warn!("Reloaded config has no consumer configurations, keeping the current consumers");
+            return true;
+        }
+        if self.lister.is_none()
+            && consumer_configs
+                .iter()
+                .any(|cfg| is_topic_pattern(&cfg.topic))
+        {
+            warn!("Reloaded topic patterns require processing.admin_service_url, keeping the current consumers");
+            return true;
+        }
+
+        let (patterns, statics) = consumer_configs
+            .into_iter()
+            .partition(|cfg| is_topic_pattern(&cfg.topic));
+        self.patterns = patterns;
+        self.statics = statics;
+        self.sync().await;
+        info!("Reloaded config applied");
+        true
+    }
+
+    /// Close the consumers no longer configured and attach the missing ones
+    ///
+    /// If the topics of the patterns cannot be listed, the consumers of discovered topics
+    /// are kept until the next discovery.
+    async fn sync(&mut self) {
+        let desired: BTreeMap<String, ConsumerConfig> = self
+            .statics
+            .iter()
+            .map(|cfg| (cfg.topic.clone(), cfg.clone()))
+            .collect();
+        let mut discovered: BTreeMap<String, ConsumerConfig> = BTreeMap::new();
+
+        match self.discover().await {
+            Ok(topics) => {
+                for (topic, cfg) in topics {
+                    if !desired.contains_key(&topic) {
+                        discovered.insert(topic, cfg);
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Topic discovery failed: {}", e);
+                for (topic, attached) in &self.consumers {
+                    if attached.discovered && !desired.contains_key(topic) {
+                        discovered.insert(topic.clone(), attached.config.clone());
+                    }
+                }
+            }
+        }
+
+        let stale: Vec<String> = self
+            .consumers
+            .iter()
+            .filter(|(topic, attached)| {
+                desired.get(*topic).or_else(|| discovered.get(*topic)) != Some(&attached.config)
+            })
+            .map(|(topic, _)| topic.clone())
+            .collect();
+        for topic in stale {
+            if let Some(attached) = self.consumers.remove(&topic) {
+                info!("Closing consumer for topic {}", topic);
+                detach(&attached.routes).await;
+                attached.consumer.lock().await.close().await;
+            }
+        }
+
+        let missing = desired
+            .into_iter()
+            .map(|(topic, cfg)| (topic, cfg, false))
+            .chain(
+                discovered
+                    .into_iter()
+                    .map(|(topic, cfg)| (topic, cfg, true)),
+            );
+        for (topic, cfg, discovered) in missing {
+            if self.consumers.contains_key(&topic) {
+                continue;
+            }
+            match subscribe_consumer(&self.client, &self.config, cfg).await {
+                Ok(consumer_stream) => {
+                    let consumer = consumer_stream.consumer.clone();
+                    let config = consumer_stream.config.clone();
+                    match self.attach(consumer_stream).await {
+                        Some(routes) => {
+                            let attached = Attached {
+                                consumer,
+                                config,
+                                discovered,
+                                routes,
+                            };
+                            self.consumers.insert(topic, attached);
+                        }
+                        // Workers stopped
+                        None => consumer.lock().await.close().await,
+                    }
+                }
+                // Retried with the next discovery or reload
+                Err(e) => warn!("Failed to attach consumer for topic {}: {}", topic, e),
+            }
+        }
+    }
+
+    /// Concrete topics matching the topic patterns, with their consumer configuration
+    async fn discover(&mut self) -> ConnectorResult<BTreeMap<String, ConsumerConfig>> {
+        let mut discovered = BTreeMap::new();
+        let Some(lister) = self.lister.as_mut() else {
+            return Ok(discovered);
+        };
+        if self.patterns.is_empty() {
+            return Ok(discovered);
+        }
+
+        let patterns: Vec<&str> = self.patterns.iter().map(|cfg| cfg.topic.as_str()).collect();
+        let topics = lister.list_topics(&patterns).await?;
+        for pattern in &self.patterns {
+            for topic in matching_topics(&pattern.topic, &topics) {
+                discovered.entry(topic.clone()).or_insert_with(|| {
+                    if !self.consumers.contains_key(&topic) {
+                        info!(
+                            "Discovered topic {} matching pattern {}",
+                            topic, pattern.topic
+                        );
+                    }
+                    ConsumerConfig {
+                        topic,
+                        ..pattern.clone()
+                    }
+                });
+            }
+        }
+        Ok(discovered)
+    }
+
+    /// Attach a consumer to the workers and forward its messages to them
+    ///
+    /// Returns the slots of the consumer, or `None` if a worker stopped; slots taken in
+    /// other workers by then are detached again.
+    async fn attach(&mut self, consumer_stream: ConsumerStream) -> Option<Vec<Route>> {
+        let targets: Vec<usize> = match self.config.processing.ordering {
+            OrderingMode::Topic => {
+                let worker = self.next_worker % self.workers.len();
+                self.next_worker += 1;
+                vec![worker]
+            }
+            OrderingMode::Key => (0..self.workers.len()).collect(),
+        };
+
+        let mut routes = Vec::new();
+        for worker in targets {
+            let channel = &mut self.workers[worker];
+            let slot = ConsumerSlot::new(&consumer_stream, &self.context);
+            if channel
+                .sender
+                .send(WorkerInput::Attach(Box::new(slot)))
+                .await
+                .is_err()
+            {
+                detach(&routes).await;
+                return None;
+            }
+            // Slots are taken in order, the worker processes the attachment before any
+            // message of the forwarder
+            routes.push(Route {
+                sender: channel.sender.clone(),
+                slot: channel.slots,
+            });
+            channel.slots += 1;
+        }
+
+        spawn_forwarder(
+            consumer_stream.stream,
+            routes.clone(),
+            self.config.processing.ordering_key.clone(),
+        );
+        Some(routes)
+    }
+}
+
+/// Detach a consumer from its workers, waiting until they delivered its buffered messages
+async fn detach(routes: &[Route]) {
+    for route in routes {
+        let (reply, done) = oneshot::channel();
+        if route
+            .sender
+            .send(WorkerInput::Detach(route.slot, reply))
+            .await
+            .is_ok()
+        {
+            // Fails if the worker stopped, dropping the slot anyway
+            let _ = done.await;
+        }
+    }
+}
+
+/// Receive the next reloaded configuration, pending forever without reloads
+async fn recv_reload(reloads: &mut Option<mpsc::Receiver<String>>) -> Option<String> {
+    match reloads {
+        Some(reloads) => reloads.recv().await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,68 @@
+//! Dead-letter publishing
+//!
+//! Sink workers republish records that are invalid or still fail after the retries, and
+//! the source runtime records failing the JSON schema of their topic, to a dead-letter
+//! topic with `dlq.*` attributes describing the failure.
+
+use crate::{ConnectorError, ConnectorResult};
+use danube_client::{DanubeClient, Producer};
+use std::collections::HashMap;
+use tracing::info;
+
+/// Dead-letter producers, keyed by topic and created on first use
+pub(super) struct DeadLetterPublisher {
+    client: DanubeClient,
+    /// Prefix of the producer names, unique per connector (and sink worker)
+    producer_prefix: String,
+    producers: HashMap<String, Producer>,
+}
+
+impl DeadLetterPublisher {
+    /// Create a publisher naming its producers `{producer_prefix}{topic}`
+    pub(super) fn new(client: DanubeClient, producer_prefix: String) -> Self {
+        Self {
+            client,
+            producer_prefix,
+            producers: HashMap::new(),
+        }
+    }
+
+    /// Publish a failed record to a dead-letter topic
+    pub(super) async fn publish(
+        &mut self,
+        topic: &str,
+        payload: Vec<u8>,
+        attributes: HashMap<String, String>,
+    ) -> ConnectorResult<()> {
+        if !self.producers.contains_key(topic) {
+            let producer_name = format!("{}{}", self.producer_prefix, topic.replace('/', "-"));
+            let mut producer = self
+                .client
+                .new_producer()
+                .with_topic(topic)
+                .with_name(&producer_name)
+                .with_reliable_dispatch()
+                .build();
+            producer.create().await.map_err(|e| {
+                ConnectorError::retryable_with_source(
+                    format!("Failed to create dead-letter producer for topic {}", topic),
+                    e,
+                )
+            })?;
+            info!("Dead-letter producer created for topic: {}", topic);
+            self.producers.insert(topic.to_string(), producer);
+        }
+
+        let producer = &self.producers[topic];
+        producer
+            .send(payload, Some(attributes))
+            .await
+            .map_err(|e| {
+                ConnectorError::retryable_with_source(
+                    format!("Failed to publish to dead-letter topic {}", topic),
+                    e,
+                )
+            })?;
+        Ok(())
+    }
+}
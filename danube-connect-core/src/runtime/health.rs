@@ -0,0 +1,114 @@
+//! Connector health tracking for the runtimes
+//!
+//! The runtimes call `health_check()` of the connector every
+//! `processing.health_check_interval_ms` and record the results here. The state backs the
+//! `danube_connector_health` gauge and, with the `prometheus` feature, the `/health` and
+//! `/ready` endpoints served next to `/metrics`.
+
+use crate::utils::{HealthChecker, HealthStatus};
+use crate::{ConnectorMetrics, ConnectorResult};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// Health and readiness of a running connector
+pub(super) struct RuntimeHealth {
+    checker: Mutex<HealthChecker>,
+    /// Set once the connector is initialized and its consumers/producers are created
+    started: AtomicBool,
+    #[cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+    shutdown: watch::Receiver<bool>,
+}
+
+impl RuntimeHealth {
+    /// Create the health state, unhealthy after `failure_threshold` failed checks in a row
+    pub(super) fn new(failure_threshold: usize, shutdown: watch::Receiver<bool>) -> Self {
+        Self {
+            checker: Mutex::new(HealthChecker::new(failure_threshold)),
+            started: AtomicBool::new(false),
+            shutdown,
+        }
+    }
+
+    /// Mark the connector as started, so it becomes ready while healthy
+    pub(super) fn set_started(&self) {
+        self.started.store(true, Ordering::Relaxed);
+    }
+
+    /// Record the result of a `health_check()` call and update the health gauge
+    pub(super) fn record_check(&self, result: &ConnectorResult<()>, metrics: &ConnectorMetrics) {
+        let mut checker = self.checker.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = checker.status();
+        match result {
+            Ok(()) => checker.record_success(),
+            Err(e) => {
+                warn!("Health check failed: {}", e);
+                checker.record_failure();
+            }
+        }
+
+        let status = checker.status();
+        if status != previous {
+            info!("Connector health changed to {}", status_name(status));
+        }
+        metrics.set_health(status != HealthStatus::Unhealthy);
+    }
+
+    /// Current health status
+    #[cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+    pub(super) fn status(&self) -> HealthStatus {
+        self.checker
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .status()
+    }
+
+    /// Whether the connector takes in work: started, not shutting down and not unhealthy
+    #[cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+    pub(super) fn is_ready(&self) -> bool {
+        self.started.load(Ordering::Relaxed)
+            && !*self.shutdown.borrow()
+            && self.status() != HealthStatus::Unhealthy
+    }
+}
+
+/// Name of a health status as reported by the endpoints
+pub(super) fn status_name(status: HealthStatus) -> &'static str {
+    match status {
+        HealthStatus::Healthy => "healthy",
+        HealthStatus::Degraded => "degraded",
+        HealthStatus::Unhealthy => "unhealthy",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConnectorError;
+
+    #[test]
+    fn test_readiness() {
+        let (shutdown, receiver) = watch::channel(false);
+        let health = RuntimeHealth::new(2, receiver);
+        let metrics = ConnectorMetrics::for_connector("test-connector");
+        assert!(!health.is_ready());
+
+        health.set_started();
+        assert!(health.is_ready());
+
+        // Degraded connectors stay ready until the failure threshold is reached
+        health.record_check(&Err(ConnectorError::retryable("unreachable")), &metrics);
+        assert_eq!(health.status(), HealthStatus::Degraded);
+        assert!(health.is_ready());
+        health.record_check(&Err(ConnectorError::retryable("unreachable")), &metrics);
+        assert_eq!(health.status(), HealthStatus::Unhealthy);
+        assert!(!health.is_ready());
+
+        health.record_check(&Ok(()), &metrics);
+        assert!(health.is_ready());
+
+        shutdown.send_replace(true);
+        assert!(!health.is_ready());
+    }
+}
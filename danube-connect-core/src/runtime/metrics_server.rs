@@ -0,0 +1,140 @@
+//! Prometheus metrics and health endpoints
+//!
+//! Installs a global Prometheus recorder for the `metrics` facade and serves the
+//! rendered metrics on `GET /metrics`, next to the `GET /health` (liveness) and
+//! `GET /ready` (readiness) probes. Only compiled with the `prometheus` feature.
+
+use super::health::{status_name, RuntimeHealth};
+use crate::utils::HealthStatus;
+use crate::{ConnectorError, ConnectorResult};
+use axum::{http::StatusCode, routing::get, Router};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Interval for draining histogram buckets of the recorder
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Recorder handle, set once per process
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the Prometheus recorder and serve `/metrics`, `/health` and `/ready` on `port`
+///
+/// Must run before `ConnectorMetrics` registers its metric descriptions. Only the first
+/// call in a process installs the recorder and starts the server, later calls are no-ops.
+///
+/// `/health` answers 503 once the connector is unhealthy, `/ready` while the connector is
+/// starting, shutting down or unhealthy; both answer 200 otherwise.
+pub(crate) async fn start_metrics_server(
+    port: u16,
+    health: Arc<RuntimeHealth>,
+) -> ConnectorResult<()> {
+    if PROMETHEUS_HANDLE.get().is_some() {
+        return Ok(());
+    }
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
+        ConnectorError::fatal_with_source(format!("Failed to bind metrics server on {}", addr), e)
+    })?;
+
+    let handle = PrometheusBuilder::new().install_recorder().map_err(|e| {
+        ConnectorError::fatal_with_source("Failed to install Prometheus recorder", e)
+    })?;
+    let _ = PROMETHEUS_HANDLE.set(handle.clone());
+
+    let upkeep_handle = handle.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(UPKEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            upkeep_handle.run_upkeep();
+        }
+    });
+
+    let readiness = health.clone();
+    let app = Router::new()
+        .route("/metrics", get(move || std::future::ready(handle.render())))
+        .route(
+            "/health",
+            get(move || {
+                let status = health.status();
+                let code = if status == HealthStatus::Unhealthy {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::OK
+                };
+                std::future::ready((code, status_name(status)))
+            }),
+        )
+        .route(
+            "/ready",
+            get(move || {
+                std::future::ready(if readiness.is_ready() {
+                    (StatusCode::OK, "ready")
+                } else {
+                    (StatusCode::SERVICE_UNAVAILABLE, "not ready")
+                })
+            }),
+        );
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            error!("Metrics server stopped: {}", e);
+        }
+    });
+
+    info!(
+        "Serving Prometheus metrics on http://{}/metrics, probes on /health and /ready",
+        addr
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConnectorMetrics;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::watch;
+
+    async fn get(port: u16, path: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .unwrap();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            path
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        // Reserve a free port for the server
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let (_shutdown, receiver) = watch::channel(false);
+        let health = Arc::new(RuntimeHealth::new(3, receiver));
+        start_metrics_server(port, health.clone()).await.unwrap();
+        ConnectorMetrics::new("test-connector", "/default/test").record_received();
+
+        let response = get(port, "/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("danube_connector_messages_received_total"));
+
+        assert!(get(port, "/health").await.starts_with("HTTP/1.1 200"));
+        assert!(get(port, "/ready").await.starts_with("HTTP/1.1 503"));
+        health.set_started();
+        assert!(get(port, "/ready").await.starts_with("HTTP/1.1 200"));
+    }
+}
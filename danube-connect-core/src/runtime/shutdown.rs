@@ -0,0 +1,76 @@
+//! Shutdown signal handling shared by the sink and source runtimes
+//!
+//! SIGTERM (sent by Kubernetes and Docker) and SIGINT start a graceful shutdown: the
+//! runtimes stop taking in new work and drain what is buffered or in flight. The drain is
+//! bounded by `processing.shutdown_timeout_ms`.
+
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{error, info};
+
+/// Spawn a task signalling `shutdown` on SIGTERM or SIGINT
+pub(super) fn spawn_signal_handler(shutdown: watch::Sender<bool>) {
+    tokio::spawn(async move {
+        if let Err(e) = wait_for_signal().await {
+            error!("Failed to listen for shutdown signals: {}", e);
+            return;
+        }
+        info!("Received shutdown signal, draining");
+        shutdown.send_replace(true);
+    });
+}
+
+/// Wait for SIGTERM or SIGINT (ctrl-c)
+#[cfg(unix)]
+async fn wait_for_signal() -> std::io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = sigterm.recv() => Ok(()),
+        result = tokio::signal::ctrl_c() => result,
+    }
+}
+
+/// Wait for ctrl-c
+#[cfg(not(unix))]
+async fn wait_for_signal() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
+
+/// Resolve once shutdown was signalled and `timeout` elapsed since
+///
+/// Raced against the drain of a runtime, so the runtime exits even if draining hangs.
+pub(super) async fn drain_deadline(mut shutdown: watch::Receiver<bool>, timeout: Duration) {
+    if shutdown.wait_for(|&stop| stop).await.is_err() {
+        // The runtime owning the sender is gone, so shutdown is never signalled
+        std::future::pending::<()>().await;
+    }
+    tokio::time::sleep(timeout).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_deadline_starts_at_shutdown() {
+        let (shutdown, receiver) = watch::channel(false);
+        let deadline = drain_deadline(receiver, Duration::from_millis(10));
+        tokio::pin!(deadline);
+
+        // Not signalled yet
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut deadline)
+                .await
+                .is_err()
+        );
+
+        shutdown.send_replace(true);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(500), &mut deadline)
+                .await
+                .is_ok()
+        );
+    }
+}
@@ -1,25 +1,46 @@
 //! Sink Runtime for Danube → External System connectors
 //!
 //! Handles message consumption from Danube topics and processing through sink connectors.
-//! Supports multiple consumers for consuming from multiple Danube topics.
-
+//! Supports multiple consumers for consuming from multiple Danube topics, processed
+//! concurrently by `processing.parallelism` workers.
+
+use super::config_reload::spawn_config_watcher;
+use super::consumer_manager::{ConsumerManager, WorkerChannel};
+use super::health::RuntimeHealth;
+use super::shutdown::{drain_deadline, spawn_signal_handler};
+use super::sink_worker::{
+    spawn_forwarder, ConsumerStream, Route, SinkWorker, StartFilter, WorkerContext,
+};
+use super::topic_discovery::is_topic_pattern;
+use crate::schema::registered_schema_type;
+use crate::transform::TransformChain;
 use crate::{
-    ConnectorConfig, ConnectorError, ConnectorMetrics, ConnectorResult, RetryConfig, RetryStrategy,
-    SinkConnector, SinkRecord, SubscriptionType,
+    AckMode, CircuitBreaker, CircuitBreakerConfig, ConnectorConfig, ConnectorError,
+    ConnectorMetrics, ConnectorResult, FailurePolicy, JsonSchema, OrderingMode, RetryConfig,
+    RetryStrategy, SchemaType, SinkConnector, SinkRecord, StartPosition, SubscriptionType,
+    Transform,
 };
-use danube_client::DanubeClient;
-use std::sync::atomic::{AtomicBool, Ordering};
+use async_trait::async_trait;
+use danube_client::{DanubeClient, Schema};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tokio::time::Instant;
-use tracing::{debug, error, info, warn};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, watch, Mutex};
+use tracing::{error, info, warn};
 
 /// Configuration for a Danube consumer
 ///
 /// Specifies how to create a consumer for a specific topic, including subscription settings.
-#[derive(Debug, Clone)]
+/// Optional settings can be left to `..Default::default()`.
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct ConsumerConfig {
     /// Danube topic to consume from (format: /{namespace}/{topic_name})
+    ///
+    /// A topic pattern such as `/events/*` or `/events/**` (see `utils::topic_matches`)
+    /// consumes every matching topic: topics are discovered through the admin API at
+    /// `processing.admin_service_url` every `processing.topic_discovery_interval_ms`, and
+    /// consumers are attached for new topics and closed for deleted ones. Records keep the
+    /// concrete topic in `SinkRecord::topic()`.
     pub topic: String,
     /// Consumer name (for identification)
     pub consumer_name: String,
@@ -27,13 +48,27 @@ pub struct ConsumerConfig {
     pub subscription: String,
     /// Subscription type (Exclusive, Shared, FailOver)
     pub subscription_type: SubscriptionType,
-}
-
-/// Internal struct to hold consumer and its stream together
-struct ConsumerStream {
-    topic: String,
-    consumer: danube_client::Consumer,
-    stream: mpsc::Receiver<danube_core::message::StreamMessage>,
+    /// Dead-letter topic for invalid and retry-exhausted records
+    ///
+    /// Falls back to `processing.dead_letter_topic` when not set. Records are republished
+    /// with their original payload and attributes plus `dlq.source_topic`,
+    /// `dlq.source_offset`, `dlq.error_kind` and `dlq.error_message`.
+    pub dead_letter_topic: Option<String>,
+    /// What happens to records that still fail after the retries
+    ///
+    /// Falls back to `retry.failure_policy` when not set.
+    pub failure_policy: Option<FailurePolicy>,
+    /// Position to start consuming from
+    ///
+    /// Records before it are skipped, see `StartPosition`. When not set the consumer
+    /// starts where the broker places the subscription.
+    pub start_position: Option<StartPosition>,
+    /// JSON Schema file the payloads must match
+    ///
+    /// Checked after the transforms and before `process`. Records that don't match fail
+    /// with `InvalidData` naming the invalid values, and go to the dead-letter topic like
+    /// other invalid records.
+    pub json_schema: Option<String>,
 }
 
 /// Runtime for Sink Connectors (Danube → External System)
@@ -45,10 +80,14 @@ pub struct SinkRuntime<C: SinkConnector> {
     config: ConnectorConfig,
     metrics: Arc<ConnectorMetrics>,
     retry_strategy: RetryStrategy,
-    shutdown: Arc<AtomicBool>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    shutdown: watch::Sender<bool>,
+    health: Arc<RuntimeHealth>,
+    config_path: Option<PathBuf>,
+    transforms: TransformChain<SinkRecord>,
 }
 
-impl<C: SinkConnector> SinkRuntime<C> {
+impl<C: SinkConnector + 'static> SinkRuntime<C> {
     /// Create a new sink runtime
     pub async fn new(connector: C, config: ConnectorConfig) -> ConnectorResult<Self> {
         // Validate configuration
@@ -68,8 +107,19 @@ impl<C: SinkConnector> SinkRuntime<C> {
             .await
             .map_err(|e| ConnectorError::fatal_with_source("Failed to create Danube client", e))?;
 
-        // Create metrics (topic will be set dynamically per consumer)
-        let metrics = Arc::new(ConnectorMetrics::new(&config.connector_name, "multi-topic"));
+        let shutdown = watch::Sender::new(false);
+        let health = Arc::new(RuntimeHealth::new(
+            config.processing.health_failure_threshold,
+            shutdown.subscribe(),
+        ));
+
+        // Serve Prometheus metrics (the recorder must exist before metrics are registered)
+        #[cfg(feature = "prometheus")]
+        super::metrics_server::start_metrics_server(config.processing.metrics_port, health.clone())
+            .await?;
+
+        // Connector-wide metrics (per-topic metrics are created with the consumers)
+        let metrics = Arc::new(ConnectorMetrics::for_connector(&config.connector_name));
         metrics.set_health(true);
 
         // Create retry strategy
@@ -79,46 +129,82 @@ impl<C: SinkConnector> SinkRuntime<C> {
             config.retry.max_backoff_ms,
         ));
 
+        // Create circuit breaker, shared by all workers
+        let breaker = &config.circuit_breaker;
+        let circuit_breaker = breaker.enabled.then(|| {
+            Arc::new(CircuitBreaker::new(CircuitBreakerConfig::new(
+                breaker.failure_rate_threshold,
+                breaker.minimum_calls,
+                breaker.window_size,
+                breaker.open_duration_ms,
+            )))
+        });
+
+        // Create the configured transforms
+        let transforms = TransformChain::for_sink(&config.transforms)?;
+        if !transforms.is_empty() {
+            info!(
+                "Applying {} transform(s) to consumed records",
+                transforms.len()
+            );
+        }
+
         Ok(Self {
             connector,
             client,
             config,
             metrics,
             retry_strategy,
-            shutdown: Arc::new(AtomicBool::new(false)),
+            circuit_breaker,
+            shutdown,
+            health,
+            config_path: None,
+            transforms,
         })
     }
 
+    /// Apply a custom transform to every record, after the configured `transforms`
+    pub fn with_transform(mut self, transform: impl Transform<SinkRecord> + 'static) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Reload the configuration file at `path` when it changes or on SIGHUP
+    ///
+    /// The new content is handed to `SinkConnector::reconfigure()` of every connector
+    /// instance, and consumers are then attached, closed or resubscribed following the new
+    /// `consumer_configs()`. Runtime settings keep their startup values.
+    pub fn with_config_reload(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
     /// Run the sink connector with multiple consumers
     pub async fn run(&mut self) -> ConnectorResult<()> {
         info!("Starting Sink Runtime");
 
         // Setup shutdown handler
-        self.setup_shutdown_handler();
+        spawn_signal_handler(self.shutdown.clone());
 
         // Initialize connector and create consumers
         self.initialize_connector().await?;
-        let mut streams = self.create_consumers().await?;
-
-        // Main processing loop
-        self.process_messages(&mut streams).await?;
-
-        // Graceful shutdown
-        self.shutdown_connector().await?;
+        let (streams, patterns) = self.create_consumers().await?;
+        self.health.set_started();
+
+        // Process until shutdown, each worker drains and shuts down its connector instance
+        let shutdown_timeout = Duration::from_millis(self.config.processing.shutdown_timeout_ms);
+        let deadline = drain_deadline(self.shutdown.subscribe(), shutdown_timeout);
+        let result = tokio::select! {
+            result = self.process_messages(streams, patterns) => result,
+            _ = deadline => Err(ConnectorError::fatal(format!(
+                "Sink did not drain within the shutdown timeout of {} ms",
+                shutdown_timeout.as_millis()
+            ))),
+        };
 
-        Ok(())
-    }
-
-    /// Setup shutdown signal handler
-    fn setup_shutdown_handler(&self) {
-        let shutdown = self.shutdown.clone();
-        tokio::spawn(async move {
-            tokio::signal::ctrl_c()
-                .await
-                .expect("Failed to listen for ctrl-c");
-            info!("Received shutdown signal");
-            shutdown.store(true, Ordering::Relaxed);
-        });
+        self.metrics.set_health(false);
+        info!("Sink Runtime stopped");
+        result
     }
 
     /// Initialize the connector
@@ -129,20 +215,14 @@ impl<C: SinkConnector> SinkRuntime<C> {
         Ok(())
     }
 
-    /// Gracefully shutdown the connector
-    async fn shutdown_connector(&mut self) -> ConnectorResult<()> {
-        info!("Shutting down connector");
-        self.connector.shutdown().await?;
-        self.metrics.set_health(false);
-        info!("Sink Runtime stopped");
-        Ok(())
-    }
-
     /// Create consumers for all configured topics
     ///
-    /// Returns a vector of (topic, consumer, message_stream) tuples
+    /// Returns the subscribed consumers, and the consumer configurations with a topic
+    /// pattern whose topics are discovered while processing.
     /// The type is intentionally opaque to avoid exposing internal danube-client stream types
-    async fn create_consumers(&mut self) -> ConnectorResult<Vec<ConsumerStream>> {
+    async fn create_consumers(
+        &mut self,
+    ) -> ConnectorResult<(Vec<ConsumerStream>, Vec<ConsumerConfig>)> {
         // Get consumer configurations from connector
         let consumer_configs = self.connector.consumer_configs().await?;
 
@@ -153,167 +233,197 @@ impl<C: SinkConnector> SinkRuntime<C> {
         }
 
         info!("Creating {} consumer(s)", consumer_configs.len());
-
-        let mut streams = Vec::new();
-
-        for consumer_cfg in consumer_configs {
+        if let Some(replay_from) = self.config.processing.replay_from_ms {
             info!(
-                "Creating consumer for topic: {} (subscription: {}, type: {:?})",
-                consumer_cfg.topic, consumer_cfg.subscription, consumer_cfg.subscription_type
+                "Replay mode: skipping records published before {} ms since the Unix epoch",
+                replay_from
             );
+        }
 
-            let mut consumer = self
-                .client
-                .new_consumer()
-                .with_topic(&consumer_cfg.topic)
-                .with_consumer_name(&consumer_cfg.consumer_name)
-                .with_subscription(&consumer_cfg.subscription)
-                .with_subscription_type(consumer_cfg.subscription_type.clone().into())
-                .build();
-
-            consumer.subscribe().await.map_err(|e| {
-                ConnectorError::fatal_with_source(
-                    format!("Failed to subscribe to topic {}", consumer_cfg.topic),
-                    e,
-                )
-            })?;
-
-            let message_stream = consumer.receive().await.map_err(|e| {
-                ConnectorError::fatal_with_source(
-                    format!(
-                        "Failed to start message stream for topic {}",
-                        consumer_cfg.topic
-                    ),
-                    e,
-                )
-            })?;
-
-            info!(
-                "Consumer subscribed successfully to topic: {}",
-                consumer_cfg.topic
-            );
+        let (patterns, configs): (Vec<_>, Vec<_>) = consumer_configs
+            .into_iter()
+            .partition(|cfg| is_topic_pattern(&cfg.topic));
 
-            streams.push(ConsumerStream {
-                topic: consumer_cfg.topic.clone(),
-                consumer,
-                stream: message_stream,
-            });
+        let mut streams = Vec::new();
+        for consumer_cfg in configs {
+            streams.push(subscribe_consumer(&self.client, &self.config, consumer_cfg).await?);
         }
 
         info!("All consumers created and subscribed successfully");
-        Ok(streams)
+        Ok((streams, patterns))
     }
 
-    /// Main message processing loop
-    async fn process_messages(&mut self, streams: &mut [ConsumerStream]) -> ConnectorResult<()> {
-        info!("Entering main processing loop");
-
-        loop {
-            if self.shutdown.load(Ordering::Relaxed) {
-                break;
-            }
-
-            let mut has_activity = false;
-
-            for consumer_stream in streams.iter_mut() {
-                // Non-blocking check for messages (short timeout to avoid accumulation)
-                match tokio::time::timeout(
-                    std::time::Duration::from_millis(10),
-                    consumer_stream.stream.recv(),
-                )
-                .await
-                {
-                    Ok(Some(msg)) => {
-                        has_activity = true;
-                        self.metrics.record_received();
-
-                        let record = SinkRecord::from_stream_message(msg.clone(), None);
-
-                        debug!(
-                            "Processing message from topic {}: offset={}",
-                            consumer_stream.topic,
-                            record.offset()
-                        );
-
-                        // Process with retry logic
-                        match self.process_with_retry(record).await {
-                            Ok(_) => {
-                                // Acknowledge successful processing
-                                if let Err(e) = consumer_stream.consumer.ack(&msg).await {
-                                    error!("Failed to acknowledge message: {}", e);
-                                } else {
-                                    self.metrics.record_success();
-                                    debug!("Message acknowledged");
-                                }
-                            }
-                            Err(e) => {
-                                error!("Failed to process message after retries: {}", e);
-                                self.metrics.record_error(&format!("{:?}", e));
-                                // TODO: Implement dead-letter queue logic
-                            }
-                        }
-                    }
-                    Ok(None) => {
-                        // Channel closed
-                    }
-                    _ => {
-                        // Timeout or no message, continue to next stream
-                    }
-                }
-            }
-
-            // If no activity, check for pending flushes
-            if !has_activity {
-                // Trigger periodic flush check for time-based flushing
-                // With 10ms timeout per stream, flush checks happen every ~(N×10ms + 100ms)
-                // where N is the number of consumer streams
-                if let Err(e) = self.connector.process_batch(vec![]).await {
-                    error!("Error during periodic flush check: {}", e);
+    /// Process messages until shutdown
+    ///
+    /// Messages are processed concurrently by up to `processing.parallelism` workers, each
+    /// on its own connector instance: this one plus instances created by
+    /// `SinkConnector::fork()`. With `OrderingMode::Topic` consumers are distributed
+    /// round-robin and a consumer's messages are processed in order by one worker. With
+    /// `OrderingMode::Key` every consumer's messages are spread over all workers by
+    /// routing key, preserving the order per key.
+    ///
+    /// Consumers of topics matching the topic `patterns` and consumers of reloaded
+    /// configurations are attached to the running workers by a `ConsumerManager` task.
+    async fn process_messages(
+        &mut self,
+        streams: Vec<ConsumerStream>,
+        patterns: Vec<ConsumerConfig>,
+    ) -> ConnectorResult<()> {
+        let processing = &self.config.processing;
+        let deferred_ack = self.connector.ack_mode() == AckMode::Deferred;
+        if deferred_ack {
+            info!("Connector uses deferred acknowledgements");
+        }
+        info!(
+            "Batching up to {} messages or {} ms per consumer",
+            processing.batch_size, processing.batch_timeout_ms
+        );
+
+        // One connector instance per additional worker
+        let worker_count = match processing.ordering {
+            OrderingMode::Topic if patterns.is_empty() => processing.parallelism.min(streams.len()),
+            OrderingMode::Topic => processing.parallelism,
+            OrderingMode::Key => processing.parallelism,
+        };
+        let mut forks = Vec::new();
+        while forks.len() + 1 < worker_count {
+            match self.connector.fork() {
+                Some(fork) => forks.push(fork),
+                None => {
+                    warn!(
+                        "Connector does not support parallel workers, processing with one worker"
+                    );
+                    break;
                 }
-                
-                // Sleep 100ms between check cycles to maintain reasonable flush check frequency
-                // This ensures flush checks happen approximately every 100ms regardless of stream count
-                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
             }
         }
 
-        Ok(())
-    }
+        let context = WorkerContext {
+            client: self.client.clone(),
+            connector_name: self.config.connector_name.clone(),
+            retry_strategy: self.retry_strategy.clone(),
+            circuit_breaker: self.circuit_breaker.clone(),
+            deferred_ack,
+            batch_size: processing.batch_size,
+            batch_timeout: Duration::from_millis(processing.batch_timeout_ms),
+            shutdown: self.shutdown.subscribe(),
+            shutdown_trigger: self.shutdown.clone(),
+            health: self.health.clone(),
+            health_check_interval: Duration::from_millis(processing.health_check_interval_ms),
+            metrics: self.metrics.clone(),
+            transforms: self.transforms.clone(),
+        };
+
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..=forks.len())
+            .map(|_| mpsc::channel(processing.batch_size))
+            .unzip();
+        let mut workers: Vec<SinkWorker> = receivers
+            .into_iter()
+            .enumerate()
+            .map(|(id, messages)| SinkWorker::new(id, context.clone(), messages))
+            .collect();
+
+        match processing.ordering {
+            OrderingMode::Topic => info!(
+                "Processing {} consumer(s) with {} worker(s), ordered per topic",
+                streams.len(),
+                workers.len()
+            ),
+            OrderingMode::Key => info!(
+                "Processing {} consumer(s) with {} worker(s), ordered per {}",
+                streams.len(),
+                workers.len(),
+                processing
+                    .ordering_key
+                    .as_deref()
+                    .map_or("producer".to_string(), |key| format!("'{}' attribute", key))
+            ),
+        }
 
-    /// Process a record with retry logic
-    async fn process_with_retry(&mut self, record: SinkRecord) -> ConnectorResult<()> {
-        let start = Instant::now();
-        let mut attempt = 0;
-
-        loop {
-            match self.connector.process(record.clone()).await {
-                Ok(_) => {
-                    let duration = start.elapsed();
-                    self.metrics.record_processing_time(duration);
-                    return Ok(());
+        let mut subscribed = Vec::with_capacity(streams.len());
+        for (idx, consumer_stream) in streams.into_iter().enumerate() {
+            let routes = match processing.ordering {
+                OrderingMode::Topic => {
+                    let worker = idx % workers.len();
+                    vec![Route {
+                        sender: senders[worker].clone(),
+                        slot: workers[worker].add_consumer(&consumer_stream),
+                    }]
                 }
-                Err(e) if e.is_retryable() && self.retry_strategy.should_retry(attempt) => {
-                    attempt += 1;
-                    self.metrics.record_retry();
+                OrderingMode::Key => workers
+                    .iter_mut()
+                    .zip(&senders)
+                    .map(|(worker, sender)| Route {
+                        sender: sender.clone(),
+                        slot: worker.add_consumer(&consumer_stream),
+                    })
+                    .collect(),
+            };
+            subscribed.push((
+                consumer_stream.config.clone(),
+                consumer_stream.consumer.clone(),
+                routes.clone(),
+            ));
+            spawn_forwarder(
+                consumer_stream.stream,
+                routes,
+                processing.ordering_key.clone(),
+            );
+        }
 
-                    let backoff = self.retry_strategy.calculate_backoff(attempt);
-                    warn!(
-                        "Retry attempt {} after {:?} - error: {}",
-                        attempt, backoff, e
-                    );
+        // Consumers of discovered topics and reloaded configurations join the running workers
+        if !patterns.is_empty() || self.config_path.is_some() {
+            let reloads = self.config_path.clone().map(|path| {
+                let (sender, reloads) = mpsc::channel(1);
+                spawn_config_watcher(path, sender, self.shutdown.subscribe());
+                reloads
+            });
+            let workers = senders
+                .iter()
+                .zip(&workers)
+                .map(|(sender, worker)| WorkerChannel {
+                    sender: sender.clone(),
+                    slots: worker.slot_count(),
+                })
+                .collect();
+            let manager = ConsumerManager::new(
+                self.client.clone(),
+                self.config.clone(),
+                subscribed,
+                patterns,
+                context,
+                workers,
+                reloads,
+            )?;
+            tokio::spawn(manager.run(self.shutdown.subscribe()));
+        }
 
-                    tokio::time::sleep(backoff).await;
-                }
-                Err(e) if e.is_invalid_data() => {
-                    // Skip invalid messages
-                    warn!("Skipping invalid message: {}", e);
-                    return Ok(());
-                }
-                Err(e) => {
-                    return Err(e);
-                }
+        // Worker channels close once all forwarders (and the consumer manager) stopped
+        drop(senders);
+
+        let handles: Vec<_> = workers
+            .drain(1..)
+            .zip(forks)
+            .map(|(worker, mut connector)| {
+                tokio::spawn(async move { worker.run(&mut connector).await })
+            })
+            .collect();
+
+        // The first worker runs on this connector instance
+        let mut result = Ok(());
+        for worker in workers {
+            result = worker.run(&mut self.connector).await;
+        }
+        for handle in handles {
+            let worker_result = handle.await.unwrap_or_else(|e| {
+                Err(ConnectorError::fatal(format!("Sink worker failed: {}", e)))
+            });
+            if let Err(e) = &worker_result {
+                error!("Sink worker stopped with error: {}", e);
             }
+            result = result.and(worker_result);
         }
+        result
     }
 
     /// Initialize tracing/logging
@@ -330,3 +440,212 @@ impl<C: SinkConnector> SinkRuntime<C> {
             .ok(); // Ignore if already initialized
     }
 }
+
+/// Subscribe a consumer and resolve its settings against the runtime configuration
+pub(super) async fn subscribe_consumer(
+    client: &DanubeClient,
+    config: &ConnectorConfig,
+    consumer_cfg: ConsumerConfig,
+) -> ConnectorResult<ConsumerStream> {
+    info!(
+        "Creating consumer for topic: {} (subscription: {}, type: {:?})",
+        consumer_cfg.topic, consumer_cfg.subscription, consumer_cfg.subscription_type
+    );
+
+    // Loaded before subscribing so that a broken schema fails without touching the broker
+    let json_schema = consumer_cfg
+        .json_schema
+        .as_deref()
+        .map(JsonSchema::load)
+        .transpose()?;
+
+    let subscribed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default();
+    let mut consumer = client
+        .new_consumer()
+        .with_topic(&consumer_cfg.topic)
+        .with_consumer_name(&consumer_cfg.consumer_name)
+        .with_subscription(&consumer_cfg.subscription)
+        .with_subscription_type(consumer_cfg.subscription_type.clone().into())
+        .build();
+
+    consumer.subscribe().await.map_err(|e| {
+        ConnectorError::fatal_with_source(
+            format!("Failed to subscribe to topic {}", consumer_cfg.topic),
+            e,
+        )
+    })?;
+
+    let message_stream = consumer.receive().await.map_err(|e| {
+        ConnectorError::fatal_with_source(
+            format!(
+                "Failed to start message stream for topic {}",
+                consumer_cfg.topic
+            ),
+            e,
+        )
+    })?;
+
+    info!(
+        "Consumer subscribed successfully to topic: {}",
+        consumer_cfg.topic
+    );
+
+    let schema_type = resolve_schema_type(client, &consumer_cfg.topic).await;
+
+    let dead_letter_topic = consumer_cfg
+        .dead_letter_topic
+        .clone()
+        .or_else(|| config.processing.dead_letter_topic.clone());
+    if let Some(dlq_topic) = &dead_letter_topic {
+        info!(
+            "Failed records from topic {} go to dead-letter topic {}",
+            consumer_cfg.topic, dlq_topic
+        );
+    }
+
+    let failure_policy = consumer_cfg
+        .failure_policy
+        .unwrap_or(config.retry.failure_policy);
+
+    let start_filter = StartFilter::new(
+        consumer_cfg.start_position,
+        config.processing.replay_from_ms,
+        subscribed_at,
+    );
+    if let Some(position) = consumer_cfg.start_position {
+        info!(
+            "Consumer for topic {} starts at {:?}",
+            consumer_cfg.topic, position
+        );
+    }
+
+    let metrics = ConnectorMetrics::new(&config.connector_name, &consumer_cfg.topic)
+        .with_subscription(&consumer_cfg.subscription);
+
+    Ok(ConsumerStream {
+        topic: consumer_cfg.topic.clone(),
+        config: consumer_cfg,
+        consumer: Arc::new(Mutex::new(consumer)),
+        stream: message_stream,
+        dead_letter_topic,
+        failure_policy,
+        start_filter,
+        metrics,
+        json_schema,
+        schema_type,
+    })
+}
+
+/// Source of the schemas registered in Danube's schema registry, a `DanubeClient`
+#[async_trait]
+trait SchemaLookup {
+    /// Get the schema registered for a topic
+    async fn get_schema(&self, topic: &str) -> danube_client::errors::Result<Schema>;
+}
+
+#[async_trait]
+impl SchemaLookup for DanubeClient {
+    async fn get_schema(&self, topic: &str) -> danube_client::errors::Result<Schema> {
+        DanubeClient::get_schema(self, topic).await
+    }
+}
+
+/// Look up the schema type registered for a topic in Danube's schema registry
+///
+/// The producers of a partitioned topic register its schema on the partition topics, so a
+/// topic without a schema of its own falls back to the schema of its first partition,
+/// `{topic}-part-0`. Topics without a registered schema, or failed lookups, leave the
+/// schema type to the connector's configuration.
+async fn resolve_schema_type(lookup: &impl SchemaLookup, topic: &str) -> Option<SchemaType> {
+    let schema = match lookup.get_schema(topic).await {
+        Ok(schema) if registered_schema_type(&schema).is_some() => Ok(schema),
+        base => {
+            let partition = format!("{}-part-0", topic);
+            match lookup.get_schema(&partition).await {
+                Ok(schema) if registered_schema_type(&schema).is_some() => Ok(schema),
+                _ => base,
+            }
+        }
+    };
+
+    match schema {
+        Ok(schema) => {
+            let schema_type = registered_schema_type(&schema);
+            match schema_type {
+                Some(SchemaType::Bytes) => warn!(
+                    "Topic {} has registered schema {} (Bytes), Avro and Protobuf payloads are registered as Bytes: set their schema type in the connector configuration",
+                    topic, schema.name
+                ),
+                Some(schema_type) => info!(
+                    "Topic {} has registered schema {} ({})",
+                    topic, schema.name, schema_type
+                ),
+                None => {}
+            }
+            schema_type
+        }
+        Err(e) => {
+            warn!("Failed to look up the schema of topic {}: {}", topic, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use danube_client::errors::DanubeError;
+    use danube_client::SchemaType as ClientSchemaType;
+    use std::collections::HashMap;
+
+    /// Registry holding the schemas of some topics
+    struct Registry(HashMap<&'static str, Schema>);
+
+    #[async_trait]
+    impl SchemaLookup for Registry {
+        async fn get_schema(&self, topic: &str) -> danube_client::errors::Result<Schema> {
+            self.0
+                .get(topic)
+                .cloned()
+                .ok_or_else(|| DanubeError::Unrecoverable(format!("no schema for {}", topic)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_schema_type_of_partitioned_topic() {
+        // Sources register the schema under the base topic name on the partition producers
+        let json = Schema::new(
+            "/default/events".to_string(),
+            ClientSchemaType::Json("{}".to_string()),
+        );
+        let registry = Registry(HashMap::from([
+            ("/default/events-part-0", json.clone()),
+            ("/default/events-part-1", json),
+            (
+                "/default/names",
+                Schema::new("/default/names".to_string(), ClientSchemaType::String),
+            ),
+            (
+                "/default/blobs-part-0",
+                Schema::new("/default/blobs".to_string(), ClientSchemaType::Bytes),
+            ),
+        ]));
+
+        assert_eq!(
+            resolve_schema_type(&registry, "/default/events").await,
+            Some(SchemaType::Json)
+        );
+        assert_eq!(
+            resolve_schema_type(&registry, "/default/names").await,
+            Some(SchemaType::String)
+        );
+        assert_eq!(
+            resolve_schema_type(&registry, "/default/blobs").await,
+            Some(SchemaType::Bytes)
+        );
+        assert_eq!(resolve_schema_type(&registry, "/default/other").await, None);
+    }
+}
@@ -0,0 +1,1228 @@
+//! Sink workers for event-driven message processing
+//!
+//! Each worker owns one connector instance and a group of consumers. A forwarding task per
+//! consumer feeds received messages into the bounded channels of the workers, so a worker
+//! reacts to new messages, batch timeouts and flush checks as they happen instead of
+//! polling the streams in turn. All messages of a consumer are routed to the same worker,
+//! or with key ordering all messages with the same routing key.
+
+use super::dead_letter::DeadLetterPublisher;
+use super::health::RuntimeHealth;
+use crate::transform::TransformChain;
+use crate::utils::Batcher;
+use crate::{
+    CircuitBreaker, CircuitState, ConnectorError, ConnectorMetrics, ConnectorResult,
+    ConsumerConfig, FailurePolicy, JsonSchema, RetryStrategy, SchemaType, SinkConnector,
+    SinkRecord, StartPosition,
+};
+use danube_client::{Consumer, DanubeClient};
+use danube_core::message::StreamMessage;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::{debug, error, info, warn};
+
+/// Interval of the periodic flush check, letting connectors flush time-based batches
+const FLUSH_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A subscribed consumer together with its message stream
+pub(super) struct ConsumerStream {
+    pub(super) topic: String,
+    /// Configuration the consumer was subscribed with
+    pub(super) config: ConsumerConfig,
+    /// Shared by the workers processing this consumer's messages
+    pub(super) consumer: Arc<Mutex<Consumer>>,
+    pub(super) stream: mpsc::Receiver<StreamMessage>,
+    /// Resolved dead-letter topic of this consumer
+    pub(super) dead_letter_topic: Option<String>,
+    /// Resolved failure policy of this consumer
+    pub(super) failure_policy: FailurePolicy,
+    /// Records of this consumer skipped before its start position
+    pub(super) start_filter: StartFilter,
+    /// Metrics labelled with this consumer's topic and subscription
+    pub(super) metrics: ConnectorMetrics,
+    /// Schema the payloads of this consumer are validated against
+    pub(super) json_schema: Option<JsonSchema>,
+    /// Schema type registered for the topic
+    pub(super) schema_type: Option<SchemaType>,
+}
+
+/// Records skipped before the start position of a consumer and the replay start
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct StartFilter {
+    /// First offset delivered
+    min_offset: u64,
+    /// First publish time delivered, in milliseconds since the Unix epoch
+    min_publish_time: u64,
+}
+
+impl StartFilter {
+    /// Resolve a start position and `processing.replay_from_ms` for a consumer that
+    /// subscribed at `subscribed_at` (milliseconds since the Unix epoch)
+    pub(super) fn new(
+        position: Option<StartPosition>,
+        replay_from: Option<u64>,
+        subscribed_at: u64,
+    ) -> Self {
+        let mut filter = match position {
+            None | Some(StartPosition::Earliest) => Self::default(),
+            Some(StartPosition::Latest) => Self {
+                min_offset: 0,
+                min_publish_time: subscribed_at,
+            },
+            Some(StartPosition::Offset(offset)) => Self {
+                min_offset: offset,
+                min_publish_time: 0,
+            },
+            Some(StartPosition::PublishTime(publish_time)) => Self {
+                min_offset: 0,
+                min_publish_time: publish_time,
+            },
+        };
+        if let Some(replay_from) = replay_from {
+            filter.min_publish_time = filter.min_publish_time.max(replay_from);
+        }
+        filter
+    }
+
+    /// Whether a message lies before the start position
+    fn skips(&self, msg: &StreamMessage) -> bool {
+        msg.msg_id.topic_offset < self.min_offset || msg.publish_time < self.min_publish_time
+    }
+}
+
+/// Settings shared by all workers of a sink runtime
+#[derive(Clone)]
+pub(super) struct WorkerContext {
+    pub(super) client: DanubeClient,
+    pub(super) connector_name: String,
+    pub(super) retry_strategy: RetryStrategy,
+    /// Circuit breaker shared by all workers, as they call the same external system
+    pub(super) circuit_breaker: Option<Arc<CircuitBreaker>>,
+    /// Whether acknowledgements wait for `SinkConnector::flushed_offsets()`
+    pub(super) deferred_ack: bool,
+    pub(super) batch_size: usize,
+    pub(super) batch_timeout: Duration,
+    pub(super) shutdown: watch::Receiver<bool>,
+    /// Signals shutdown to all workers when one stops on a failed record
+    pub(super) shutdown_trigger: watch::Sender<bool>,
+    /// Health state fed by the health checks of the first worker
+    pub(super) health: Arc<RuntimeHealth>,
+    pub(super) health_check_interval: Duration,
+    /// Connector-wide metrics, carrying the health gauge
+    pub(super) metrics: Arc<ConnectorMetrics>,
+    /// Transforms applied to every record before it is delivered to the connector
+    pub(super) transforms: TransformChain<SinkRecord>,
+}
+
+/// Input of a sink worker
+pub(super) enum WorkerInput {
+    /// A message of the consumer in a slot
+    Message(usize, StreamMessage),
+    /// A consumer attached at runtime, taking the next slot
+    Attach(Box<ConsumerSlot>),
+    /// A consumer about to be closed: its buffered batch is delivered and its slot removed,
+    /// answered once done
+    Detach(usize, oneshot::Sender<()>),
+    /// A reloaded configuration for the connector instance, answered with the result of
+    /// `SinkConnector::reconfigure()` and the new `consumer_configs()`
+    Reconfigure(
+        Arc<str>,
+        oneshot::Sender<ConnectorResult<Vec<ConsumerConfig>>>,
+    ),
+}
+
+/// Destination of a consumer's messages: a worker channel and the consumer's slot in it
+#[derive(Clone)]
+pub(super) struct Route {
+    pub(super) sender: mpsc::Sender<WorkerInput>,
+    pub(super) slot: usize,
+}
+
+/// Forward the messages of a consumer stream to its workers
+///
+/// With several routes each message goes to the route selected by its routing key, see
+/// `route_index()`. The task ends when the stream or all workers are closed.
+pub(super) fn spawn_forwarder(
+    mut stream: mpsc::Receiver<StreamMessage>,
+    routes: Vec<Route>,
+    key_attribute: Option<String>,
+) {
+    tokio::spawn(async move {
+        while let Some(msg) = stream.recv().await {
+            let route = &routes[route_index(&msg, key_attribute.as_deref(), routes.len())];
+            if route
+                .sender
+                .send(WorkerInput::Message(route.slot, msg))
+                .await
+                .is_err()
+            {
+                // Worker stopped
+                break;
+            }
+        }
+    });
+}
+
+/// Index of the route for a message, the same for all messages with the same routing key
+///
+/// The routing key is the `key_attribute` attribute of the message, or its producer name
+/// if no attribute is configured or the message lacks it.
+fn route_index(msg: &StreamMessage, key_attribute: Option<&str>, routes: usize) -> usize {
+    if routes <= 1 {
+        return 0;
+    }
+
+    let key = key_attribute
+        .and_then(|attribute| msg.attributes.get(attribute))
+        .unwrap_or(&msg.producer_name);
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % routes as u64) as usize
+}
+
+/// Processing state of a consumer assigned to a worker
+pub(super) struct ConsumerSlot {
+    topic: String,
+    consumer: Arc<Mutex<Consumer>>,
+    dead_letter_topic: Option<String>,
+    failure_policy: FailurePolicy,
+    start_filter: StartFilter,
+    metrics: ConnectorMetrics,
+    json_schema: Option<JsonSchema>,
+    schema_type: Option<SchemaType>,
+    /// Received messages waiting to be delivered to the connector as a batch
+    batch: Batcher<StreamMessage>,
+    /// Processed messages waiting for the connector to report them as flushed
+    /// (only used with `AckMode::Deferred`)
+    pending: Vec<StreamMessage>,
+}
+
+impl ConsumerSlot {
+    /// Create the processing state of a consumer for a worker
+    pub(super) fn new(consumer_stream: &ConsumerStream, context: &WorkerContext) -> Self {
+        Self {
+            topic: consumer_stream.topic.clone(),
+            consumer: consumer_stream.consumer.clone(),
+            dead_letter_topic: consumer_stream.dead_letter_topic.clone(),
+            failure_policy: consumer_stream.failure_policy,
+            start_filter: consumer_stream.start_filter,
+            metrics: consumer_stream.metrics.clone(),
+            json_schema: consumer_stream.json_schema.clone(),
+            schema_type: consumer_stream.schema_type,
+            batch: Batcher::new(context.batch_size, context.batch_timeout),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Acknowledge a processed message, or keep it pending until flushed
+    async fn complete(&mut self, msg: StreamMessage, deferred_ack: bool) {
+        if deferred_ack {
+            // Acknowledged once the connector reports it as flushed
+            self.pending.push(msg);
+        } else if let Err(e) = self.consumer.lock().await.ack(&msg).await {
+            error!("Failed to acknowledge message: {}", e);
+        } else {
+            self.metrics.record_success();
+        }
+    }
+
+    /// The record of a received message
+    fn record(&self, msg: &StreamMessage) -> SinkRecord {
+        let mut record = SinkRecord::from_stream_message(msg.clone(), None);
+        record.danube_metadata.schema_type = self.schema_type;
+        record
+    }
+
+    /// Check the transformed records of a message against the consumer's JSON schema
+    fn validate(&self, records: Vec<SinkRecord>) -> ConnectorResult<Vec<SinkRecord>> {
+        if let Some(schema) = &self.json_schema {
+            for record in &records {
+                schema.validate(record.payload())?;
+            }
+        }
+        Ok(records)
+    }
+
+    /// Acknowledge a message before the start position without processing it
+    async fn skip(&mut self, msg: &StreamMessage) {
+        debug!(
+            "Skipping message from topic {} before start position: offset={}",
+            self.topic, msg.msg_id.topic_offset
+        );
+        if let Err(e) = self.consumer.lock().await.ack(msg).await {
+            error!("Failed to acknowledge message: {}", e);
+        } else {
+            self.metrics.record_skipped();
+        }
+    }
+
+    /// Acknowledge a message dropped by a transform without processing it
+    async fn drop_filtered(&mut self, msg: &StreamMessage) {
+        debug!(
+            "Message from topic {} dropped by transform: offset={}",
+            self.topic, msg.msg_id.topic_offset
+        );
+        if let Err(e) = self.consumer.lock().await.ack(msg).await {
+            error!("Failed to acknowledge message: {}", e);
+        } else {
+            self.metrics.record_filtered();
+        }
+    }
+
+    /// Acknowledge pending messages of `topic` up to and including `offset`
+    ///
+    /// Returns the number of acknowledged messages.
+    async fn ack_up_to(&mut self, topic: &str, offset: u64) -> usize {
+        let (ready, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|msg| msg.msg_id.topic_name == topic && msg.msg_id.topic_offset <= offset);
+        self.pending = pending;
+
+        let mut acked = 0;
+        for msg in &ready {
+            match self.consumer.lock().await.ack(msg).await {
+                Ok(_) => acked += 1,
+                Err(e) => error!("Failed to acknowledge message: {}", e),
+            }
+        }
+        acked
+    }
+
+    /// Handle a record that failed processing, according to the consumer's failure policy
+    ///
+    /// Invalid records are republished to the consumer's dead-letter topic, if any, and
+    /// acknowledged; without a dead-letter topic they are skipped (acknowledged). See
+    /// `effective_policy()` for the policy other records fall under. Returns a fatal error,
+    /// leaving the record unacknowledged, if the policy stops the runtime.
+    async fn handle_failure(
+        &mut self,
+        dead_letters: &mut DeadLetterPublisher,
+        msg: &StreamMessage,
+        record: &SinkRecord,
+        error: &ConnectorError,
+    ) -> ConnectorResult<()> {
+        let policy = effective_policy(self.failure_policy, self.dead_letter_topic.is_some(), error);
+
+        match policy {
+            FailurePolicy::DeadLetter => match &self.dead_letter_topic {
+                Some(dlq_topic) => {
+                    let attributes = dead_letter_attributes(record, error);
+                    let payload = record.payload().to_vec();
+                    if let Err(e) = dead_letters.publish(dlq_topic, payload, attributes).await {
+                        error!(
+                            "Failed to publish message from topic {} (offset {}) to dead-letter topic {}: {}",
+                            record.topic(),
+                            record.offset(),
+                            dlq_topic,
+                            e
+                        );
+                        return Ok(());
+                    }
+                    self.metrics.record_dead_letter();
+                    warn!(
+                        "Sent message from topic {} (offset {}) to dead-letter topic {}: {}",
+                        record.topic(),
+                        record.offset(),
+                        dlq_topic,
+                        error
+                    );
+                }
+                // Only invalid records are dead-lettered without a dead-letter topic
+                None => {
+                    warn!("Skipping invalid message: {}", error);
+                }
+            },
+            FailurePolicy::Skip => {
+                warn!(
+                    "Skipping message from topic {} (offset {}) after retries: {}",
+                    record.topic(),
+                    record.offset(),
+                    error
+                );
+            }
+            FailurePolicy::RetryForever if error.is_retryable() => {
+                // Retrying only ends on shutdown, the message is redelivered after restart
+                info!(
+                    "Leaving message from topic {} (offset {}) unacknowledged for redelivery",
+                    record.topic(),
+                    record.offset()
+                );
+                return Ok(());
+            }
+            FailurePolicy::RetryForever | FailurePolicy::Stop => {
+                return Err(ConnectorError::fatal(format!(
+                    "Stopping on failed message from topic {} (offset {}): {}",
+                    record.topic(),
+                    record.offset(),
+                    error
+                )));
+            }
+        }
+
+        if let Err(e) = self.consumer.lock().await.ack(msg).await {
+            error!("Failed to acknowledge message: {}", e);
+        }
+        Ok(())
+    }
+}
+
+/// Processes the consumers assigned to it on one connector instance
+pub(super) struct SinkWorker {
+    id: usize,
+    context: WorkerContext,
+    /// Consumer slots by index, detached slots are removed and their index is not reused
+    slots: BTreeMap<usize, ConsumerSlot>,
+    /// Index of the next consumer slot
+    next_slot: usize,
+    /// Messages routed to this worker, tagged with the index of their slot
+    messages: mpsc::Receiver<WorkerInput>,
+    dead_letters: DeadLetterPublisher,
+}
+
+impl SinkWorker {
+    /// Create a worker without consumers, receiving its messages from `messages`
+    pub(super) fn new(
+        id: usize,
+        context: WorkerContext,
+        messages: mpsc::Receiver<WorkerInput>,
+    ) -> Self {
+        let dead_letters = DeadLetterPublisher::new(
+            context.client.clone(),
+            format!("{}-{}-dlq", context.connector_name, id),
+        );
+
+        Self {
+            id,
+            context,
+            slots: BTreeMap::new(),
+            next_slot: 0,
+            messages,
+            dead_letters,
+        }
+    }
+
+    /// Assign a consumer to this worker and return the index of its slot
+    pub(super) fn add_consumer(&mut self, consumer_stream: &ConsumerStream) -> usize {
+        let slot = ConsumerSlot::new(consumer_stream, &self.context);
+        self.insert_slot(slot)
+    }
+
+    /// Number of consumer slots assigned to this worker so far
+    pub(super) fn slot_count(&self) -> usize {
+        self.next_slot
+    }
+
+    /// Take the next slot for a consumer and return its index
+    fn insert_slot(&mut self, slot: ConsumerSlot) -> usize {
+        let idx = self.next_slot;
+        self.slots.insert(idx, slot);
+        self.next_slot += 1;
+        idx
+    }
+
+    /// Indices of the current consumer slots
+    fn slot_indices(&self) -> Vec<usize> {
+        self.slots.keys().copied().collect()
+    }
+
+    /// Process messages until shutdown is signalled or all forwarders are closed
+    ///
+    /// Messages are accumulated per consumer and delivered to `process_batch()` once
+    /// `processing.batch_size` messages are buffered or `processing.batch_timeout_ms`
+    /// elapsed since the first buffered message. The connector is shut down afterwards.
+    ///
+    /// A record failing under `FailurePolicy::Stop` stops the worker with its fatal error
+    /// and signals shutdown to the other workers.
+    pub(super) async fn run<C: SinkConnector>(mut self, connector: &mut C) -> ConnectorResult<()> {
+        info!(
+            "Sink worker {} processing {} consumer(s)",
+            self.id,
+            self.slots.len()
+        );
+
+        let mut flush_check = tokio::time::interval(FLUSH_CHECK_INTERVAL);
+        flush_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        // Connector instances share their external systems, the first worker checks them
+        let checks_health = self.id == 0;
+        let mut health_check = tokio::time::interval(self.context.health_check_interval);
+        health_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut stopped = Ok(());
+        'run: loop {
+            let deadline = self
+                .slots
+                .values()
+                .filter_map(|slot| slot.batch.deadline())
+                .min()
+                .map(Instant::from_std);
+
+            tokio::select! {
+                _ = self.context.shutdown.changed() => break,
+                received = self.messages.recv() => {
+                    let (idx, msg) = match received {
+                        Some(WorkerInput::Message(idx, msg)) => (idx, msg),
+                        Some(WorkerInput::Attach(slot)) => {
+                            info!(
+                                "Sink worker {} attached consumer for topic {}",
+                                self.id, slot.topic
+                            );
+                            self.insert_slot(*slot);
+                            continue;
+                        }
+                        Some(WorkerInput::Detach(idx, reply)) => {
+                            if let Err(e) = self.detach(connector, idx).await {
+                                stopped = Err(e);
+                                break;
+                            }
+                            let _ = reply.send(());
+                            continue;
+                        }
+                        Some(WorkerInput::Reconfigure(config, reply)) => {
+                            match self.reconfigure(connector, &config).await {
+                                Ok(result) => {
+                                    let _ = reply.send(result);
+                                }
+                                Err(e) => {
+                                    stopped = Err(e);
+                                    break;
+                                }
+                            }
+                            continue;
+                        }
+                        None => {
+                            info!("All consumer streams of sink worker {} closed", self.id);
+                            break;
+                        }
+                    };
+
+                    let Some(slot) = self.slots.get_mut(&idx) else {
+                        // Left unacknowledged, redelivered to the next consumer of the topic
+                        debug!("Dropping message of detached consumer slot {}", idx);
+                        continue;
+                    };
+                    slot.metrics.record_received();
+                    if slot.start_filter.skips(&msg) {
+                        slot.skip(&msg).await;
+                        continue;
+                    }
+                    debug!(
+                        "Received message from topic {}: offset={}",
+                        slot.topic, msg.msg_id.topic_offset
+                    );
+                    slot.batch.add(msg);
+
+                    // Deliver the batch once it is full
+                    if slot.batch.should_flush() {
+                        if let Err(e) = self.deliver_batch(connector, idx).await {
+                            stopped = Err(e);
+                            break;
+                        }
+                        self.ack_flushed(connector).await;
+                    }
+                }
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    // Deliver the batches whose timeout elapsed
+                    for idx in self.slot_indices() {
+                        if self.slots[&idx].batch.should_flush() {
+                            if let Err(e) = self.deliver_batch(connector, idx).await {
+                                stopped = Err(e);
+                                break 'run;
+                            }
+                        }
+                    }
+                    self.ack_flushed(connector).await;
+                }
+                _ = flush_check.tick(), if !self.circuit_open() => {
+                    if let Err(e) = connector.process_batch(vec![]).await {
+                        error!("Error during periodic flush check: {}", e);
+                    }
+                    self.ack_flushed(connector).await;
+                }
+                _ = health_check.tick(), if checks_health => {
+                    let result = connector.health_check().await;
+                    self.context.health.record_check(&result, &self.context.metrics);
+                }
+            }
+        }
+
+        if let Err(e) = &stopped {
+            error!("Sink worker {} stopping: {}", self.id, e);
+            self.context.shutdown_trigger.send_replace(true);
+        }
+        self.shutdown(connector, stopped.is_ok()).await?;
+        stopped
+    }
+
+    /// Deliver the remaining batches and shut down the connector instance
+    ///
+    /// With `AckMode::Deferred`, records flushed by the connector during shutdown are
+    /// acknowledged before the consumers are dropped. A stopped worker leaves its remaining
+    /// messages unacknowledged, to be redelivered in order.
+    async fn shutdown<C: SinkConnector>(
+        mut self,
+        connector: &mut C,
+        deliver_remaining: bool,
+    ) -> ConnectorResult<()> {
+        // Deliver messages still waiting for a full batch
+        if deliver_remaining {
+            for idx in self.slot_indices() {
+                if let Err(e) = self.deliver_batch(connector, idx).await {
+                    error!("Sink worker {} stopping: {}", self.id, e);
+                    break;
+                }
+            }
+        }
+
+        info!("Shutting down connector of sink worker {}", self.id);
+        connector.shutdown().await?;
+        if self.context.deferred_ack {
+            self.ack_flushed(connector).await;
+
+            let unacked: usize = self.slots.values().map(|slot| slot.pending.len()).sum();
+            if unacked > 0 {
+                warn!(
+                    "{} processed message(s) were not flushed and will be redelivered",
+                    unacked
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply a reloaded configuration to the connector instance
+    ///
+    /// Buffered batches are delivered with the old configuration first. Returns the result
+    /// of the reconfiguration with the connector's new consumer configurations, or the
+    /// fatal error of a failure policy stopping the worker while delivering.
+    async fn reconfigure<C: SinkConnector>(
+        &mut self,
+        connector: &mut C,
+        config: &str,
+    ) -> ConnectorResult<ConnectorResult<Vec<ConsumerConfig>>> {
+        for idx in self.slot_indices() {
+            self.deliver_batch(connector, idx).await?;
+        }
+        self.ack_flushed(connector).await;
+
+        let result = match connector.reconfigure(config).await {
+            Ok(()) => connector.consumer_configs().await,
+            Err(e) => Err(e),
+        };
+        match &result {
+            Ok(_) => info!("Sink worker {} reconfigured its connector", self.id),
+            Err(e) => warn!("Sink worker {} rejected reloaded config: {}", self.id, e),
+        }
+        Ok(result)
+    }
+
+    /// Deliver the buffered batch of a consumer about to be closed and remove its slot
+    ///
+    /// With `AckMode::Deferred`, its records flushed by now are acknowledged; the others are
+    /// redelivered to the next consumer of the topic. Returns the fatal error of a failure
+    /// policy stopping the worker while delivering.
+    async fn detach<C: SinkConnector>(
+        &mut self,
+        connector: &mut C,
+        idx: usize,
+    ) -> ConnectorResult<()> {
+        self.deliver_batch(connector, idx).await?;
+        self.ack_flushed(connector).await;
+
+        if let Some(slot) = self.slots.remove(&idx) {
+            if !slot.pending.is_empty() {
+                warn!(
+                    "{} processed message(s) from topic {} were not flushed and will be redelivered",
+                    slot.pending.len(),
+                    slot.topic
+                );
+            }
+            info!(
+                "Sink worker {} detached consumer for topic {}",
+                self.id, slot.topic
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the circuit breaker currently pauses calls to the connector
+    fn circuit_open(&self) -> bool {
+        self.context
+            .circuit_breaker
+            .as_ref()
+            .is_some_and(|breaker| breaker.state() == CircuitState::Open)
+    }
+
+    /// Deliver the buffered messages of a consumer to the connector as one batch
+    ///
+    /// The records go through the transforms first: dropped records are acknowledged and
+    /// records failing a transform, or the consumer's JSON schema, are handled by the
+    /// failure policy. Failed messages are dead-lettered with their original payload.
+    ///
+    /// On success every message of the batch is acknowledged. If the batch is rejected as
+    /// invalid, its messages are reprocessed one by one, with all records a transform split
+    /// them into, so that only the invalid ones are skipped or dead-lettered; any other
+    /// failure applies to the whole batch. Returns the fatal error of a failure policy
+    /// stopping the worker.
+    async fn deliver_batch<C: SinkConnector>(
+        &mut self,
+        connector: &mut C,
+        idx: usize,
+    ) -> ConnectorResult<()> {
+        let context = &self.context;
+        let Some(slot) = self.slots.get_mut(&idx) else {
+            return Ok(());
+        };
+        let retry_forever = slot.failure_policy == FailurePolicy::RetryForever;
+
+        let messages = slot.batch.flush();
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        slot.metrics.record_batch_size(messages.len());
+
+        // Transformed messages with the range of their records
+        let mut transformed = Vec::with_capacity(messages.len());
+        let mut records = Vec::with_capacity(messages.len());
+        for msg in messages {
+            match context
+                .transforms
+                .apply_to_message(slot.record(&msg))
+                .and_then(|split| slot.validate(split))
+            {
+                Ok(split) if split.is_empty() => slot.drop_filtered(&msg).await,
+                Ok(split) => {
+                    let start = records.len();
+                    records.extend(split);
+                    transformed.push((msg, start..records.len()));
+                }
+                Err(e) => {
+                    let record = slot.record(&msg);
+                    slot.metrics.record_error(e.kind());
+                    slot.handle_failure(&mut self.dead_letters, &msg, &record, &e)
+                        .await?;
+                }
+            }
+        }
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        debug!(
+            "Processing batch of {} messages from topic {}",
+            records.len(),
+            slot.topic
+        );
+
+        match process_with_retry(connector, context, &records, &slot.metrics, retry_forever).await {
+            Ok(_) => {
+                for (msg, _) in transformed {
+                    slot.complete(msg, context.deferred_ack).await;
+                }
+            }
+            Err(e) if e.is_invalid_data() && records.len() > 1 => {
+                warn!(
+                    "Batch from topic {} contains invalid data ({}), processing its {} messages individually",
+                    slot.topic,
+                    e,
+                    transformed.len()
+                );
+                for (msg, range) in transformed {
+                    match process_with_retry(
+                        connector,
+                        context,
+                        &records[range],
+                        &slot.metrics,
+                        retry_forever,
+                    )
+                    .await
+                    {
+                        Ok(_) => slot.complete(msg, context.deferred_ack).await,
+                        Err(e) => {
+                            let record = slot.record(&msg);
+                            slot.metrics.record_error(e.kind());
+                            slot.handle_failure(&mut self.dead_letters, &msg, &record, &e)
+                                .await?;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                for (msg, _) in &transformed {
+                    let record = slot.record(msg);
+                    slot.metrics.record_error(e.kind());
+                    slot.handle_failure(&mut self.dead_letters, msg, &record, &e)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Acknowledge pending messages up to the watermarks reported by the connector
+    async fn ack_flushed<C: SinkConnector>(&mut self, connector: &mut C) {
+        if !self.context.deferred_ack {
+            return;
+        }
+
+        let watermarks = match connector.flushed_offsets().await {
+            Ok(watermarks) => watermarks,
+            Err(e) => {
+                error!("Failed to get flushed offsets from connector: {}", e);
+                return;
+            }
+        };
+
+        for watermark in watermarks {
+            for slot in self.slots.values_mut() {
+                let acked = slot.ack_up_to(&watermark.partition, watermark.value).await;
+                for _ in 0..acked {
+                    slot.metrics.record_success();
+                }
+                if acked > 0 {
+                    debug!(
+                        "Acknowledged {} flushed message(s) from topic {} up to offset {}",
+                        acked, watermark.partition, watermark.value
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Failure policy applied to a record failing with `error`
+///
+/// Invalid records are always dead-lettered (skipped without a dead-letter topic). Other
+/// records are only dead-lettered when the consumer has a dead-letter topic: without one,
+/// `DeadLetter` stops the runtime, leaving the record for redelivery. Under
+/// `RetryForever`, errors that cannot be retried are dead-lettered too when the consumer
+/// has a dead-letter topic, and stop the runtime otherwise.
+fn effective_policy(
+    policy: FailurePolicy,
+    has_dead_letter_topic: bool,
+    error: &ConnectorError,
+) -> FailurePolicy {
+    match policy {
+        _ if error.is_invalid_data() => FailurePolicy::DeadLetter,
+        FailurePolicy::DeadLetter if !has_dead_letter_topic => FailurePolicy::Stop,
+        FailurePolicy::RetryForever if !error.is_retryable() && has_dead_letter_topic => {
+            FailurePolicy::DeadLetter
+        }
+        policy => policy,
+    }
+}
+
+/// Process a batch of records with retry logic
+///
+/// With a circuit breaker, calls wait while the circuit is open. Records failing while
+/// the circuit opens are held and retried once it lets calls through again, so an outage
+/// of the external system pauses processing instead of exhausting the retries.
+///
+/// With `retry_forever`, retryable failures are retried until they succeed or shutdown is
+/// signalled.
+async fn process_with_retry<C: SinkConnector>(
+    connector: &mut C,
+    context: &WorkerContext,
+    records: &[SinkRecord],
+    metrics: &ConnectorMetrics,
+    retry_forever: bool,
+) -> ConnectorResult<()> {
+    let retry_strategy = &context.retry_strategy;
+    let circuit_breaker = context.circuit_breaker.as_deref();
+    let start = Instant::now();
+    let mut attempt = 0;
+
+    loop {
+        if let Some(breaker) = circuit_breaker {
+            breaker.acquire().await;
+        }
+
+        let result = connector.process_batch(records.to_vec()).await;
+        if let Some(breaker) = circuit_breaker {
+            // Only retryable errors indicate that the external system is unavailable
+            match &result {
+                Err(e) if e.is_retryable() => breaker.record_failure(),
+                _ => breaker.record_success(),
+            }
+        }
+
+        match result {
+            Ok(_) => {
+                let duration = start.elapsed();
+                metrics.record_processing_time(duration);
+                return Ok(());
+            }
+            Err(e)
+                if e.is_retryable()
+                    && circuit_breaker.is_some_and(|b| b.state() != CircuitState::Closed) =>
+            {
+                warn!(
+                    "Circuit open, holding {} record(s) until the external system recovers - error: {}",
+                    records.len(),
+                    e
+                );
+                metrics.record_retry();
+                attempt = 0;
+            }
+            Err(e)
+                if e.is_retryable() && (retry_forever || retry_strategy.should_retry(attempt)) =>
+            {
+                attempt = attempt.saturating_add(1);
+                metrics.record_retry();
+
+                let backoff = retry_strategy.calculate_backoff(attempt);
+                warn!(
+                    "Retry attempt {} after {:?} - error: {}",
+                    attempt, backoff, e
+                );
+
+                if retry_forever {
+                    let mut shutdown = context.shutdown.clone();
+                    tokio::select! {
+                        _ = tokio::time::sleep(backoff) => {}
+                        _ = shutdown.wait_for(|&stop| stop) => return Err(e),
+                    }
+                } else {
+                    tokio::time::sleep(backoff).await;
+                }
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Attributes of a dead-letter message: the original attributes plus the failure context
+fn dead_letter_attributes(record: &SinkRecord, error: &ConnectorError) -> HashMap<String, String> {
+    let mut attributes = record.attributes().clone();
+    attributes.insert("dlq.source_topic".to_string(), record.topic().to_string());
+    attributes.insert("dlq.source_offset".to_string(), record.offset().to_string());
+    attributes.insert("dlq.error_kind".to_string(), error.kind().to_string());
+    attributes.insert("dlq.error_message".to_string(), error.to_string());
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::DanubeMetadata;
+    use crate::ConnectorConfig;
+    use async_trait::async_trait;
+    use danube_core::message::MessageID;
+
+    /// Connector recording the offsets of the records it processed
+    struct RecordingSink {
+        processed: Arc<std::sync::Mutex<Vec<u64>>>,
+        /// Fail every record with a fatal error after recording it
+        fails: bool,
+    }
+
+    #[async_trait]
+    impl SinkConnector for RecordingSink {
+        async fn initialize(&mut self, _config: ConnectorConfig) -> ConnectorResult<()> {
+            Ok(())
+        }
+
+        async fn consumer_configs(&self) -> ConnectorResult<Vec<ConsumerConfig>> {
+            Ok(Vec::new())
+        }
+
+        async fn process(&mut self, record: SinkRecord) -> ConnectorResult<()> {
+            self.processed.lock().unwrap().push(record.offset());
+            if self.fails {
+                return Err(ConnectorError::fatal("permission denied"));
+            }
+            Ok(())
+        }
+    }
+
+    fn message(producer_name: &str, attributes: &[(&str, &str)]) -> StreamMessage {
+        StreamMessage {
+            request_id: 1,
+            msg_id: MessageID {
+                producer_id: 1,
+                topic_name: "/default/orders".to_string(),
+                broker_addr: "localhost:6650".to_string(),
+                topic_offset: 0,
+            },
+            payload: Vec::new(),
+            publish_time: 0,
+            producer_name: producer_name.to_string(),
+            subscription_name: None,
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_route_index_by_key() {
+        // The same key always goes to the same route, regardless of the producer
+        let first = message("producer-a", &[("record_id", "order-1")]);
+        let second = message("producer-b", &[("record_id", "order-1")]);
+        let route = route_index(&first, Some("record_id"), 8);
+        assert!(route < 8);
+        assert_eq!(route_index(&second, Some("record_id"), 8), route);
+
+        // Keys spread over the routes
+        let routes: std::collections::HashSet<_> = (0..100)
+            .map(|i| {
+                let key = format!("order-{}", i);
+                route_index(
+                    &message("producer-a", &[("record_id", &key)]),
+                    Some("record_id"),
+                    8,
+                )
+            })
+            .collect();
+        assert!(routes.len() > 1);
+    }
+
+    #[test]
+    fn test_route_index_falls_back_to_producer_name() {
+        let without_key = message("producer-a", &[]);
+        let by_producer = route_index(&without_key, None, 8);
+        assert_eq!(route_index(&without_key, Some("record_id"), 8), by_producer);
+        assert_eq!(route_index(&without_key, Some("record_id"), 1), 0);
+    }
+
+    #[test]
+    fn test_start_filter() {
+        let at = |offset: u64, publish_time: u64| {
+            let mut msg = message("producer-a", &[]);
+            msg.msg_id.topic_offset = offset;
+            msg.publish_time = publish_time;
+            msg
+        };
+
+        let earliest = StartFilter::new(Some(StartPosition::Earliest), None, 5_000);
+        assert!(!earliest.skips(&at(0, 0)));
+
+        let latest = StartFilter::new(Some(StartPosition::Latest), None, 5_000);
+        assert!(latest.skips(&at(10, 4_999)));
+        assert!(!latest.skips(&at(0, 5_000)));
+
+        let offset = StartFilter::new(Some(StartPosition::Offset(10)), None, 5_000);
+        assert!(offset.skips(&at(9, 9_000)));
+        assert!(!offset.skips(&at(10, 0)));
+
+        // The replay start applies on top of the start position
+        let replay = StartFilter::new(Some(StartPosition::Offset(10)), Some(2_000), 5_000);
+        assert!(replay.skips(&at(20, 1_999)));
+        assert!(!replay.skips(&at(20, 2_000)));
+        let replay = StartFilter::new(Some(StartPosition::PublishTime(3_000)), Some(2_000), 5_000);
+        assert!(replay.skips(&at(20, 2_500)));
+    }
+
+    #[test]
+    fn test_dead_letter_attributes() {
+        let record = SinkRecord {
+            payload: b"not-a-vector".to_vec(),
+            attributes: HashMap::from([("source".to_string(), "sensor-1".to_string())]),
+            danube_metadata: DanubeMetadata {
+                topic: "/default/embeddings".to_string(),
+                offset: 42,
+                publish_time: 0,
+                message_id: "topic:/default/embeddings/producer:1/offset:42".to_string(),
+                producer_name: "producer".to_string(),
+                schema_type: None,
+                split_index: 0,
+                split_count: 1,
+            },
+            partition: None,
+        };
+        let error = ConnectorError::invalid_data("missing vector", vec![]);
+
+        let attributes = dead_letter_attributes(&record, &error);
+        assert_eq!(attributes["source"], "sensor-1");
+        assert_eq!(attributes["dlq.source_topic"], "/default/embeddings");
+        assert_eq!(attributes["dlq.source_offset"], "42");
+        assert_eq!(attributes["dlq.error_kind"], "invalid_data");
+        assert_eq!(
+            attributes["dlq.error_message"],
+            "Invalid data: missing vector"
+        );
+    }
+
+    /// Worker context and the stream of a consumer that was never subscribed, so its
+    /// acknowledgements succeed without a broker
+    async fn fixture() -> (WorkerContext, ConsumerStream) {
+        let client = DanubeClient::builder()
+            .service_url("http://localhost:6650")
+            .build()
+            .await
+            .unwrap();
+        let (shutdown_trigger, shutdown) = watch::channel(false);
+        let context = WorkerContext {
+            client: client.clone(),
+            connector_name: "test".to_string(),
+            retry_strategy: RetryStrategy::default(),
+            circuit_breaker: None,
+            deferred_ack: false,
+            batch_size: 10,
+            batch_timeout: Duration::from_secs(60),
+            shutdown: shutdown.clone(),
+            shutdown_trigger,
+            health: Arc::new(RuntimeHealth::new(3, shutdown)),
+            health_check_interval: Duration::from_secs(60),
+            metrics: Arc::new(ConnectorMetrics::new("test", "/default/orders")),
+            transforms: TransformChain::for_sink(&[]).unwrap(),
+        };
+        let consumer = client
+            .new_consumer()
+            .with_topic("/default/orders")
+            .with_consumer_name("test-consumer")
+            .with_subscription("test-subscription")
+            .build();
+        let (_, stream) = mpsc::channel(1);
+        let consumer_stream = ConsumerStream {
+            topic: "/default/orders".to_string(),
+            config: ConsumerConfig {
+                topic: "/default/orders".to_string(),
+                consumer_name: "test-consumer".to_string(),
+                subscription: "test-subscription".to_string(),
+                ..Default::default()
+            },
+            consumer: Arc::new(Mutex::new(consumer)),
+            stream,
+            dead_letter_topic: None,
+            failure_policy: FailurePolicy::default(),
+            start_filter: StartFilter::default(),
+            metrics: ConnectorMetrics::new("test", "/default/orders"),
+            json_schema: None,
+            schema_type: None,
+        };
+
+        (context, consumer_stream)
+    }
+
+    fn at(offset: u64) -> StreamMessage {
+        let mut msg = message("producer-a", &[]);
+        msg.msg_id.topic_offset = offset;
+        msg
+    }
+
+    #[tokio::test]
+    async fn test_detach_and_reattach_consumer() {
+        let (context, consumer_stream) = fixture().await;
+        let (sender, messages) = mpsc::channel(16);
+        let processed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut connector = RecordingSink {
+            processed: processed.clone(),
+            fails: false,
+        };
+        let worker = SinkWorker::new(0, context.clone(), messages);
+        let run = tokio::spawn(async move { worker.run(&mut connector).await });
+
+        let send = |input: WorkerInput| {
+            let sender = sender.clone();
+            async move { sender.send(input).await.unwrap() }
+        };
+
+        // The buffered batch is delivered on detach, later messages of the slot are dropped
+        let slot = ConsumerSlot::new(&consumer_stream, &context);
+        send(WorkerInput::Attach(Box::new(slot))).await;
+        send(WorkerInput::Message(0, at(1))).await;
+        send(WorkerInput::Message(0, at(2))).await;
+        let (reply, detached) = oneshot::channel();
+        send(WorkerInput::Detach(0, reply)).await;
+        detached.await.unwrap();
+        assert_eq!(*processed.lock().unwrap(), vec![1, 2]);
+        send(WorkerInput::Message(0, at(3))).await;
+
+        // The re-attached consumer takes a new slot
+        let slot = ConsumerSlot::new(&consumer_stream, &context);
+        send(WorkerInput::Attach(Box::new(slot))).await;
+        send(WorkerInput::Message(1, at(3))).await;
+        let (reply, detached) = oneshot::channel();
+        send(WorkerInput::Detach(1, reply)).await;
+        detached.await.unwrap();
+
+        drop(sender);
+        run.await.unwrap().unwrap();
+        assert_eq!(*processed.lock().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_record_without_dead_letter_topic_stops_worker() {
+        let (context, consumer_stream) = fixture().await;
+        assert_eq!(consumer_stream.failure_policy, FailurePolicy::DeadLetter);
+        assert!(consumer_stream.dead_letter_topic.is_none());
+
+        let (sender, messages) = mpsc::channel(16);
+        let processed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut connector = RecordingSink {
+            processed: processed.clone(),
+            fails: true,
+        };
+        let worker = SinkWorker::new(0, context.clone(), messages);
+        let run = tokio::spawn(async move { worker.run(&mut connector).await });
+
+        let slot = ConsumerSlot::new(&consumer_stream, &context);
+        sender
+            .send(WorkerInput::Attach(Box::new(slot)))
+            .await
+            .unwrap();
+        sender.send(WorkerInput::Message(0, at(1))).await.unwrap();
+        let (reply, _detached) = oneshot::channel();
+        sender.send(WorkerInput::Detach(0, reply)).await.unwrap();
+
+        // The record is left unacknowledged and the runtime stops instead of dropping it
+        let stopped = run.await.unwrap();
+        assert!(matches!(stopped, Err(ConnectorError::Fatal { .. })));
+        assert_eq!(*processed.lock().unwrap(), vec![1]);
+        assert!(*context.shutdown.borrow());
+    }
+
+    #[test]
+    fn test_effective_policy() {
+        let retryable = ConnectorError::retryable("timeout");
+        let fatal = ConnectorError::fatal("permission denied");
+        let invalid = ConnectorError::invalid_data("missing vector", vec![]);
+
+        // Retry forever only holds records that can be retried
+        assert_eq!(
+            effective_policy(FailurePolicy::RetryForever, true, &retryable),
+            FailurePolicy::RetryForever
+        );
+        assert_eq!(
+            effective_policy(FailurePolicy::RetryForever, true, &fatal),
+            FailurePolicy::DeadLetter
+        );
+        assert_eq!(
+            effective_policy(FailurePolicy::RetryForever, false, &fatal),
+            FailurePolicy::RetryForever
+        );
+
+        // Dead-lettering needs a dead-letter topic, except for invalid records
+        assert_eq!(
+            effective_policy(FailurePolicy::DeadLetter, true, &fatal),
+            FailurePolicy::DeadLetter
+        );
+        assert_eq!(
+            effective_policy(FailurePolicy::DeadLetter, false, &retryable),
+            FailurePolicy::Stop
+        );
+        assert_eq!(
+            effective_policy(FailurePolicy::DeadLetter, false, &invalid),
+            FailurePolicy::DeadLetter
+        );
+
+        assert_eq!(
+            effective_policy(FailurePolicy::Stop, true, &invalid),
+            FailurePolicy::DeadLetter
+        );
+        assert_eq!(
+            effective_policy(FailurePolicy::Stop, true, &fatal),
+            FailurePolicy::Stop
+        );
+    }
+}
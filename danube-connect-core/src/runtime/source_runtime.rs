@@ -3,23 +3,34 @@
 //! Handles polling external systems and publishing messages to Danube topics with
 //! dynamic multi-producer management.
 
+use super::config_reload::spawn_config_watcher;
+use super::dead_letter::DeadLetterPublisher;
+use super::health::RuntimeHealth;
+use super::keyed_producer::{KeyedProducer, TopicSender};
+use super::shutdown::{drain_deadline, spawn_signal_handler};
+use crate::transform::TransformChain;
+use crate::utils::topic_matches;
 use crate::{
-    ConnectorConfig, ConnectorError, ConnectorMetrics, ConnectorResult, SourceConnector,
-    SourceRecord,
+    CircuitBreaker, CircuitBreakerConfig, ConnectorConfig, ConnectorError, ConnectorMetrics,
+    ConnectorResult, FileOffsetStore, JsonSchema, Offset, OffsetStore, OffsetStoreSettings,
+    SourceConnector, SourceRecord, TopicOffsetStore, TopicSchema, Transform,
 };
-use danube_client::{DanubeClient, Producer};
+use danube_client::DanubeClient;
+use futures::stream::{FuturesOrdered, StreamExt};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::{mpsc, watch};
 use tokio::time::Instant;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 /// Configuration for a Danube producer
 ///
 /// Specifies how to create a producer for a specific topic, including partitioning
-/// and reliability settings.
-#[derive(Debug, Clone)]
+/// and reliability settings. The default is a non-partitioned topic without reliable dispatch
+/// or schemas.
+#[derive(Debug, Clone, Default)]
 pub struct ProducerConfig {
     /// Danube topic name (format: /{namespace}/{topic_name})
     pub topic: String,
@@ -27,19 +38,47 @@ pub struct ProducerConfig {
     pub partitions: usize,
     /// Use reliable dispatch (WAL + Cloud persistence)
     pub reliable_dispatch: bool,
+    /// JSON Schema file the payloads must match
+    ///
+    /// Checked after the transforms and before publishing. Records that don't match are
+    /// invalid: they go to `processing.dead_letter_topic` if set and are dropped otherwise.
+    pub json_schema: Option<String>,
+    /// Schema registered for the topic in Danube's schema registry
+    ///
+    /// Registered under the topic name when the producer is created, so sinks can pick
+    /// the schema type of the topic up. Without it the client registers its default
+    /// `String` schema.
+    pub schema: Option<TopicSchema>,
 }
 
 /// Runtime for Source Connectors (External System → Danube)
 ///
-/// Manages multiple producers for publishing to Danube topics. All producers are
-/// created upfront based on connector configuration.
+/// Manages multiple producers for publishing to Danube topics. Producers for the topics of
+/// `producer_configs()` are created upfront, producers for other topics allowed by
+/// `processing.allowed_topics` on first use.
 pub struct SourceRuntime<C: SourceConnector> {
     connector: C,
     client: DanubeClient,
-    producers: HashMap<String, Producer>, // topic -> producer
+    producers: HashMap<String, KeyedProducer>, // topic -> producer
     config: ConnectorConfig,
     metrics: Arc<ConnectorMetrics>,
-    shutdown: Arc<AtomicBool>,
+    topic_metrics: HashMap<String, ConnectorMetrics>, // topic -> metrics
+    json_schemas: HashMap<String, JsonSchema>,        // topic -> payload schema
+    dead_letters: DeadLetterPublisher,
+    offset_store: Option<Box<dyn OffsetStore>>,
+    circuit_breaker: Option<CircuitBreaker>,
+    shutdown: watch::Sender<bool>,
+    health: Arc<RuntimeHealth>,
+    config_path: Option<PathBuf>,
+    transforms: TransformChain<SourceRecord>,
+}
+
+/// A polled record on its way to Danube
+enum Outgoing {
+    /// A record and the partition of its topic it is sent to
+    Publish(Box<SourceRecord>, usize),
+    /// The source offset of a record dropped by the transforms or dead-lettered
+    Dropped(Option<Offset>),
 }
 
 impl<C: SourceConnector> SourceRuntime<C> {
@@ -62,50 +101,160 @@ impl<C: SourceConnector> SourceRuntime<C> {
             .await
             .map_err(|e| ConnectorError::fatal_with_source("Failed to create Danube client", e))?;
 
-        // Create metrics (topic will be set dynamically per producer)
-        let metrics = Arc::new(ConnectorMetrics::new(&config.connector_name, "multi-topic"));
+        let shutdown = watch::Sender::new(false);
+        let health = Arc::new(RuntimeHealth::new(
+            config.processing.health_failure_threshold,
+            shutdown.subscribe(),
+        ));
+
+        // Serve Prometheus metrics (the recorder must exist before metrics are registered)
+        #[cfg(feature = "prometheus")]
+        super::metrics_server::start_metrics_server(config.processing.metrics_port, health.clone())
+            .await?;
+
+        // Connector-wide metrics (per-topic metrics are created with the producers)
+        let metrics = Arc::new(ConnectorMetrics::for_connector(&config.connector_name));
         metrics.set_health(true);
 
+        // Create the configured offset store
+        let offset_store: Option<Box<dyn OffsetStore>> = match &config.offset_store {
+            OffsetStoreSettings::None => None,
+            OffsetStoreSettings::File { path } => {
+                info!("Storing offsets in file: {}", path);
+                Some(Box::new(FileOffsetStore::new(path)))
+            }
+            OffsetStoreSettings::Topic { topic } => {
+                info!("Storing offsets on topic: {}", topic);
+                Some(Box::new(TopicOffsetStore::new(
+                    client.clone(),
+                    topic,
+                    format!("{}-offsets", config.connector_name),
+                )))
+            }
+        };
+
+        // Create circuit breaker around polling the external system
+        let breaker = &config.circuit_breaker;
+        let circuit_breaker = breaker.enabled.then(|| {
+            CircuitBreaker::new(CircuitBreakerConfig::new(
+                breaker.failure_rate_threshold,
+                breaker.minimum_calls,
+                breaker.window_size,
+                breaker.open_duration_ms,
+            ))
+        });
+
+        // Create the configured transforms
+        let transforms = TransformChain::for_source(&config.transforms)?;
+        if !transforms.is_empty() {
+            info!(
+                "Applying {} transform(s) to polled records",
+                transforms.len()
+            );
+        }
+
+        let dead_letters =
+            DeadLetterPublisher::new(client.clone(), format!("{}-dlq", config.connector_name));
+
         Ok(Self {
             connector,
             client,
             producers: HashMap::new(), // Will be populated during initialization
             config,
             metrics,
-            shutdown: Arc::new(AtomicBool::new(false)),
+            topic_metrics: HashMap::new(),
+            json_schemas: HashMap::new(),
+            dead_letters,
+            offset_store,
+            circuit_breaker,
+            shutdown,
+            health,
+            config_path: None,
+            transforms,
         })
     }
 
+    /// Use a custom offset store instead of the one from `ConnectorConfig::offset_store`
+    pub fn with_offset_store(mut self, store: impl OffsetStore + 'static) -> Self {
+        self.offset_store = Some(Box::new(store));
+        self
+    }
+
+    /// Apply a custom transform to every record, after the configured `transforms`
+    pub fn with_transform(mut self, transform: impl Transform<SourceRecord> + 'static) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Reload the configuration file at `path` when it changes or on SIGHUP
+    ///
+    /// The new content is handed to `SourceConnector::reconfigure()` between polls, and
+    /// producers are created for new topics of `producer_configs()`. Runtime settings keep
+    /// their startup values.
+    pub fn with_config_reload(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
     /// Run the source connector
     pub async fn run(&mut self) -> ConnectorResult<()> {
         info!("Starting Source Runtime");
 
         // Setup shutdown handler
-        self.setup_shutdown_handler();
+        spawn_signal_handler(self.shutdown.clone());
 
-        // Initialize connector and create producers
+        // Restore stored offsets, then initialize connector and create producers
+        self.restore_offsets().await?;
         self.initialize_connector().await?;
         self.create_producers().await?;
+        self.health.set_started();
+
+        // Poll until shutdown, then finish in-flight publishes and shut down the connector
+        let shutdown_timeout = Duration::from_millis(self.config.processing.shutdown_timeout_ms);
+        let deadline = drain_deadline(self.shutdown.subscribe(), shutdown_timeout);
+        tokio::select! {
+            result = self.poll_and_shutdown() => result,
+            _ = deadline => {
+                self.metrics.set_health(false);
+                Err(ConnectorError::fatal(format!(
+                    "Source did not drain within the shutdown timeout of {} ms",
+                    shutdown_timeout.as_millis()
+                )))
+            }
+        }
+    }
 
-        // Main polling loop
+    /// Main polling loop followed by the graceful shutdown of the connector
+    async fn poll_and_shutdown(&mut self) -> ConnectorResult<()> {
         self.process_polling_loop().await?;
+        self.shutdown_connector().await
+    }
 
-        // Graceful shutdown
-        self.shutdown_connector().await?;
+    /// Load the stored offsets and hand them to the connector
+    async fn restore_offsets(&mut self) -> ConnectorResult<()> {
+        let Some(store) = self.offset_store.as_mut() else {
+            return Ok(());
+        };
 
-        Ok(())
+        let offsets = store.load().await?;
+        info!("Restoring {} stored offset(s)", offsets.len());
+        self.connector.restore_offsets(offsets).await
     }
 
-    /// Setup shutdown signal handler for SIGTERM/SIGINT
-    fn setup_shutdown_handler(&self) {
-        let shutdown = self.shutdown.clone();
-        tokio::spawn(async move {
-            tokio::signal::ctrl_c()
-                .await
-                .expect("Failed to listen for ctrl-c");
-            info!("Received shutdown signal");
-            shutdown.store(true, Ordering::Relaxed);
-        });
+    /// Commit published offsets to the connector and the offset store
+    async fn commit_offsets(&mut self, offsets: Vec<Offset>) {
+        let stored = self.offset_store.is_some().then(|| offsets.clone());
+        if let Err(e) = self.connector.commit(offsets).await {
+            error!("Failed to commit offsets: {}", e);
+            return;
+        }
+
+        if let (Some(store), Some(offsets)) = (self.offset_store.as_mut(), stored) {
+            if let Err(e) = store.save(&offsets).await {
+                // The next successful save stores the latest offsets again
+                warn!("Failed to store offsets: {}", e);
+            }
+        }
     }
 
     /// Initialize the connector
@@ -132,80 +281,193 @@ impl<C: SourceConnector> SourceRuntime<C> {
         let producer_configs = self.connector.producer_configs().await?;
 
         if producer_configs.is_empty() {
-            return Err(ConnectorError::config(
-                "No producer configurations provided by connector",
-            ));
+            info!("No producer configurations provided, producers are created on first use");
+            return Ok(());
         }
 
         info!("Creating {} producer(s)", producer_configs.len());
 
-        for producer_cfg in producer_configs {
-            let topic = &producer_cfg.topic;
+        for producer_cfg in &producer_configs {
+            self.create_producer(producer_cfg).await?;
+        }
+
+        info!("All producers created successfully");
+        Ok(())
+    }
 
+    /// Create the producer and metrics for a topic
+    async fn create_producer(&mut self, producer_cfg: &ProducerConfig) -> ConnectorResult<()> {
+        let topic = &producer_cfg.topic;
+
+        info!(
+            "Creating producer for topic: {} (partitions: {}, reliable: {})",
+            topic, producer_cfg.partitions, producer_cfg.reliable_dispatch
+        );
+        let max_in_flight = self.config.processing.max_in_flight;
+        if max_in_flight > 1 && producer_cfg.partitions <= 1 {
+            warn!(
+                "processing.max_in_flight = {} has no effect on topic {}: sends overlap across partitions only",
+                max_in_flight, topic
+            );
+        }
+        if let Some(schema) = &producer_cfg.schema {
             info!(
-                "Creating producer for topic: {} (partitions: {}, reliable: {})",
-                topic, producer_cfg.partitions, producer_cfg.reliable_dispatch
+                "Registering {} schema for topic {}",
+                schema.schema_type, topic
             );
+        }
 
-            // Generate producer name: connector_name-topic_name
-            let topic_suffix = topic.replace('/', "-");
-            let producer_name = format!("{}-{}", self.config.connector_name, topic_suffix);
-
+        // Loaded before creating the producer so that a broken schema fails early
+        let json_schema = producer_cfg
+            .json_schema
+            .as_deref()
+            .map(JsonSchema::load)
+            .transpose()?;
+
+        // Generate producer name: connector_name-topic_name
+        let topic_suffix = topic.replace('/', "-");
+        let producer_name = format!("{}-{}", self.config.connector_name, topic_suffix);
+
+        // Partitioned topics get a producer per partition, so keyed records can be
+        // routed to a fixed partition (same topic and producer names as the client uses)
+        let targets: Vec<(String, String)> = if producer_cfg.partitions > 0 {
+            (0..producer_cfg.partitions)
+                .map(|partition| {
+                    (
+                        format!("{}-part-{}", topic, partition),
+                        format!("{}-{}", producer_name, partition),
+                    )
+                })
+                .collect()
+        } else {
+            vec![(topic.clone(), producer_name)]
+        };
+
+        let mut partitions = Vec::with_capacity(targets.len());
+        for (target_topic, target_name) in targets {
             let mut producer_builder = self
                 .client
                 .new_producer()
-                .with_topic(topic)
-                .with_name(&producer_name);
-
-            // Add partitions if specified
-            if producer_cfg.partitions > 0 {
-                producer_builder = producer_builder.with_partitions(producer_cfg.partitions);
-            }
+                .with_topic(&target_topic)
+                .with_name(&target_name);
 
             // Add reliable dispatch if requested
             if producer_cfg.reliable_dispatch {
                 producer_builder = producer_builder.with_reliable_dispatch();
             }
 
+            if let Some(schema) = &producer_cfg.schema {
+                producer_builder =
+                    producer_builder.with_schema(topic.clone(), schema.registry_type());
+            }
+
             let mut producer = producer_builder.build();
             producer.create().await.map_err(|e| {
-                ConnectorError::fatal_with_source(
-                    format!("Failed to create producer for topic {}", topic),
+                ConnectorError::retryable_with_source(
+                    format!("Failed to create producer for topic {}", target_topic),
                     e,
                 )
             })?;
-
-            info!("Producer created successfully for topic: {}", topic);
-            self.producers.insert(topic.clone(), producer);
+            partitions.push(producer);
         }
 
-        info!("All producers created successfully");
+        info!("Producer created successfully for topic: {}", topic);
+        self.producers.insert(
+            topic.clone(),
+            KeyedProducer::new(partitions, self.config.processing.max_in_flight),
+        );
+        self.topic_metrics.insert(
+            topic.clone(),
+            ConnectorMetrics::new(&self.config.connector_name, topic),
+        );
+        if let Some(schema) = json_schema {
+            info!(
+                "Records for topic {} are validated against JSON schema {}",
+                topic,
+                schema.name()
+            );
+            self.json_schemas.insert(topic.clone(), schema);
+        }
         Ok(())
     }
 
+    /// Create the producer for a topic not declared in `producer_configs()`
+    ///
+    /// Uses the record's `producer_config`, or a non-partitioned producer without
+    /// reliable dispatch. Records of topics `processing.allowed_topics` does not allow are
+    /// dropped by `drop_disallowed()` beforehand.
+    async fn create_record_producer(&mut self, record: &SourceRecord) -> ConnectorResult<()> {
+        let producer_cfg = match &record.producer_config {
+            Some(producer_cfg) => ProducerConfig {
+                topic: record.topic.clone(),
+                ..producer_cfg.clone()
+            },
+            None => ProducerConfig {
+                topic: record.topic.clone(),
+                ..Default::default()
+            },
+        };
+        self.create_producer(&producer_cfg).await
+    }
+
     /// Main polling loop - polls connector and publishes records
     async fn process_polling_loop(&mut self) -> ConnectorResult<()> {
         info!("Entering main polling loop");
         let poll_interval = Duration::from_millis(self.config.processing.poll_interval_ms);
+        let health_check_interval =
+            Duration::from_millis(self.config.processing.health_check_interval_ms);
+        let mut last_health_check = Instant::now();
+        let mut reloads = self.config_path.clone().map(|path| {
+            let (sender, reloads) = mpsc::channel(1);
+            spawn_config_watcher(path, sender, self.shutdown.subscribe());
+            reloads
+        });
+
+        // Checked between batches, so the publishes of a polled batch always complete
+        while !*self.shutdown.borrow() {
+            if let Some(content) = reloads.as_mut().and_then(|reloads| reloads.try_recv().ok()) {
+                self.reconfigure(&content).await;
+            }
+
+            if last_health_check.elapsed() >= health_check_interval {
+                let result = self.connector.health_check().await;
+                self.health.record_check(&result, &self.metrics);
+                last_health_check = Instant::now();
+            }
 
-        while !self.shutdown.load(Ordering::Relaxed) {
-            match self.connector.poll().await {
+            // Stop polling while the circuit is open, unless shutting down meanwhile
+            if let Some(breaker) = &self.circuit_breaker {
+                let mut shutdown = self.shutdown.subscribe();
+                tokio::select! {
+                    _ = breaker.acquire() => {}
+                    _ = shutdown.wait_for(|&stop| stop) => break,
+                }
+            }
+
+            let poll_result = self.connector.poll().await;
+            if let Some(breaker) = &self.circuit_breaker {
+                match &poll_result {
+                    Err(e) if e.is_retryable() => breaker.record_failure(),
+                    _ => breaker.record_success(),
+                }
+            }
+
+            match poll_result {
                 Ok(records) if !records.is_empty() => {
                     info!("Polled {} records", records.len());
                     self.metrics.record_batch_size(records.len());
 
                     // Publish records
-                    match self.publish_batch(records).await {
-                        Ok(offsets) => {
-                            // Commit offsets
-                            if let Err(e) = self.connector.commit(offsets).await {
-                                error!("Failed to commit offsets: {}", e);
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to publish batch: {}", e);
-                            self.metrics.record_error(&format!("{:?}", e));
-                        }
+                    let mut offsets = Vec::new();
+                    let result = self.publish_batch(records, &mut offsets).await;
+
+                    // Commit the published records, also if a later record failed
+                    if !offsets.is_empty() {
+                        self.commit_offsets(offsets).await;
+                    }
+                    if let Err(e) = result {
+                        // Counted per topic in publish_batch
+                        error!("Failed to publish batch: {}", e);
                     }
                 }
                 Ok(_) => {
@@ -214,7 +476,7 @@ impl<C: SourceConnector> SourceRuntime<C> {
                 }
                 Err(e) => {
                     error!("Poll error: {}", e);
-                    self.metrics.record_error(&format!("{:?}", e));
+                    self.metrics.record_error(e.kind());
                     tokio::time::sleep(Duration::from_secs(1)).await;
                 }
             }
@@ -223,61 +485,185 @@ impl<C: SourceConnector> SourceRuntime<C> {
         Ok(())
     }
 
+    /// Apply a reloaded configuration and create the producers of new topics
+    ///
+    /// A configuration the connector rejects is dropped and polling continues as before.
+    async fn reconfigure(&mut self, content: &str) {
+        if let Err(e) = self.connector.reconfigure(content).await {
+            warn!("Reloaded config rejected, keeping the current one: {}", e);
+            return;
+        }
+
+        let producer_configs = match self.connector.producer_configs().await {
+            Ok(producer_configs) => producer_configs,
+            Err(e) => {
+                error!("Failed to get producer configurations after reload: {}", e);
+                return;
+            }
+        };
+        for producer_cfg in &producer_configs {
+            if self.producers.contains_key(&producer_cfg.topic) {
+                continue;
+            }
+            if let Err(e) = self.create_producer(producer_cfg).await {
+                // Created with the first record of the topic otherwise
+                warn!(
+                    "Failed to create producer for topic {}: {}",
+                    producer_cfg.topic, e
+                );
+            }
+        }
+        info!("Reloaded config applied");
+    }
+
+    /// Apply the transforms to a batch of polled records
+    ///
+    /// Dropped records and records failing a transform keep their place in the batch, so
+    /// their offsets are committed in order with the published records. Of the records a
+    /// record is split into, only the last one carries its offset, committed once all of
+    /// them are published.
+    fn transform_batch(
+        &self,
+        records: Vec<SourceRecord>,
+    ) -> Vec<Result<SourceRecord, Option<Offset>>> {
+        let mut transformed = Vec::with_capacity(records.len());
+        for record in records {
+            let offset = record.offset.clone();
+            match self.transforms.apply(record) {
+                Ok(split) if split.is_empty() => {
+                    self.metrics.record_filtered();
+                    transformed.push(Err(offset));
+                }
+                Ok(split) => {
+                    let last = split.len() - 1;
+                    transformed.extend(split.into_iter().enumerate().map(|(i, mut record)| {
+                        if i < last {
+                            record.offset = None;
+                        }
+                        Ok(record)
+                    }));
+                }
+                Err(e) => {
+                    // Polled records cannot be redelivered, the record is skipped
+                    error!("Skipping record failing a transform: {}", e);
+                    self.metrics.record_error(e.kind());
+                    transformed.push(Err(offset));
+                }
+            }
+        }
+        transformed
+    }
+
     /// Publish a batch of records to their respective topics
     ///
-    /// Records are routed to pre-created producers based on their topic field.
-    /// Each record's routing key (if present) will be used for partition selection.
+    /// Records are transformed first, then routed to producers based on their topic field.
+    /// Each record's routing key (if present) selects its partition of a partitioned topic.
+    /// Records not matching the JSON schema of their topic are republished to
+    /// `processing.dead_letter_topic` if set, and dropped otherwise.
+    ///
+    /// Records are published in order and publishing stops at the first failure, including
+    /// a failed dead-letter publish. The source offsets of the published and dropped records
+    /// are appended to `offsets`.
     async fn publish_batch(
         &mut self,
         records: Vec<SourceRecord>,
-    ) -> ConnectorResult<Vec<crate::traits::Offset>> {
-        let mut offsets = Vec::new();
+        offsets: &mut Vec<Offset>,
+    ) -> ConnectorResult<()> {
+        let mut records = self.transform_batch(records);
+        drop_disallowed(
+            &mut records,
+            &self.producers,
+            &self.config.processing.allowed_topics,
+            &self.metrics,
+        );
+
+        // Create the producers of topics that were not configured upfront. A failure leaves
+        // its record and the ones after it unpublished, the records before are published.
+        let mut failure = None;
+        for position in 0..records.len() {
+            let Ok(record) = &records[position] else {
+                continue;
+            };
+            if !self.producers.contains_key(&record.topic) {
+                if let Err(e) = self.create_record_producer(record).await {
+                    self.metrics.record_error(e.kind());
+                    failure = Some(e);
+                    records.truncate(position);
+                    break;
+                }
+            }
+        }
 
-        for (idx, record) in records.into_iter().enumerate() {
-            let start = Instant::now();
+        // Pick the partition of every record in record order, so keys and round-robin
+        // routing don't depend on the order sends complete in
+        let mut routed = Vec::with_capacity(records.len());
+        for record in records {
+            let record = match record {
+                Ok(record) => record,
+                Err(offset) => {
+                    routed.push(Outgoing::Dropped(offset));
+                    continue;
+                }
+            };
             let topic = &record.topic;
-
-            // Get the pre-created producer for this topic
-            let producer = self.producers.get_mut(topic).ok_or_else(|| {
-                ConnectorError::fatal(format!(
-                    "No producer found for topic: {}. Ensure producer_configs() includes this topic.",
-                    topic
-                ))
-            })?;
-
-            // Send message with routing key if present
-            let send_result = if let Some(key) = &record.key {
-                // Use key-based routing (for partitioned topics - will be used when Danube supports it)
-                debug!("Sending message with key: {} to topic: {}", key, topic);
-                // TODO: Use send_with_key when danube-client supports it
-                // For now, key is preserved in SourceRecord but not used in actual send
-                producer.send(record.payload, Some(record.attributes)).await
-            } else {
-                producer.send(record.payload, Some(record.attributes)).await
+            let (Some(producer), true) = (
+                self.producers.get_mut(topic),
+                self.topic_metrics.contains_key(topic),
+            ) else {
+                let err = ConnectorError::fatal(format!("No producer found for topic: {}", topic));
+                self.metrics.record_error(err.kind());
+                return Err(err);
             };
 
-            match send_result {
-                Ok(message_id) => {
-                    let duration = start.elapsed();
-                    self.metrics.record_processing_time(duration);
-                    self.metrics.record_success();
-                    debug!("Message sent successfully: {}", message_id);
-
-                    // Create offset
-                    let offset = crate::traits::Offset::new(record.topic, idx as u64);
-                    offsets.push(offset);
-                }
-                Err(e) => {
-                    error!("Failed to publish message: {}", e);
-                    return Err(ConnectorError::retryable_with_source(
-                        "Failed to publish batch",
-                        e,
-                    ));
+            if let Some(schema) = self.json_schemas.get(topic) {
+                if let Err(e) = schema.validate(&record.payload) {
+                    let metrics = &self.topic_metrics[topic];
+                    metrics.record_error(e.kind());
+                    match &self.config.processing.dead_letter_topic {
+                        Some(dlq_topic) => {
+                            let attributes = dead_letter_attributes(&record, &e);
+                            let payload = record.payload.clone();
+                            if let Err(dlq_err) = self
+                                .dead_letters
+                                .publish(dlq_topic, payload, attributes)
+                                .await
+                            {
+                                error!(
+                                    "Failed to publish record for topic {} to dead-letter topic {}: {}",
+                                    topic, dlq_topic, dlq_err
+                                );
+                                metrics.record_error(dlq_err.kind());
+                                failure = Some(dlq_err);
+                                break;
+                            }
+                            metrics.record_dead_letter();
+                            warn!(
+                                "Sent record for topic {} to dead-letter topic {}: {}",
+                                topic, dlq_topic, e
+                            );
+                        }
+                        // Polled records cannot be redelivered, the record is skipped
+                        None => error!(
+                            "Skipping record for topic {} without dead-letter topic: {}",
+                            topic, e
+                        ),
+                    }
+                    routed.push(Outgoing::Dropped(record.offset));
+                    continue;
                 }
             }
+
+            // Keyed records of partitioned topics always go to the partition of their key
+            if let Some(key) = &record.key {
+                debug!("Sending message with key: {} to topic: {}", key, topic);
+            }
+            let partition = producer.partition(record.key.as_deref());
+            routed.push(Outgoing::Publish(Box::new(record), partition));
         }
 
-        Ok(offsets)
+        // Publish the records before a failed one, which is left uncommitted
+        send_routed(&self.producers, &self.topic_metrics, routed, offsets).await?;
+        failure.map_or(Ok(()), Err)
     }
 
     /// Initialize tracing/logging
@@ -294,3 +680,270 @@ impl<C: SourceConnector> SourceRuntime<C> {
             .ok(); // Ignore if already initialized
     }
 }
+
+/// Drop the records of topics without a producer that `allowed_topics` does not allow
+///
+/// Dropped records keep their position and source offset, so the records around them are
+/// still published and committed. An empty `allowed_topics` allows any topic.
+fn drop_disallowed<P: TopicSender>(
+    records: &mut [Result<SourceRecord, Option<Offset>>],
+    producers: &HashMap<String, KeyedProducer<P>>,
+    allowed_topics: &[String],
+    metrics: &ConnectorMetrics,
+) {
+    if allowed_topics.is_empty() {
+        return;
+    }
+    for slot in records.iter_mut() {
+        let Ok(record) = slot else {
+            continue;
+        };
+        if producers.contains_key(&record.topic)
+            || allowed_topics
+                .iter()
+                .any(|pattern| topic_matches(pattern, &record.topic))
+        {
+            continue;
+        }
+
+        // Polled records cannot be redelivered, the record is skipped
+        let e = ConnectorError::config(format!(
+            "Topic {} is neither in producer_configs() nor in processing.allowed_topics",
+            record.topic
+        ));
+        error!("Skipping record: {}", e);
+        metrics.record_error(e.kind());
+        *slot = Err(record.offset.clone());
+    }
+}
+
+/// Attributes of a dead-letter message: the original attributes plus the failure context
+fn dead_letter_attributes(
+    record: &SourceRecord,
+    error: &ConnectorError,
+) -> HashMap<String, String> {
+    let mut attributes = record.attributes.clone();
+    attributes.insert("dlq.source_topic".to_string(), record.topic.clone());
+    attributes.insert("dlq.error_kind".to_string(), error.kind().to_string());
+    attributes.insert("dlq.error_message".to_string(), error.to_string());
+    attributes
+}
+
+/// Send routed records, pipelining the sends within the in-flight window of each producer
+///
+/// Results are handled in record order and sending stops at the first failure, so only the
+/// source offsets of a published prefix of the records are appended to `offsets`.
+async fn send_routed<P: TopicSender>(
+    producers: &HashMap<String, KeyedProducer<P>>,
+    topic_metrics: &HashMap<String, ConnectorMetrics>,
+    routed: Vec<Outgoing>,
+    offsets: &mut Vec<Offset>,
+) -> ConnectorResult<()> {
+    let mut sends: FuturesOrdered<_> = routed
+        .into_iter()
+        .map(|outgoing| async move {
+            let (record, partition) = match outgoing {
+                Outgoing::Publish(record, partition) => (*record, partition),
+                Outgoing::Dropped(offset) => return (None, offset),
+            };
+            let start = Instant::now();
+            let producer = &producers[&record.topic];
+            let send_result = producer
+                .send(partition, record.payload, Some(record.attributes))
+                .await;
+            (
+                Some((record.topic, start.elapsed(), send_result)),
+                record.offset,
+            )
+        })
+        .collect();
+
+    while let Some((sent, offset)) = sends.next().await {
+        let Some((topic, duration, send_result)) = sent else {
+            // Dropped records are committed once the records before them are published
+            if let Some(offset) = offset {
+                offsets.push(offset);
+            }
+            continue;
+        };
+        let metrics = &topic_metrics[&topic];
+        match send_result {
+            Ok(message_id) => {
+                metrics.record_processing_time(duration);
+                metrics.record_success();
+                debug!("Message sent successfully: {}", message_id);
+
+                if let Some(offset) = offset {
+                    offsets.push(offset);
+                }
+            }
+            Err(e) => {
+                // Dropping the remaining sends cancels them; records already published
+                // are not committed and will be delivered again (at-least-once)
+                error!("Failed to publish message: {}", e);
+                let err = ConnectorError::retryable_with_source("Failed to publish batch", e);
+                metrics.record_error(err.kind());
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use danube_client::errors::{DanubeError, Result as DanubeResult};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Sender whose payload is `[fail, delay_ms]`, counting the sends in flight
+    #[derive(Default)]
+    struct MockSender {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl TopicSender for Arc<MockSender> {
+        async fn send(
+            &self,
+            payload: Vec<u8>,
+            _attributes: Option<HashMap<String, String>>,
+        ) -> DanubeResult<u64> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(payload[1] as u64)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            if payload[0] == 1 {
+                return Err(DanubeError::Unrecoverable("send failed".into()));
+            }
+            Ok(0)
+        }
+    }
+
+    fn publish(offset: u64, fail: bool, delay_ms: u8) -> Outgoing {
+        let record = SourceRecord::new("/default/events", vec![fail as u8, delay_ms])
+            .with_offset(Offset::new("events", offset));
+        Outgoing::Publish(Box::new(record), 0)
+    }
+
+    fn topic_metrics() -> HashMap<String, ConnectorMetrics> {
+        HashMap::from([(
+            "/default/events".to_string(),
+            ConnectorMetrics::new("test", "/default/events"),
+        )])
+    }
+
+    #[tokio::test]
+    async fn test_send_failure_commits_published_prefix() {
+        let sender = Arc::new(MockSender::default());
+        let producers = HashMap::from([(
+            "/default/events".to_string(),
+            KeyedProducer::new(vec![sender.clone()], 4),
+        )]);
+
+        // The records after the failed one complete first but are not committed
+        let routed = vec![
+            publish(0, false, 30),
+            publish(1, true, 10),
+            publish(2, false, 0),
+            Outgoing::Dropped(Some(Offset::new("events", 3))),
+        ];
+        let mut offsets = Vec::new();
+        let err = send_routed(&producers, &topic_metrics(), routed, &mut offsets)
+            .await
+            .unwrap_err();
+
+        assert!(err.is_retryable());
+        assert_eq!(offsets, vec![Offset::new("events", 0)]);
+        assert_eq!(sender.max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_sends_bounded_by_window() {
+        let sender = Arc::new(MockSender::default());
+        let producers = HashMap::from([(
+            "/default/events".to_string(),
+            KeyedProducer::new(vec![sender.clone()], 3),
+        )]);
+
+        let routed = (0..10).map(|offset| publish(offset, false, 5)).collect();
+        let mut offsets = Vec::new();
+        send_routed(&producers, &topic_metrics(), routed, &mut offsets)
+            .await
+            .unwrap();
+
+        let expected: Vec<_> = (0..10)
+            .map(|offset| Offset::new("events", offset))
+            .collect();
+        assert_eq!(offsets, expected);
+        assert_eq!(sender.max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_topic_in_batch_is_skipped() {
+        let sender = Arc::new(MockSender::default());
+        let producers = HashMap::from([(
+            "/default/events".to_string(),
+            KeyedProducer::new(vec![sender.clone()], 4),
+        )]);
+        let allowed_topics = vec!["/iot/*".to_string()];
+        let metrics = ConnectorMetrics::new("test", "test");
+        let record = |topic: &str, offset: u64| {
+            Ok(SourceRecord::new(topic, vec![0, 0]).with_offset(Offset::new("events", offset)))
+        };
+
+        // Allowed topics without a producer yet are kept for it to be created
+        let mut records = vec![
+            record("/default/events", 0),
+            record("/other/audit", 1),
+            record("/iot/sensor-1", 2),
+            record("/default/events", 3),
+        ];
+        drop_disallowed(&mut records, &producers, &allowed_topics, &metrics);
+        assert!(records[0].is_ok());
+        assert_eq!(
+            records[1].as_ref().err(),
+            Some(&Some(Offset::new("events", 1)))
+        );
+        // Leave out the record of the allowed topic, which has no producer here
+        assert!(records.remove(2).is_ok());
+
+        // The records around the dropped one are published, all offsets are committed
+        let routed = records
+            .into_iter()
+            .map(|record| match record {
+                Ok(record) => Outgoing::Publish(Box::new(record), 0),
+                Err(offset) => Outgoing::Dropped(offset),
+            })
+            .collect();
+        let mut offsets = Vec::new();
+        send_routed(&producers, &topic_metrics(), routed, &mut offsets)
+            .await
+            .unwrap();
+        let expected: Vec<_> = [0, 1, 3]
+            .into_iter()
+            .map(|offset| Offset::new("events", offset))
+            .collect();
+        assert_eq!(offsets, expected);
+    }
+
+    #[test]
+    fn test_dead_letter_attributes() {
+        let record = SourceRecord::new("/default/payments", b"{}".to_vec())
+            .with_attribute("source", "gateway-1")
+            .with_offset(Offset::new("payments", 7));
+        let error = ConnectorError::invalid_data("/amount: missing", vec![]);
+
+        let attributes = dead_letter_attributes(&record, &error);
+        assert_eq!(attributes["source"], "gateway-1");
+        assert_eq!(attributes["dlq.source_topic"], "/default/payments");
+        assert_eq!(attributes["dlq.error_kind"], "invalid_data");
+        assert_eq!(
+            attributes["dlq.error_message"],
+            "Invalid data: /amount: missing"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Topic discovery for sink consumers with topic patterns
+//!
+//! A `ConsumerConfig` whose topic contains `*` is a topic pattern (see
+//! `utils::topic_matches`). Every `processing.topic_discovery_interval_ms` the sink runtime
+//! lists the topics of the pattern's namespace through the Danube admin API, subscribes to
+//! new matching topics and attaches their consumers to the running workers. Consumers of
+//! topics that no longer exist are closed.
+
+pub(super) use crate::utils::is_topic_pattern;
+use crate::utils::topic_matches;
+use crate::{ConnectorError, ConnectorResult};
+use danube_core::admin_proto::broker_admin_client::BrokerAdminClient;
+use danube_core::admin_proto::namespace_admin_client::NamespaceAdminClient;
+use danube_core::admin_proto::{Empty, NamespaceRequest};
+use std::collections::BTreeSet;
+use tonic::transport::{Channel, Endpoint};
+
+/// Lists the topics of namespaces through the Danube admin API
+pub(super) struct TopicLister {
+    brokers: BrokerAdminClient<Channel>,
+    namespaces: NamespaceAdminClient<Channel>,
+}
+
+impl TopicLister {
+    /// Create a lister connecting on first use
+    pub(super) fn new(admin_service_url: &str) -> ConnectorResult<Self> {
+        let channel = Endpoint::from_shared(admin_service_url.to_string())
+            .map_err(|e| {
+                ConnectorError::config(format!(
+                    "Invalid admin_service_url {}: {}",
+                    admin_service_url, e
+                ))
+            })?
+            .connect_lazy();
+
+        Ok(Self {
+            brokers: BrokerAdminClient::new(channel.clone()),
+            namespaces: NamespaceAdminClient::new(channel),
+        })
+    }
+
+    /// List the topics of all namespaces the patterns can match
+    pub(super) async fn list_topics(&mut self, patterns: &[&str]) -> ConnectorResult<Vec<String>> {
+        let mut namespaces = BTreeSet::new();
+        let mut all_namespaces = false;
+        for pattern in patterns {
+            match pattern_namespace(pattern) {
+                Some(namespace) => {
+                    namespaces.insert(namespace.to_string());
+                }
+                None => all_namespaces = true,
+            }
+        }
+
+        if all_namespaces {
+            let response = self.brokers.list_namespaces(Empty {}).await.map_err(|e| {
+                ConnectorError::retryable_with_source("Failed to list namespaces", e)
+            })?;
+            namespaces.extend(
+                response
+                    .into_inner()
+                    .namespaces
+                    .iter()
+                    .map(|namespace| namespace.trim_start_matches('/').to_string()),
+            );
+        }
+
+        let mut topics = Vec::new();
+        for namespace in namespaces {
+            let request = NamespaceRequest {
+                name: namespace.clone(),
+            };
+            let response = self
+                .namespaces
+                .get_namespace_topics(request)
+                .await
+                .map_err(|e| {
+                    ConnectorError::retryable_with_source(
+                        format!("Failed to list topics of namespace {}", namespace),
+                        e,
+                    )
+                })?;
+            topics.extend(response.into_inner().topics.into_iter().map(|topic| {
+                if topic.starts_with('/') {
+                    topic
+                } else {
+                    format!("/{}", topic)
+                }
+            }));
+        }
+        Ok(topics)
+    }
+}
+
+/// Namespace of a topic pattern, or `None` if the namespace segment has a wildcard
+fn pattern_namespace(pattern: &str) -> Option<&str> {
+    let namespace = pattern.trim_start_matches('/').split('/').next()?;
+    (!namespace.is_empty() && !namespace.contains('*')).then_some(namespace)
+}
+
+/// Topics matching a pattern, with partitions (`{topic}-part-{n}`) collapsed into their
+/// topic, as consumers subscribe to all partitions of a topic
+pub(super) fn matching_topics(pattern: &str, topics: &[String]) -> BTreeSet<String> {
+    topics
+        .iter()
+        .map(|topic| base_topic(topic))
+        .filter(|topic| topic_matches(pattern, topic))
+        .map(str::to_string)
+        .collect()
+}
+
+fn base_topic(topic: &str) -> &str {
+    match topic.rsplit_once("-part-") {
+        Some((base, partition))
+            if !partition.is_empty() && partition.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            base
+        }
+        _ => topic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_namespace() {
+        assert_eq!(pattern_namespace("/events/*"), Some("events"));
+        assert_eq!(pattern_namespace("/events/**"), Some("events"));
+        assert_eq!(pattern_namespace("/*/orders"), None);
+        assert_eq!(pattern_namespace("**"), None);
+    }
+
+    #[test]
+    fn test_matching_topics_collapses_partitions() {
+        let topics: Vec<String> = [
+            "/events/orders-part-0",
+            "/events/orders-part-1",
+            "/events/users",
+            "/events/part-time-part-x",
+            "/default/orders",
+        ]
+        .iter()
+        .map(|topic| topic.to_string())
+        .collect();
+
+        let matching: Vec<String> = matching_topics("/events/*", &topics).into_iter().collect();
+        assert_eq!(
+            matching,
+            vec![
+                "/events/orders",
+                "/events/part-time-part-x",
+                "/events/users"
+            ]
+        );
+    }
+}
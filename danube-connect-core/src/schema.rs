@@ -1,18 +1,42 @@
 //! Schema types for Danube messages.
 //!
 //! This module provides schema type definitions that mirror Danube's schema system.
-//! Connectors use these to understand how to interpret message payloads.
+//! Connectors use these to understand how to interpret message payloads. Sources register
+//! a [`TopicSchema`] for their topics, sinks see the registered schema type of a consumed
+//! topic in `SinkRecord::schema_type()`.
+//!
+//! `Avro` and `Protobuf` payloads are decoded with a [`PayloadSchema`] carrying their
+//! schema definition. JSON payloads can be validated against a [`JsonSchema`].
+
+#[cfg(feature = "avro")]
+mod avro;
+mod json_schema;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+mod registry;
+
+/// A parsed Avro schema, as returned by [`PayloadSchema::avro_schema`]
+#[cfg(feature = "avro")]
+pub use apache_avro::Schema as AvroSchema;
+pub use json_schema::JsonSchema;
+pub(crate) use registry::registered_schema_type;
+pub use registry::TopicSchema;
 
+use crate::{ConnectorError, ConnectorResult};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+#[cfg(feature = "avro")]
+use std::sync::Arc;
 
 /// Schema type for Danube messages
 ///
 /// Defines how message payloads are encoded and should be interpreted.
 /// This mirrors the SchemaType enum from danube-client.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "PascalCase")]
 pub enum SchemaType {
     /// Raw bytes - no specific encoding
+    #[default]
     Bytes,
     /// UTF-8 encoded string
     String,
@@ -20,12 +44,19 @@ pub enum SchemaType {
     Int64,
     /// JSON-encoded data
     Json,
+    /// Avro binary-encoded data, decoded with an Avro schema
+    Avro,
+    /// Protobuf-encoded data, decoded with a message descriptor
+    Protobuf,
 }
 
 impl SchemaType {
-    /// Check if this schema type represents structured data (JSON)
+    /// Check if this schema type represents structured data (JSON, Avro or Protobuf)
     pub fn is_structured(&self) -> bool {
-        matches!(self, SchemaType::Json)
+        matches!(
+            self,
+            SchemaType::Json | SchemaType::Avro | SchemaType::Protobuf
+        )
     }
 
     /// Check if this schema type represents text data
@@ -50,16 +81,12 @@ impl SchemaType {
             SchemaType::String => "UTF-8 encoded string",
             SchemaType::Int64 => "64-bit signed integer",
             SchemaType::Json => "JSON-encoded structured data",
+            SchemaType::Avro => "Avro binary-encoded structured data",
+            SchemaType::Protobuf => "Protobuf-encoded structured data",
         }
     }
 }
 
-impl Default for SchemaType {
-    fn default() -> Self {
-        SchemaType::Bytes
-    }
-}
-
 impl std::fmt::Display for SchemaType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -67,6 +94,8 @@ impl std::fmt::Display for SchemaType {
             SchemaType::String => write!(f, "String"),
             SchemaType::Int64 => write!(f, "Int64"),
             SchemaType::Json => write!(f, "Json"),
+            SchemaType::Avro => write!(f, "Avro"),
+            SchemaType::Protobuf => write!(f, "Protobuf"),
         }
     }
 }
@@ -80,11 +109,173 @@ impl std::str::FromStr for SchemaType {
             "string" => Ok(SchemaType::String),
             "int64" => Ok(SchemaType::Int64),
             "json" => Ok(SchemaType::Json),
+            "avro" => Ok(SchemaType::Avro),
+            "protobuf" => Ok(SchemaType::Protobuf),
             _ => Err(format!("Unknown schema type: {}", s)),
         }
     }
 }
 
+/// A schema type with the schema definition its payloads are decoded with
+///
+/// `Avro` and `Protobuf` payloads need a definition: an Avro schema, or a Protobuf file
+/// descriptor set and message type, read from a file with [`PayloadSchema::load`] or
+/// supplied by a schema registry. The other schema types convert from [`SchemaType`].
+#[derive(Debug, Clone)]
+pub struct PayloadSchema {
+    schema_type: SchemaType,
+    definition: Option<Definition>,
+}
+
+#[derive(Debug, Clone)]
+enum Definition {
+    #[cfg(feature = "avro")]
+    Avro(Arc<avro::AvroDefinition>),
+    #[cfg(feature = "protobuf")]
+    Protobuf(prost_reflect::MessageDescriptor),
+}
+
+impl PayloadSchema {
+    /// Schema of Avro payloads, from the JSON definition of the Avro schema
+    #[cfg(feature = "avro")]
+    pub fn avro(definition: &str) -> ConnectorResult<Self> {
+        Ok(Self {
+            schema_type: SchemaType::Avro,
+            definition: Some(Definition::Avro(Arc::new(avro::AvroDefinition::parse(
+                definition,
+            )?))),
+        })
+    }
+
+    /// Schema of Protobuf payloads, from an encoded file descriptor set and the fully
+    /// qualified name of the payload message
+    #[cfg(feature = "protobuf")]
+    pub fn protobuf(descriptor_set: &[u8], message_type: &str) -> ConnectorResult<Self> {
+        Ok(Self {
+            schema_type: SchemaType::Protobuf,
+            definition: Some(Definition::Protobuf(protobuf::message_descriptor(
+                descriptor_set,
+                message_type,
+            )?)),
+        })
+    }
+
+    /// Load the schema definition of `schema_type`
+    ///
+    /// `Avro` reads the Avro schema (`.avsc`) at `schema_file`. `Protobuf` reads the file
+    /// descriptor set at `schema_file` and needs `message_type`. Other schema types need no
+    /// definition and ignore both.
+    pub fn load(
+        schema_type: SchemaType,
+        schema_file: Option<&str>,
+        message_type: Option<&str>,
+    ) -> ConnectorResult<Self> {
+        let schema_file = || {
+            schema_file.ok_or_else(|| {
+                ConnectorError::config(format!(
+                    "{} schema type requires a schema_file",
+                    schema_type
+                ))
+            })
+        };
+
+        match schema_type {
+            #[cfg(feature = "avro")]
+            SchemaType::Avro => {
+                let path = schema_file()?;
+                let definition = std::fs::read_to_string(path).map_err(|e| read_error(path, e))?;
+                Self::avro(&definition)
+            }
+            #[cfg(not(feature = "avro"))]
+            SchemaType::Avro => {
+                let _ = schema_file;
+                Err(ConnectorError::config(
+                    "Avro schemas require the `avro` feature of danube-connect-core",
+                ))
+            }
+            #[cfg(feature = "protobuf")]
+            SchemaType::Protobuf => {
+                let path = schema_file()?;
+                let message_type = message_type.ok_or_else(|| {
+                    ConnectorError::config("Protobuf schema type requires a message_type")
+                })?;
+                let descriptor_set = std::fs::read(path).map_err(|e| read_error(path, e))?;
+                Self::protobuf(&descriptor_set, message_type)
+            }
+            #[cfg(not(feature = "protobuf"))]
+            SchemaType::Protobuf => {
+                let _ = message_type;
+                Err(ConnectorError::config(
+                    "Protobuf schemas require the `protobuf` feature of danube-connect-core",
+                ))
+            }
+            _ => Ok(schema_type.into()),
+        }
+    }
+
+    /// The schema type
+    pub fn schema_type(&self) -> SchemaType {
+        self.schema_type
+    }
+
+    /// The Avro schema of `Avro` payloads
+    #[cfg(feature = "avro")]
+    pub fn avro_schema(&self) -> Option<&AvroSchema> {
+        match &self.definition {
+            Some(Definition::Avro(definition)) => Some(definition.schema()),
+            _ => None,
+        }
+    }
+
+    /// Decode an `Avro` or `Protobuf` payload with the schema definition
+    #[cfg_attr(
+        not(any(feature = "avro", feature = "protobuf")),
+        allow(unreachable_code, unused_variables)
+    )]
+    pub(crate) fn decode(&self, payload: &[u8]) -> ConnectorResult<Value> {
+        let decoded: Result<Value, String> = match &self.definition {
+            #[cfg(feature = "avro")]
+            Some(Definition::Avro(definition)) => definition.decode(payload),
+            #[cfg(feature = "protobuf")]
+            Some(Definition::Protobuf(descriptor)) => protobuf::decode(descriptor, payload),
+            #[cfg(not(any(feature = "avro", feature = "protobuf")))]
+            Some(definition) => match *definition {},
+            None => {
+                return Err(ConnectorError::config(format!(
+                    "{} payloads need a schema definition",
+                    self.schema_type
+                )))
+            }
+        };
+        decoded.map_err(|e| {
+            ConnectorError::invalid_data(
+                format!("Failed to decode {} payload: {}", self.schema_type, e),
+                payload.to_vec(),
+            )
+        })
+    }
+}
+
+#[cfg(any(feature = "avro", feature = "protobuf"))]
+fn read_error(path: &str, e: std::io::Error) -> ConnectorError {
+    ConnectorError::config(format!("Failed to read schema file {}: {}", path, e))
+}
+
+impl From<SchemaType> for PayloadSchema {
+    fn from(schema_type: SchemaType) -> Self {
+        Self {
+            schema_type,
+            definition: None,
+        }
+    }
+}
+
+impl From<&PayloadSchema> for PayloadSchema {
+    fn from(schema: &PayloadSchema) -> Self {
+        schema.clone()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,6 +304,11 @@ mod tests {
         assert_eq!("bytes".parse::<SchemaType>().unwrap(), SchemaType::Bytes);
         assert_eq!("string".parse::<SchemaType>().unwrap(), SchemaType::String);
         assert_eq!("int64".parse::<SchemaType>().unwrap(), SchemaType::Int64);
+        assert_eq!("avro".parse::<SchemaType>().unwrap(), SchemaType::Avro);
+        assert_eq!(
+            "Protobuf".parse::<SchemaType>().unwrap(),
+            SchemaType::Protobuf
+        );
 
         assert!("invalid".parse::<SchemaType>().is_err());
     }
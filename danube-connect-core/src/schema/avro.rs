@@ -0,0 +1,348 @@
+//! Avro binary decoding
+//!
+//! Parses Avro schema definitions (the JSON of `.avsc` files) and decodes Avro binary
+//! datums with the `apache-avro` crate into `serde_json::Value`. Payloads in Avro
+//! single-object encoding have their header skipped, other payloads must be plain binary
+//! datums.
+//!
+//! Decoded values take the shape sinks expect rather than the Avro JSON encoding: unions
+//! decode to the value of their branch, `bytes` and `fixed` to base64 strings, `decimal`
+//! to decimal strings, `date` to `YYYY-MM-DD` strings, timestamps to RFC 3339 strings and
+//! `duration` to objects of `months`, `days` and `millis`.
+
+use crate::{ConnectorError, ConnectorResult};
+use apache_avro::schema::{Name, ResolvedSchema, Schema};
+use apache_avro::types::Value as AvroValue;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Map, Number, Value};
+use std::collections::HashMap;
+
+/// Header of the Avro single-object encoding, followed by an 8-byte schema fingerprint
+const SINGLE_OBJECT_MAGIC: [u8; 2] = [0xC3, 0x01];
+
+/// A parsed Avro schema with the named types it defines
+#[derive(Debug, Clone)]
+pub(crate) struct AvroDefinition {
+    schema: Schema,
+    names: HashMap<Name, Schema>,
+}
+
+impl AvroDefinition {
+    /// Parse an Avro schema definition
+    pub(crate) fn parse(definition: &str) -> ConnectorResult<Self> {
+        let invalid =
+            |e: apache_avro::Error| ConnectorError::config(format!("Invalid Avro schema: {}", e));
+
+        let schema = Schema::parse_str(definition).map_err(invalid)?;
+        let names = ResolvedSchema::try_from(&schema)
+            .map_err(invalid)?
+            .get_names()
+            .iter()
+            .map(|(name, schema)| (name.clone(), (*schema).clone()))
+            .collect();
+        Ok(Self { schema, names })
+    }
+
+    /// The parsed schema
+    pub(crate) fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Decode an Avro binary datum written with this schema
+    pub(crate) fn decode(&self, payload: &[u8]) -> Result<Value, String> {
+        let mut input = match payload.strip_prefix(&SINGLE_OBJECT_MAGIC) {
+            Some(header) => header
+                .get(8..)
+                .ok_or("truncated single-object encoding header")?,
+            None => payload,
+        };
+
+        let value = apache_avro::from_avro_datum(&self.schema, &mut input, None)
+            .map_err(|e| e.to_string())?;
+        if !input.is_empty() {
+            return Err(format!("{} trailing bytes after the datum", input.len()));
+        }
+        self.json_value(value, &self.schema)
+    }
+
+    /// Convert a decoded value to JSON, following `schema` for the scale of decimals
+    fn json_value(&self, value: AvroValue, schema: &Schema) -> Result<Value, String> {
+        let schema = match schema {
+            Schema::Ref { name } => self
+                .names
+                .get(name)
+                .ok_or_else(|| format!("unknown type '{}'", name))?,
+            _ => schema,
+        };
+        let mismatch = || format!("decoded value does not match the schema {:?}", schema);
+
+        let value = match value {
+            AvroValue::Null => Value::Null,
+            AvroValue::Boolean(value) => value.into(),
+            AvroValue::Int(value) | AvroValue::TimeMillis(value) => value.into(),
+            AvroValue::Long(value) | AvroValue::TimeMicros(value) => value.into(),
+            AvroValue::Float(value) => float_value(value.into()),
+            AvroValue::Double(value) => float_value(value),
+            AvroValue::Bytes(bytes) | AvroValue::Fixed(_, bytes) => BASE64.encode(bytes).into(),
+            AvroValue::String(value) | AvroValue::Enum(_, value) => value.into(),
+            AvroValue::Uuid(uuid) => uuid.to_string().into(),
+            AvroValue::Union(index, value) => {
+                let Schema::Union(union) = schema else {
+                    return Err(mismatch());
+                };
+                let branch = union
+                    .variants()
+                    .get(index as usize)
+                    .ok_or_else(|| format!("invalid union branch {}", index))?;
+                self.json_value(*value, branch)?
+            }
+            AvroValue::Record(fields) => {
+                let Schema::Record(record) = schema else {
+                    return Err(mismatch());
+                };
+                let mut object = Map::new();
+                for ((name, value), field) in fields.into_iter().zip(&record.fields) {
+                    object.insert(name, self.json_value(value, &field.schema)?);
+                }
+                Value::Object(object)
+            }
+            AvroValue::Array(items) => {
+                let Schema::Array(array) = schema else {
+                    return Err(mismatch());
+                };
+                items
+                    .into_iter()
+                    .map(|item| self.json_value(item, &array.items))
+                    .collect::<Result<_, _>>()?
+            }
+            AvroValue::Map(entries) => {
+                let Schema::Map(map) = schema else {
+                    return Err(mismatch());
+                };
+                let mut object = Map::new();
+                for (key, value) in entries {
+                    object.insert(key, self.json_value(value, &map.types)?);
+                }
+                Value::Object(object)
+            }
+            AvroValue::Decimal(decimal) => {
+                let Schema::Decimal(decimal_schema) = schema else {
+                    return Err(mismatch());
+                };
+                let bytes = Vec::<u8>::try_from(&decimal).map_err(|e| e.to_string())?;
+                decimal_string(&bytes, decimal_schema.scale)?.into()
+            }
+            AvroValue::BigDecimal(decimal) => decimal.to_string().into(),
+            AvroValue::Date(days) => {
+                let date = i64::from(days)
+                    .checked_mul(86_400)
+                    .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                    .ok_or_else(|| format!("date out of range: {}", days))?;
+                date.date_naive().to_string().into()
+            }
+            AvroValue::TimestampMillis(value) => {
+                timestamp_string(value, DateTime::from_timestamp_millis)?.into()
+            }
+            AvroValue::TimestampMicros(value) => {
+                timestamp_string(value, DateTime::from_timestamp_micros)?.into()
+            }
+            AvroValue::TimestampNanos(value) => {
+                timestamp_string(value, |nanos| Some(DateTime::from_timestamp_nanos(nanos)))?.into()
+            }
+            AvroValue::LocalTimestampMillis(value) => {
+                local_timestamp_string(value, DateTime::from_timestamp_millis)?.into()
+            }
+            AvroValue::LocalTimestampMicros(value) => {
+                local_timestamp_string(value, DateTime::from_timestamp_micros)?.into()
+            }
+            AvroValue::LocalTimestampNanos(value) => {
+                local_timestamp_string(value, |nanos| Some(DateTime::from_timestamp_nanos(nanos)))?
+                    .into()
+            }
+            AvroValue::Duration(duration) => json!({
+                "months": u32::from(duration.months()),
+                "days": u32::from(duration.days()),
+                "millis": u32::from(duration.millis()),
+            }),
+        };
+        Ok(value)
+    }
+}
+
+fn float_value(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+/// Format a big-endian two's-complement unscaled decimal
+fn decimal_string(bytes: &[u8], scale: usize) -> Result<String, String> {
+    if bytes.len() > 16 {
+        return Err(format!("decimal of {} bytes is too large", bytes.len()));
+    }
+    let negative = bytes.first().is_some_and(|byte| byte & 0x80 != 0);
+    let unscaled = bytes
+        .iter()
+        .fold(if negative { -1i128 } else { 0 }, |unscaled, byte| {
+            (unscaled << 8) | i128::from(*byte)
+        });
+
+    let digits = unscaled.unsigned_abs().to_string();
+    let sign = if negative { "-" } else { "" };
+    if scale == 0 {
+        return Ok(format!("{}{}", sign, digits));
+    }
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    Ok(format!("{}{}.{}", sign, integer, fraction))
+}
+
+fn timestamp_string(value: i64, from: fn(i64) -> Option<DateTime<Utc>>) -> Result<String, String> {
+    from(value)
+        .map(|timestamp| timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+        .ok_or_else(|| format!("timestamp out of range: {}", value))
+}
+
+fn local_timestamp_string(
+    value: i64,
+    from: fn(i64) -> Option<DateTime<Utc>>,
+) -> Result<String, String> {
+    from(value)
+        .map(|timestamp| {
+            timestamp
+                .naive_utc()
+                .format("%Y-%m-%dT%H:%M:%S%.f")
+                .to_string()
+        })
+        .ok_or_else(|| format!("timestamp out of range: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Zigzag variable-length encoding of `value`
+    fn long(value: i64) -> Vec<u8> {
+        let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+        let mut bytes = Vec::new();
+        loop {
+            if zigzag < 0x80 {
+                bytes.push(zigzag as u8);
+                return bytes;
+            }
+            bytes.push((zigzag as u8 & 0x7F) | 0x80);
+            zigzag >>= 7;
+        }
+    }
+
+    fn string(value: &str) -> Vec<u8> {
+        let mut bytes = long(value.len() as i64);
+        bytes.extend_from_slice(value.as_bytes());
+        bytes
+    }
+
+    const READING: &str = r#"{
+        "type": "record",
+        "name": "Reading",
+        "namespace": "iot",
+        "fields": [
+            {"name": "device", "type": "string"},
+            {"name": "value", "type": "double"},
+            {"name": "count", "type": "long"},
+            {"name": "unit", "type": ["null", {"type": "enum", "name": "Unit", "symbols": ["C", "F"]}]},
+            {"name": "tags", "type": {"type": "map", "values": "string"}},
+            {"name": "at", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+            {"name": "price", "type": {"type": "bytes", "logicalType": "decimal", "precision": 6, "scale": 2}},
+            {"name": "previous", "type": ["null", "Reading"]}
+        ]
+    }"#;
+
+    fn reading(previous: Option<&[u8]>) -> Vec<u8> {
+        let mut bytes = string("sensor-1");
+        bytes.extend_from_slice(&21.5f64.to_le_bytes());
+        bytes.extend(long(-3));
+        bytes.extend(long(1)); // union branch 1: Unit
+        bytes.extend(long(1)); // F
+        bytes.extend(long(1)); // one map entry
+        bytes.extend(string("room"));
+        bytes.extend(string("kitchen"));
+        bytes.extend(long(0));
+        bytes.extend(long(1_700_000_000_123));
+        bytes.extend(long(2)); // -12.34 as 0xFB2E
+        bytes.extend_from_slice(&[0xFB, 0x2E]);
+        match previous {
+            Some(previous) => {
+                bytes.extend(long(1));
+                bytes.extend_from_slice(previous);
+            }
+            None => bytes.extend(long(0)),
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_avro_decode_record() {
+        let schema = AvroDefinition::parse(READING).unwrap();
+        assert!(
+            matches!(schema.schema(), Schema::Record(record) if record.name.fullname(None) == "iot.Reading")
+        );
+
+        let payload = reading(Some(&reading(None)));
+        let expected_previous = json!({
+            "device": "sensor-1",
+            "value": 21.5,
+            "count": -3,
+            "unit": "F",
+            "tags": {"room": "kitchen"},
+            "at": "2023-11-14T22:13:20.123Z",
+            "price": "-12.34",
+            "previous": null,
+        });
+        let value = schema.decode(&payload).unwrap();
+        assert_eq!(value["previous"], expected_previous);
+        assert_eq!(value["count"], -3);
+
+        // Single-object encoding header and fingerprint are skipped
+        let mut single_object = vec![0xC3, 0x01, 1, 2, 3, 4, 5, 6, 7, 8];
+        single_object.extend(reading(None));
+        assert_eq!(schema.decode(&single_object).unwrap(), expected_previous);
+    }
+
+    #[test]
+    fn test_avro_decode_errors() {
+        let schema = AvroDefinition::parse(READING).unwrap();
+        let payload = reading(None);
+        assert!(schema.decode(&payload[..payload.len() - 3]).is_err());
+
+        let mut trailing = payload.clone();
+        trailing.push(0);
+        assert!(schema.decode(&trailing).is_err());
+
+        assert!(AvroDefinition::parse(
+            r#"{"type": "record", "name": "R", "fields": [{"name": "a", "type": "Missing"}]}"#
+        )
+        .is_err());
+        assert!(AvroDefinition::parse("not json").is_err());
+    }
+
+    #[test]
+    fn test_avro_decode_arrays_of_null() {
+        let schema = AvroDefinition::parse(
+            r#"{"type": "record", "name": "Gaps", "fields": [
+                {"name": "gaps", "type": {"type": "array", "items": "null"}},
+                {"name": "since", "type": {"type": "int", "logicalType": "date"}}
+            ]}"#,
+        )
+        .unwrap();
+
+        // Three nulls take no bytes, then the end of the array
+        let mut payload = long(3);
+        payload.extend(long(0));
+        payload.extend(long(19_675));
+        assert_eq!(
+            schema.decode(&payload).unwrap(),
+            json!({"gaps": [null, null, null], "since": "2023-11-14"})
+        );
+    }
+}
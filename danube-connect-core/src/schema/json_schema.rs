@@ -0,0 +1,138 @@
+//! JSON Schema validation of payloads
+//!
+//! Schemas are JSON Schema documents (drafts 4 to 2020-12, detected from `$schema`).
+//! References to other files or URLs are not resolved, a schema must be self-contained.
+
+use crate::{ConnectorError, ConnectorResult};
+use jsonschema::Validator;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Number of validation errors listed in the error message of an invalid payload
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// A compiled JSON Schema payloads are validated against
+#[derive(Clone)]
+pub struct JsonSchema {
+    /// File the schema was loaded from, or another name of it, for error messages
+    name: String,
+    validator: Arc<Validator>,
+}
+
+impl JsonSchema {
+    /// Load and compile the JSON Schema at `path`
+    pub fn load(path: &str) -> ConnectorResult<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            ConnectorError::config(format!("Failed to read JSON schema {}: {}", path, e))
+        })?;
+        let schema: Value = serde_json::from_str(&content).map_err(|e| {
+            ConnectorError::config(format!("JSON schema {} is not valid JSON: {}", path, e))
+        })?;
+        Self::new(path, &schema)
+    }
+
+    /// Compile a JSON Schema, `name` identifies it in error messages
+    pub fn new(name: &str, schema: &Value) -> ConnectorResult<Self> {
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|e| ConnectorError::config(format!("Invalid JSON schema {}: {}", name, e)))?;
+        Ok(Self {
+            name: name.to_string(),
+            validator: Arc::new(validator),
+        })
+    }
+
+    /// Name of the schema in error messages
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Check that a payload is JSON matching the schema
+    ///
+    /// Fails with `InvalidData` listing the JSON pointer of each invalid value and why it
+    /// is invalid, e.g. `/amount: "12" is not of type "number"`.
+    pub fn validate(&self, payload: &[u8]) -> ConnectorResult<()> {
+        let value: Value = serde_json::from_slice(payload).map_err(|e| {
+            ConnectorError::invalid_data(
+                format!("Payload is not valid JSON for schema {}: {}", self.name, e),
+                payload.to_vec(),
+            )
+        })?;
+
+        let mut errors = self.validator.iter_errors(&value).peekable();
+        if errors.peek().is_none() {
+            return Ok(());
+        }
+        let errors: Vec<String> = errors
+            .take(MAX_REPORTED_ERRORS)
+            .map(|error| {
+                let path = error.instance_path.as_str();
+                format!("{}: {}", if path.is_empty() { "/" } else { path }, error)
+            })
+            .collect();
+        Err(ConnectorError::invalid_data(
+            format!(
+                "Payload does not match JSON schema {}: {}",
+                self.name,
+                errors.join("; ")
+            ),
+            payload.to_vec(),
+        ))
+    }
+}
+
+impl std::fmt::Debug for JsonSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonSchema")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_schema_validate() {
+        let schema = JsonSchema::new(
+            "payment",
+            &json!({
+                "type": "object",
+                "required": ["id", "amount"],
+                "properties": {
+                    "id": {"type": "string"},
+                    "amount": {"type": "number", "minimum": 0},
+                    "items": {"type": "array", "items": {"type": "object", "required": ["sku"]}}
+                }
+            }),
+        )
+        .unwrap();
+
+        assert!(schema.validate(br#"{"id": "p-1", "amount": 12.5}"#).is_ok());
+
+        let err = schema
+            .validate(br#"{"id": "p-1", "amount": "12", "items": [{"sku": "a"}, {}]}"#)
+            .unwrap_err();
+        assert!(err.is_invalid_data());
+        let message = err.to_string();
+        assert!(
+            message.contains("/amount: \"12\" is not of type \"number\""),
+            "{}",
+            message
+        );
+        assert!(
+            message.contains("/items/1: \"sku\" is a required property"),
+            "{}",
+            message
+        );
+
+        let err = schema.validate(br#"{"id": "p-1"}"#).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("/: \"amount\" is a required property"));
+
+        assert!(schema.validate(b"not json").unwrap_err().is_invalid_data());
+        assert!(JsonSchema::new("broken", &json!({"type": 12})).is_err());
+    }
+}
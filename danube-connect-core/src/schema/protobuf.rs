@@ -0,0 +1,96 @@
+//! Protobuf decoding with runtime message descriptors
+//!
+//! Schemas are compiled file descriptor sets, as written by
+//! `protoc --include_imports --descriptor_set_out=<file>`, plus the fully qualified name of
+//! the payload message. Decoded messages use the proto field names, keep 64-bit integers
+//! as numbers and include fields with default values.
+
+use crate::{ConnectorError, ConnectorResult};
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, SerializeOptions};
+use serde_json::Value;
+
+/// Look up `message_type` in an encoded file descriptor set
+pub(crate) fn message_descriptor(
+    descriptor_set: &[u8],
+    message_type: &str,
+) -> ConnectorResult<MessageDescriptor> {
+    let pool = DescriptorPool::decode(descriptor_set)
+        .map_err(|e| ConnectorError::config(format!("Invalid Protobuf descriptor set: {}", e)))?;
+    pool.get_message_by_name(message_type).ok_or_else(|| {
+        ConnectorError::config(format!(
+            "Message type {} not found in the Protobuf descriptor set",
+            message_type
+        ))
+    })
+}
+
+/// Decode a Protobuf message
+pub(crate) fn decode(descriptor: &MessageDescriptor, payload: &[u8]) -> Result<Value, String> {
+    let message = DynamicMessage::decode(descriptor.clone(), payload).map_err(|e| e.to_string())?;
+    let options = SerializeOptions::new()
+        .stringify_64_bit_integers(false)
+        .use_proto_field_name(true)
+        .skip_default_fields(false);
+    message
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::prost::Message;
+    use prost_reflect::prost_types::field_descriptor_proto::{Label, Type};
+    use prost_reflect::prost_types::{
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    };
+    use serde_json::json;
+
+    fn field(name: &str, number: i32, field_type: Type) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(field_type as i32),
+            ..Default::default()
+        }
+    }
+
+    /// Descriptor set of `message Reading { string device = 1; double value = 2; int64 count = 3; }`
+    fn descriptor_set() -> Vec<u8> {
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("reading.proto".to_string()),
+                package: Some("iot".to_string()),
+                syntax: Some("proto3".to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some("Reading".to_string()),
+                    field: vec![
+                        field("device", 1, Type::String),
+                        field("value", 2, Type::Double),
+                        field("count", 3, Type::Int64),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_protobuf_decode() {
+        let descriptor = message_descriptor(&descriptor_set(), "iot.Reading").unwrap();
+
+        // device = "s1", value = 1.5, count left at its default
+        let mut payload = vec![0x0A, 2, b's', b'1', 0x11];
+        payload.extend_from_slice(&1.5f64.to_le_bytes());
+        assert_eq!(
+            decode(&descriptor, &payload).unwrap(),
+            json!({"device": "s1", "value": 1.5, "count": 0})
+        );
+
+        assert!(decode(&descriptor, &[0x0A, 10]).is_err());
+        assert!(message_descriptor(&descriptor_set(), "iot.Missing").is_err());
+    }
+}
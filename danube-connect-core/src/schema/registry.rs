@@ -0,0 +1,106 @@
+//! Topic schemas in Danube's schema registry
+//!
+//! The broker keeps a schema per topic, registered by the producers creating it. Its schema
+//! types are `Bytes`, `String`, `Int64` and `Json` (with the JSON Schema as definition);
+//! `Avro` and `Protobuf` payloads are registered as `Bytes`.
+
+use super::SchemaType;
+use crate::{ConnectorError, ConnectorResult};
+use danube_client::{Schema, SchemaType as ClientSchemaType};
+
+/// Name of the schema the client registers for producers without one
+///
+/// It says nothing about the payloads, a topic carrying it has no registered schema.
+const CLIENT_DEFAULT_SCHEMA: &str = "string_schema";
+
+/// Schema a source registers for a topic when creating its producer
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicSchema {
+    /// Schema type of the payloads
+    pub schema_type: SchemaType,
+    /// Schema definition, the JSON Schema of `Json` payloads (other types ignore it)
+    pub definition: Option<String>,
+}
+
+impl TopicSchema {
+    /// Schema of `schema_type` without a definition
+    pub fn new(schema_type: SchemaType) -> Self {
+        Self {
+            schema_type,
+            definition: None,
+        }
+    }
+
+    /// Set the schema definition
+    pub fn with_definition(mut self, definition: impl Into<String>) -> Self {
+        self.definition = Some(definition.into());
+        self
+    }
+
+    /// Schema of `schema_type` with the definition read from `definition_file`, if any
+    pub fn load(schema_type: SchemaType, definition_file: Option<&str>) -> ConnectorResult<Self> {
+        let schema = Self::new(schema_type);
+        match definition_file {
+            Some(path) => {
+                let definition = std::fs::read_to_string(path).map_err(|e| {
+                    ConnectorError::config(format!("Failed to read schema file {}: {}", path, e))
+                })?;
+                Ok(schema.with_definition(definition))
+            }
+            None => Ok(schema),
+        }
+    }
+
+    /// Schema type in the registry, `Avro` and `Protobuf` are registered as `Bytes`
+    pub(crate) fn registry_type(&self) -> ClientSchemaType {
+        match self.schema_type {
+            SchemaType::Bytes | SchemaType::Avro | SchemaType::Protobuf => ClientSchemaType::Bytes,
+            SchemaType::String => ClientSchemaType::String,
+            SchemaType::Int64 => ClientSchemaType::Int64,
+            SchemaType::Json => ClientSchemaType::Json(self.definition.clone().unwrap_or_default()),
+        }
+    }
+}
+
+/// Schema type of a topic's registered schema, `None` for the client default
+pub(crate) fn registered_schema_type(schema: &Schema) -> Option<SchemaType> {
+    match &schema.type_schema {
+        ClientSchemaType::String if schema.name == CLIENT_DEFAULT_SCHEMA => None,
+        ClientSchemaType::Bytes => Some(SchemaType::Bytes),
+        ClientSchemaType::String => Some(SchemaType::String),
+        ClientSchemaType::Int64 => Some(SchemaType::Int64),
+        ClientSchemaType::Json(_) => Some(SchemaType::Json),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_schema_types() {
+        let schema = TopicSchema::new(SchemaType::Json).with_definition(r#"{"type":"object"}"#);
+        assert!(matches!(
+            schema.registry_type(),
+            ClientSchemaType::Json(definition) if definition == r#"{"type":"object"}"#
+        ));
+        assert!(matches!(
+            TopicSchema::new(SchemaType::Avro).registry_type(),
+            ClientSchemaType::Bytes
+        ));
+
+        let registered = |name: &str, type_schema| Schema::new(name.to_string(), type_schema);
+        assert_eq!(
+            registered_schema_type(&registered("events", ClientSchemaType::Json(String::new()))),
+            Some(SchemaType::Json)
+        );
+        assert_eq!(
+            registered_schema_type(&registered("names", ClientSchemaType::String)),
+            Some(SchemaType::String)
+        );
+        assert_eq!(
+            registered_schema_type(&registered(CLIENT_DEFAULT_SCHEMA, ClientSchemaType::String)),
+            None
+        );
+    }
+}
@@ -4,8 +4,9 @@
 //! - `SinkConnector`: For consuming from Danube and writing to external systems
 //! - `SourceConnector`: For reading from external systems and producing to Danube
 
-use crate::{ConnectorConfig, ConnectorResult, SinkRecord, SourceRecord};
+use crate::{ConnectorConfig, ConnectorError, ConnectorResult, SinkRecord, SourceRecord};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 
 /// Trait for implementing Sink Connectors (Danube → External System)
 ///
@@ -37,6 +38,7 @@ use async_trait::async_trait;
 ///             consumer_name: "http-sink-consumer".to_string(),
 ///             subscription: "http-sink-sub".to_string(),
 ///             subscription_type: SubscriptionType::Exclusive,
+///             ..Default::default()
 ///         }])
 ///     }
 ///     
@@ -81,13 +83,14 @@ pub trait SinkConnector: Send + Sync {
 
     /// Process a single message from Danube
     ///
-    /// This method is called for each message received from the Danube topic.
+    /// The default `process_batch()` calls this method for each record of a batch.
     ///
     /// # Return Value
     ///
     /// - `Ok(())`: Message processed successfully, will be acknowledged
     /// - `Err(ConnectorError::Retryable)`: Transient failure, message will be retried
-    /// - `Err(ConnectorError::Fatal)`: Permanent failure, connector will stop
+    /// - `Err(ConnectorError::Fatal)`: Permanent failure, handled by the `failure_policy`:
+    ///   sent to the DLQ by default, the connector stops without one
     /// - `Err(ConnectorError::InvalidData)`: Bad message, will be skipped or sent to DLQ
     ///
     /// # Errors
@@ -97,6 +100,15 @@ pub trait SinkConnector: Send + Sync {
 
     /// Optional: Process a batch of messages for better throughput
     ///
+    /// The runtime accumulates messages per consumer and calls this method once
+    /// `processing.batch_size` messages are buffered or `processing.batch_timeout_ms`
+    /// elapsed since the first one. It is also called with an empty batch as a periodic
+    /// flush check while the topics are idle.
+    ///
+    /// On success all records of the batch are acknowledged. Retryable errors retry the
+    /// whole batch, so writes should be idempotent. On `ConnectorError::InvalidData` the
+    /// runtime retries the records one at a time to isolate the invalid ones.
+    ///
     /// Override this method to implement batch processing for better performance.
     /// The default implementation calls `process()` for each record sequentially.
     ///
@@ -115,6 +127,7 @@ pub trait SinkConnector: Send + Sync {
     /// #             consumer_name: "test-consumer".to_string(),
     /// #             subscription: "test-sub".to_string(),
     /// #             subscription_type: SubscriptionType::Exclusive,
+    /// #             ..Default::default()
     /// #         }])
     /// #     }
     /// #     async fn process(&mut self, record: SinkRecord) -> ConnectorResult<()> { Ok(()) }
@@ -132,6 +145,69 @@ pub trait SinkConnector: Send + Sync {
         Ok(())
     }
 
+    /// Optional: When the runtime should acknowledge processed messages
+    ///
+    /// Connectors that buffer records in `process()` and write them to the external
+    /// system later must return `AckMode::Deferred` and report written records through
+    /// `flushed_offsets()`, otherwise a crash loses messages that were already acknowledged.
+    fn ack_mode(&self) -> AckMode {
+        AckMode::Immediate
+    }
+
+    /// Optional: Report the records durably written since the last call
+    ///
+    /// Only called when `ack_mode()` returns `AckMode::Deferred`. Each returned `Offset`
+    /// is a watermark: `partition` is the Danube topic (as returned by `SinkRecord::topic()`)
+    /// and `value` the highest offset written to the external system. The runtime
+    /// acknowledges every processed message of that topic up to and including `value`.
+    ///
+    /// The records a transform split a message into share its offset: a message is written
+    /// once its last record (`SinkRecord::is_last_split()`) is, and `SinkRecord::position()`
+    /// tells the records apart, e.g. to skip records still buffered when a batch is retried.
+    ///
+    /// The runtime calls this after each delivered batch, on every periodic flush
+    /// check and after `shutdown()`.
+    async fn flushed_offsets(&mut self) -> ConnectorResult<Vec<Offset>> {
+        Ok(Vec::new())
+    }
+
+    /// Optional: Create another instance of this connector for a parallel worker
+    ///
+    /// With `processing.parallelism` greater than 1 the runtime calls this after
+    /// `initialize()` once per additional worker and splits the consumers between the
+    /// instances, so that different topics are processed concurrently. Calls on a single
+    /// instance are never concurrent. All records of a topic go to the same instance,
+    /// unless `processing.ordering` is `OrderingMode::Key`: records of a topic are then
+    /// spread over all instances, and all records with the same routing key go to the
+    /// same instance.
+    ///
+    /// The returned instance must be ready to process records (e.g. share the client
+    /// connection of `self`) and is shut down with `shutdown()` like this one.
+    ///
+    /// Returns `None` by default: all consumers are then processed by this instance.
+    fn fork(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Optional: Apply a changed configuration without restarting
+    ///
+    /// Called by a runtime created with `SinkRuntime::with_config_reload()` when the
+    /// configuration file changed or on SIGHUP, with the new content of the file. Parse and
+    /// validate it, and keep the current configuration if it is invalid. Each connector
+    /// instance is reconfigured after its buffered batches were delivered; the runtime then
+    /// calls `consumer_configs()` again, attaches consumers for added topics and closes
+    /// those of removed ones, leaving unchanged consumers running.
+    ///
+    /// Returns a configuration error by default: the change is only applied on restart.
+    async fn reconfigure(&mut self, _config: &str) -> ConnectorResult<()> {
+        Err(ConnectorError::config(
+            "Connector does not support reconfiguration, restart it to apply the change",
+        ))
+    }
+
     /// Optional: Called before shutdown for cleanup
     ///
     /// Use this to:
@@ -145,8 +221,10 @@ pub trait SinkConnector: Send + Sync {
 
     /// Optional: Health check implementation
     ///
-    /// This method is called periodically to verify the connector is healthy.
-    /// Check connectivity to external systems and return an error if unhealthy.
+    /// This method is called every `processing.health_check_interval_ms` to verify the
+    /// connector is healthy. Check connectivity to external systems and return an error
+    /// if unhealthy; the result backs the health gauge and the `/health` and `/ready`
+    /// endpoints.
     async fn health_check(&self) -> ConnectorResult<()> {
         Ok(())
     }
@@ -184,6 +262,7 @@ pub trait SinkConnector: Send + Sync {
 ///                 topic: "/default/file_data".to_string(),
 ///                 partitions: 0,
 ///                 reliable_dispatch: false,
+///                 ..Default::default()
 ///             }
 ///         ])
 ///     }
@@ -210,6 +289,16 @@ pub trait SourceConnector: Send + Sync {
     /// - Initialize internal state
     async fn initialize(&mut self, config: ConnectorConfig) -> ConnectorResult<()>;
 
+    /// Optional: Resume from the offsets saved by the configured offset store
+    ///
+    /// Called before `initialize()` when an offset store is configured (see
+    /// `ConnectorConfig::offset_store`), with the latest committed offset per partition.
+    /// The list is empty on first start.
+    async fn restore_offsets(&mut self, offsets: Vec<Offset>) -> ConnectorResult<()> {
+        let _ = offsets; // Suppress unused warning
+        Ok(())
+    }
+
     /// Get producer configurations for all topics this connector will publish to
     ///
     /// This method should return the complete list of Danube topics and their
@@ -218,7 +307,9 @@ pub trait SourceConnector: Send + Sync {
     ///
     /// The connector doesn't need to know about producers - it just returns
     /// SourceRecords with topic information, and the runtime maps them to the
-    /// appropriate pre-created producer.
+    /// appropriate pre-created producer. Records for other topics get a producer on
+    /// first use (from `SourceRecord::producer_config`) if the topic matches
+    /// `processing.allowed_topics`.
     ///
     /// # Returns
     ///
@@ -232,7 +323,8 @@ pub trait SourceConnector: Send + Sync {
     /// - Empty vector when no data is available (non-blocking)
     ///
     /// The runtime will handle publishing records to Danube and calling `commit()`
-    /// after successful acknowledgment.
+    /// after successful acknowledgment. Set `SourceRecord::offset` to the position of
+    /// each record in the source system to receive it back in `commit()`.
     ///
     /// # Errors
     ///
@@ -246,27 +338,56 @@ pub trait SourceConnector: Send + Sync {
     /// published and acknowledged by Danube. Use it to save checkpoints or
     /// acknowledge messages in the source system.
     ///
+    /// Records of a poll are published in order. If publishing fails, the records
+    /// published before the failure are still committed and the remaining ones are
+    /// dropped, so the connector should resume after the last committed offset.
+    ///
     /// # Arguments
     ///
-    /// * `offsets` - List of offsets that were successfully published
+    /// * `offsets` - The `SourceRecord::offset` values of the published records, in
+    ///   publish order (records without an offset are left out)
     async fn commit(&mut self, offsets: Vec<Offset>) -> ConnectorResult<()> {
         let _ = offsets; // Suppress unused warning
         Ok(())
     }
 
+    /// Optional: Apply a changed configuration without restarting
+    ///
+    /// Called by a runtime created with `SourceRuntime::with_config_reload()` between two
+    /// polls, see `SinkConnector::reconfigure()`. The runtime then creates the producers of
+    /// topics added to `producer_configs()`.
+    async fn reconfigure(&mut self, _config: &str) -> ConnectorResult<()> {
+        Err(ConnectorError::config(
+            "Connector does not support reconfiguration, restart it to apply the change",
+        ))
+    }
+
     /// Optional: Called before shutdown
     async fn shutdown(&mut self) -> ConnectorResult<()> {
         Ok(())
     }
 
     /// Optional: Health check implementation
+    ///
+    /// Called between polls every `processing.health_check_interval_ms`, see
+    /// `SinkConnector::health_check()`.
     async fn health_check(&self) -> ConnectorResult<()> {
         Ok(())
     }
 }
 
-/// Checkpoint/offset information for source connectors
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Acknowledgement mode of a sink connector
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AckMode {
+    /// Acknowledge each message as soon as `process()` returns `Ok` (default)
+    #[default]
+    Immediate,
+    /// Acknowledge messages only once `flushed_offsets()` reports them as written
+    Deferred,
+}
+
+/// Checkpoint/offset information for source connectors and flushed sink records
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Offset {
     /// The partition or source identifier
     pub partition: String,
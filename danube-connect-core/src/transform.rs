@@ -0,0 +1,709 @@
+//! Single-message transforms (SMT) applied by the runtimes.
+//!
+//! A chain of transforms sits between Danube and the connector: sink runtimes apply it to
+//! every consumed `SinkRecord` before `process_batch()`, source runtimes to every polled
+//! `SourceRecord` before publishing. Each transform changes, splits or drops the record, so
+//! data can be reshaped without forking a connector.
+//!
+//! The built-in transforms are configured as `[[transforms]]` tables of the connector
+//! configuration (see `TransformSettings`) and run in the order they are listed. Custom
+//! transforms implement `Transform` and are added with `with_transform()` of the runtimes,
+//! running after the configured ones.
+//!
+//! Field paths address the JSON payload with dots, `user.email` being the `email` field of
+//! the `user` object.
+
+#[cfg(feature = "scripting")]
+mod script;
+
+use crate::{
+    ConnectorError, ConnectorResult, MaskMode, SinkRecord, SourceRecord, TransformSettings,
+};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// A single-message transform
+///
+/// `R` is `SinkRecord` for transforms applied by sink runtimes and `SourceRecord` for
+/// transforms applied by source runtimes. Transforms working on payload and attributes
+/// only can implement it for every `TransformRecord`.
+pub trait Transform<R>: Send + Sync {
+    /// Transform a record into the records replacing it: none to drop it, several to
+    /// split it
+    ///
+    /// Dropped records are acknowledged by sinks and committed by sources like published
+    /// ones. A split sink record is acknowledged once all its records are processed. An
+    /// error is handled like a record the connector failed to process:
+    /// `ConnectorError::InvalidData` sends sink records to the dead-letter topic, source
+    /// records failing a transform are skipped.
+    fn apply(&self, record: R) -> ConnectorResult<Vec<R>>;
+}
+
+/// Payload and attributes of a record, shared by `SinkRecord` and `SourceRecord`
+pub trait TransformRecord: Send {
+    /// The message payload
+    fn payload(&self) -> &[u8];
+    /// Replace the message payload
+    fn set_payload(&mut self, payload: Vec<u8>);
+    /// The message attributes
+    fn attributes(&self) -> &HashMap<String, String>;
+    /// The message attributes, for changes
+    fn attributes_mut(&mut self) -> &mut HashMap<String, String>;
+}
+
+impl TransformRecord for SinkRecord {
+    fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    fn set_payload(&mut self, payload: Vec<u8>) {
+        self.payload = payload;
+    }
+
+    fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
+
+    fn attributes_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.attributes
+    }
+}
+
+impl TransformRecord for SourceRecord {
+    fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    fn set_payload(&mut self, payload: Vec<u8>) {
+        self.payload = payload;
+    }
+
+    fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
+
+    fn attributes_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.attributes
+    }
+}
+
+/// Transforms applied in order to every record of a runtime
+pub(crate) struct TransformChain<R> {
+    transforms: Vec<Arc<dyn Transform<R>>>,
+}
+
+impl<R> Clone for TransformChain<R> {
+    fn clone(&self) -> Self {
+        Self {
+            transforms: self.transforms.clone(),
+        }
+    }
+}
+
+impl<R> TransformChain<R> {
+    /// Append a transform to the chain
+    pub(crate) fn push(&mut self, transform: impl Transform<R> + 'static) {
+        self.transforms.push(Arc::new(transform));
+    }
+
+    /// Number of transforms in the chain
+    pub(crate) fn len(&self) -> usize {
+        self.transforms.len()
+    }
+
+    /// Whether the chain has no transforms
+    pub(crate) fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// Apply all transforms, each to every record the previous one returned
+    pub(crate) fn apply(&self, record: R) -> ConnectorResult<Vec<R>> {
+        let mut records = vec![record];
+        for transform in &self.transforms {
+            let mut transformed = Vec::with_capacity(records.len());
+            for record in records {
+                transformed.extend(transform.apply(record)?);
+            }
+            records = transformed;
+            if records.is_empty() {
+                break;
+            }
+        }
+        Ok(records)
+    }
+}
+
+impl TransformChain<SinkRecord> {
+    /// Build the chain of a sink runtime from the configured transforms
+    ///
+    /// `set_topic` and `set_key` only apply to source records and are rejected.
+    pub(crate) fn for_sink(settings: &[TransformSettings]) -> ConnectorResult<Self> {
+        let mut transforms: Vec<Arc<dyn Transform<SinkRecord>>> = Vec::new();
+        for setting in settings {
+            transforms.push(match setting {
+                TransformSettings::SetTopic { .. } | TransformSettings::SetKey { .. } => {
+                    return Err(ConnectorError::config(format!(
+                        "Transform {} only applies to source connectors",
+                        setting.name()
+                    )));
+                }
+                TransformSettings::Script {
+                    path,
+                    max_operations,
+                } => script_transform(path, *max_operations)?,
+                _ => record_transform(setting)?,
+            });
+        }
+        Ok(Self { transforms })
+    }
+
+    /// Apply all transforms to a consumed message, numbering the records it is split into
+    /// (see `SinkRecord::position()`)
+    pub(crate) fn apply_to_message(&self, record: SinkRecord) -> ConnectorResult<Vec<SinkRecord>> {
+        let mut records = self.apply(record)?;
+        let split_count = records.len();
+        for (split_index, record) in records.iter_mut().enumerate() {
+            record.danube_metadata.split_index = split_index;
+            record.danube_metadata.split_count = split_count;
+        }
+        Ok(records)
+    }
+}
+
+impl TransformChain<SourceRecord> {
+    /// Build the chain of a source runtime from the configured transforms
+    pub(crate) fn for_source(settings: &[TransformSettings]) -> ConnectorResult<Self> {
+        let mut transforms: Vec<Arc<dyn Transform<SourceRecord>>> = Vec::new();
+        for setting in settings {
+            transforms.push(match setting {
+                TransformSettings::SetTopic { topic } => {
+                    if topic.is_empty() {
+                        return Err(ConnectorError::config("set_topic requires a topic"));
+                    }
+                    Arc::new(SetTopic {
+                        topic: topic.clone(),
+                    })
+                }
+                TransformSettings::SetKey { attribute, field } => Arc::new(SetKey {
+                    source: ValueSource::new(setting.name(), attribute, field)?,
+                }),
+                TransformSettings::Script {
+                    path,
+                    max_operations,
+                } => script_transform(path, *max_operations)?,
+                _ => record_transform(setting)?,
+            });
+        }
+        Ok(Self { transforms })
+    }
+}
+
+/// Build a transform working on payload and attributes
+fn record_transform<R: TransformRecord>(
+    setting: &TransformSettings,
+) -> ConnectorResult<Arc<dyn Transform<R>>> {
+    Ok(match setting {
+        TransformSettings::Filter {
+            attribute,
+            field,
+            equals,
+            negate,
+        } => Arc::new(Filter {
+            source: ValueSource::new(setting.name(), attribute, field)?,
+            equals: equals.clone(),
+            negate: *negate,
+        }),
+        TransformSettings::DropFields { fields } => Arc::new(DropFields {
+            fields: fields.clone(),
+        }),
+        TransformSettings::RenameFields { fields } => Arc::new(RenameFields {
+            fields: fields.clone(),
+        }),
+        TransformSettings::InsertField { field, value } => Arc::new(InsertField {
+            field: field.clone(),
+            value: value.clone(),
+        }),
+        TransformSettings::CopyAttributes { attributes, field } => Arc::new(CopyAttributes {
+            attributes: attributes.clone(),
+            field: field.clone(),
+        }),
+        TransformSettings::MaskFields {
+            fields,
+            mode,
+            replacement,
+        } => Arc::new(MaskFields {
+            fields: fields.clone(),
+            mode: *mode,
+            replacement: replacement.clone(),
+        }),
+        TransformSettings::SetTopic { .. }
+        | TransformSettings::SetKey { .. }
+        | TransformSettings::Script { .. } => {
+            unreachable!("record transforms exclude record type specific transforms")
+        }
+    })
+}
+
+/// Build a Rhai script transform
+#[cfg(feature = "scripting")]
+fn script_transform<R>(path: &str, max_operations: u64) -> ConnectorResult<Arc<dyn Transform<R>>>
+where
+    script::ScriptTransform: Transform<R>,
+{
+    Ok(Arc::new(script::ScriptTransform::new(
+        path,
+        max_operations,
+    )?))
+}
+
+/// Script transforms are unavailable without the `scripting` feature
+#[cfg(not(feature = "scripting"))]
+fn script_transform<R>(path: &str, _max_operations: u64) -> ConnectorResult<Arc<dyn Transform<R>>> {
+    Err(ConnectorError::config(format!(
+        "Script transform {} requires the scripting feature of danube-connect-core",
+        path
+    )))
+}
+
+/// Where a transform reads a value from: an attribute or a payload field
+enum ValueSource {
+    Attribute(String),
+    Field(String),
+}
+
+impl ValueSource {
+    /// Exactly one of `attribute` and `field` must be set
+    fn new(
+        transform: &str,
+        attribute: &Option<String>,
+        field: &Option<String>,
+    ) -> ConnectorResult<Self> {
+        match (attribute, field) {
+            (Some(attribute), None) => Ok(Self::Attribute(attribute.clone())),
+            (None, Some(field)) => Ok(Self::Field(field.clone())),
+            _ => Err(ConnectorError::config(format!(
+                "Transform {} requires either attribute or field",
+                transform
+            ))),
+        }
+    }
+
+    /// The value of a record as a string, `None` if missing or the payload is not JSON
+    fn read<R: TransformRecord>(&self, record: &R) -> Option<String> {
+        match self {
+            Self::Attribute(attribute) => record.attributes().get(attribute).cloned(),
+            Self::Field(field) => {
+                let payload: Value = serde_json::from_slice(record.payload()).ok()?;
+                get_path(&payload, field)
+                    .filter(|value| !value.is_null())
+                    .map(value_string)
+            }
+        }
+    }
+}
+
+/// Keeps the records whose attribute or field exists and, if set, equals `equals`
+struct Filter {
+    source: ValueSource,
+    equals: Option<Value>,
+    negate: bool,
+}
+
+impl<R: TransformRecord> Transform<R> for Filter {
+    fn apply(&self, record: R) -> ConnectorResult<Vec<R>> {
+        let matches = match (self.source.read(&record), &self.equals) {
+            (Some(value), Some(expected)) => value == value_string(expected),
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if matches != self.negate {
+            Ok(vec![record])
+        } else {
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Removes fields from the payload
+struct DropFields {
+    fields: Vec<String>,
+}
+
+impl<R: TransformRecord> Transform<R> for DropFields {
+    fn apply(&self, record: R) -> ConnectorResult<Vec<R>> {
+        update_payload(record, |payload| {
+            for field in &self.fields {
+                remove_path(payload, field);
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Moves payload fields to new paths
+struct RenameFields {
+    fields: BTreeMap<String, String>,
+}
+
+impl<R: TransformRecord> Transform<R> for RenameFields {
+    fn apply(&self, record: R) -> ConnectorResult<Vec<R>> {
+        update_payload(record, |payload| {
+            for (from, to) in &self.fields {
+                if let Some(value) = remove_path(payload, from) {
+                    insert_path(payload, to, value)?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Sets a payload field to a fixed value
+struct InsertField {
+    field: String,
+    value: Value,
+}
+
+impl<R: TransformRecord> Transform<R> for InsertField {
+    fn apply(&self, record: R) -> ConnectorResult<Vec<R>> {
+        update_payload(record, |payload| {
+            insert_path(payload, &self.field, self.value.clone())
+        })
+    }
+}
+
+/// Copies attributes into the payload, at the root or into an object at `field`
+struct CopyAttributes {
+    /// Attributes to copy, all if empty
+    attributes: Vec<String>,
+    field: Option<String>,
+}
+
+impl<R: TransformRecord> Transform<R> for CopyAttributes {
+    fn apply(&self, record: R) -> ConnectorResult<Vec<R>> {
+        let copied: Vec<(String, Value)> = record
+            .attributes()
+            .iter()
+            .filter(|(name, _)| self.attributes.is_empty() || self.attributes.contains(name))
+            .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+            .collect();
+        if copied.is_empty() {
+            return Ok(vec![record]);
+        }
+
+        update_payload(record, |payload| {
+            for (name, value) in copied {
+                let path = match &self.field {
+                    Some(field) => format!("{}.{}", field, name),
+                    None => name,
+                };
+                insert_path(payload, &path, value)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Replaces sensitive payload fields with a fixed string or their SHA-256 hash
+struct MaskFields {
+    fields: Vec<String>,
+    mode: MaskMode,
+    replacement: String,
+}
+
+impl<R: TransformRecord> Transform<R> for MaskFields {
+    fn apply(&self, record: R) -> ConnectorResult<Vec<R>> {
+        update_payload(record, |payload| {
+            for field in &self.fields {
+                if let Some(value) = get_path_mut(payload, field) {
+                    *value = match self.mode {
+                        MaskMode::Mask => Value::String(self.replacement.clone()),
+                        MaskMode::Hash => {
+                            let digest = Sha256::digest(value_string(value).as_bytes());
+                            Value::String(format!("{:x}", digest))
+                        }
+                    };
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Routes source records to a fixed topic
+struct SetTopic {
+    topic: String,
+}
+
+impl Transform<SourceRecord> for SetTopic {
+    fn apply(&self, mut record: SourceRecord) -> ConnectorResult<Vec<SourceRecord>> {
+        record.topic = self.topic.clone();
+        Ok(vec![record])
+    }
+}
+
+/// Sets the routing key of source records from an attribute or payload field
+///
+/// Records without the attribute or field keep their key.
+struct SetKey {
+    source: ValueSource,
+}
+
+impl Transform<SourceRecord> for SetKey {
+    fn apply(&self, mut record: SourceRecord) -> ConnectorResult<Vec<SourceRecord>> {
+        if let Some(key) = self.source.read(&record) {
+            record.key = Some(key);
+        }
+        Ok(vec![record])
+    }
+}
+
+/// Parse the JSON payload of a record, change it and serialize it back
+fn update_payload<R: TransformRecord>(
+    mut record: R,
+    update: impl FnOnce(&mut Value) -> ConnectorResult<()>,
+) -> ConnectorResult<Vec<R>> {
+    let mut payload: Value = serde_json::from_slice(record.payload()).map_err(|e| {
+        ConnectorError::invalid_data(
+            format!("Transform requires a JSON payload: {}", e),
+            record.payload().to_vec(),
+        )
+    })?;
+    update(&mut payload)?;
+
+    let payload =
+        serde_json::to_vec(&payload).map_err(|e| ConnectorError::Serialization(e.to_string()))?;
+    record.set_payload(payload);
+    Ok(vec![record])
+}
+
+/// A value as string: strings as they are, other values as JSON
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn get_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(value, |value, segment| value.as_object()?.get(segment))
+}
+
+fn get_path_mut<'a>(value: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    path.split('.').try_fold(value, |value, segment| {
+        value.as_object_mut()?.get_mut(segment)
+    })
+}
+
+/// Remove the value at a path, returning it if it existed
+fn remove_path(value: &mut Value, path: &str) -> Option<Value> {
+    let (parent, last) = match path.rsplit_once('.') {
+        Some((parent, last)) => (get_path_mut(value, parent)?, last),
+        None => (value, path),
+    };
+    parent.as_object_mut()?.remove(last)
+}
+
+/// Set the value at a path, creating missing objects on the way
+fn insert_path(value: &mut Value, path: &str, new_value: Value) -> ConnectorResult<()> {
+    let mut segments: Vec<&str> = path.split('.').collect();
+    let last = segments.pop().unwrap_or_default();
+
+    let mut current = value;
+    for segment in segments {
+        current = object_mut(current, path)?
+            .entry(segment)
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    object_mut(current, path)?.insert(last.to_string(), new_value);
+    Ok(())
+}
+
+fn object_mut<'a>(value: &'a mut Value, path: &str) -> ConnectorResult<&'a mut Map<String, Value>> {
+    value.as_object_mut().ok_or_else(|| {
+        ConnectorError::invalid_data(
+            format!("Cannot set field {}: parent is not a JSON object", path),
+            Vec::new(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(payload: Value) -> SourceRecord {
+        SourceRecord::from_json("/default/events", &payload)
+            .unwrap()
+            .with_attribute("env", "prod")
+    }
+
+    fn payload(record: &SourceRecord) -> Value {
+        serde_json::from_slice(&record.payload).unwrap()
+    }
+
+    fn chain(toml: &str) -> TransformChain<SourceRecord> {
+        #[derive(serde::Deserialize)]
+        struct Config {
+            transforms: Vec<TransformSettings>,
+        }
+        let config: Config = toml::from_str(toml).unwrap();
+        TransformChain::for_source(&config.transforms).unwrap()
+    }
+
+    #[test]
+    fn test_filter() {
+        let chain = chain(
+            r#"
+            [[transforms]]
+            type = "filter"
+            attribute = "env"
+            equals = "prod"
+
+            [[transforms]]
+            type = "filter"
+            field = "status"
+            equals = "deleted"
+            negate = true
+            "#,
+        );
+
+        let kept = record(json!({"status": "active"}));
+        assert_eq!(chain.apply(kept).unwrap().len(), 1);
+
+        let deleted = record(json!({"status": "deleted"}));
+        assert!(chain.apply(deleted).unwrap().is_empty());
+
+        let staging = record(json!({"status": "active"})).with_attribute("env", "staging");
+        assert!(chain.apply(staging).unwrap().is_empty());
+
+        // Non-JSON payloads never match a field predicate
+        let binary = SourceRecord::new("/default/events", vec![0xff]).with_attribute("env", "prod");
+        assert_eq!(chain.apply(binary).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_field_transforms() {
+        let chain = chain(
+            r#"
+            [[transforms]]
+            type = "drop_fields"
+            fields = ["internal", "user.password"]
+
+            [[transforms]]
+            type = "rename_fields"
+            fields = { "user.name" = "customer.name" }
+
+            [[transforms]]
+            type = "insert_field"
+            field = "meta.version"
+            value = 2
+
+            [[transforms]]
+            type = "copy_attributes"
+            field = "headers"
+            "#,
+        );
+
+        let transformed = chain
+            .apply(record(json!({
+                "id": 1,
+                "internal": true,
+                "user": {"name": "Ada", "password": "secret"}
+            })))
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            payload(&transformed),
+            json!({
+                "id": 1,
+                "user": {},
+                "customer": {"name": "Ada"},
+                "meta": {"version": 2},
+                "headers": {"env": "prod"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_mask_fields() {
+        let chain = chain(
+            r#"
+            [[transforms]]
+            type = "mask_fields"
+            fields = ["card"]
+
+            [[transforms]]
+            type = "mask_fields"
+            fields = ["email", "missing"]
+            mode = "hash"
+            "#,
+        );
+
+        let transformed = chain
+            .apply(record(json!({"card": "4111", "email": "ada@example.com"})))
+            .unwrap()
+            .remove(0);
+        let transformed = payload(&transformed);
+        assert_eq!(transformed["card"], "****");
+        let email = transformed["email"].as_str().unwrap();
+        assert_eq!(email.len(), 64);
+        assert_ne!(email, "ada@example.com");
+        assert!(transformed.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_set_topic_and_key() {
+        let chain = chain(
+            r#"
+            [[transforms]]
+            type = "set_topic"
+            topic = "/default/orders"
+
+            [[transforms]]
+            type = "set_key"
+            field = "order.id"
+            "#,
+        );
+
+        let transformed = chain
+            .apply(record(json!({"order": {"id": 42}})))
+            .unwrap()
+            .remove(0);
+        assert_eq!(transformed.topic, "/default/orders");
+        assert_eq!(transformed.key.as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn test_invalid_transforms() {
+        let set_topic = [TransformSettings::SetTopic {
+            topic: "/default/orders".to_string(),
+        }];
+        assert!(TransformChain::<SinkRecord>::for_sink(&set_topic).is_err());
+
+        let filter = [TransformSettings::Filter {
+            attribute: Some("env".to_string()),
+            field: Some("env".to_string()),
+            equals: None,
+            negate: false,
+        }];
+        assert!(TransformChain::<SourceRecord>::for_source(&filter).is_err());
+
+        // Field transforms reject payloads that are not JSON
+        let chain = chain(
+            r#"
+            [[transforms]]
+            type = "drop_fields"
+            fields = ["id"]
+            "#,
+        );
+        let err = chain
+            .apply(SourceRecord::from_string("/default/events", "plain text"))
+            .unwrap_err();
+        assert!(err.is_invalid_data());
+    }
+}
@@ -0,0 +1,394 @@
+//! Rhai script transforms
+//!
+//! A script file defines `fn transform(record)`, called for every record with a map of
+//! `payload` (the parsed JSON payload, or a string or blob for other payloads),
+//! `attributes`, `topic` and `key` (`()` without key and for sink records). It returns the
+//! record map, possibly changed, an array of record maps to split the record, or `()` to
+//! drop it. Fields missing from a returned map keep the values of the input record.
+//!
+//! Changes to `topic` and `key` only apply to source records: sink records keep the topic
+//! they were consumed from, as their acknowledgement follows it.
+//!
+//! Scripts run sandboxed: the engine has no file or network access, `eval` and `import` are
+//! disabled and a call is aborted after `max_operations` operations.
+
+use super::{Transform, TransformRecord};
+use crate::{ConnectorError, ConnectorResult, SinkRecord, SourceRecord};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+/// Name of the function a script must define
+const TRANSFORM_FN: &str = "transform";
+
+/// Runs the `transform(record)` function of a Rhai script
+pub(crate) struct ScriptTransform {
+    path: String,
+    engine: Engine,
+    ast: AST,
+}
+
+/// A record returned by a script
+struct ScriptRecord {
+    payload: Vec<u8>,
+    attributes: HashMap<String, String>,
+    topic: String,
+    key: Option<String>,
+}
+
+impl ScriptTransform {
+    /// Compile the script at `path`, limiting every call to `max_operations` operations
+    pub(crate) fn new(path: &str, max_operations: u64) -> ConnectorResult<Self> {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(max_operations)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(1 << 20)
+            .set_max_array_size(10_000)
+            .set_max_map_size(10_000)
+            .disable_symbol("eval")
+            // The default module resolver loads scripts from any path
+            .disable_symbol("import")
+            .set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+
+        let script = path.to_string();
+        engine.on_print(move |text| info!("Script {}: {}", script, text));
+        let script = path.to_string();
+        engine.on_debug(move |text, _, position| {
+            debug!("Script {} ({}): {}", script, position, text)
+        });
+
+        let ast = engine.compile_file(path.into()).map_err(|e| {
+            ConnectorError::config(format!("Failed to compile script {}: {}", path, e))
+        })?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == TRANSFORM_FN && f.params.len() == 1)
+        {
+            return Err(ConnectorError::config(format!(
+                "Script {} does not define fn {}(record)",
+                path, TRANSFORM_FN
+            )));
+        }
+
+        info!("Loaded script transform {}", path);
+        Ok(Self {
+            path: path.to_string(),
+            engine,
+            ast,
+        })
+    }
+
+    /// Call the script on a record and collect the records it returns
+    fn run<R: TransformRecord>(
+        &self,
+        record: &R,
+        topic: &str,
+        key: Option<&str>,
+    ) -> ConnectorResult<Vec<ScriptRecord>> {
+        let (payload, json) = payload_to_dynamic(record.payload());
+        let attributes: Map = record
+            .attributes()
+            .iter()
+            .map(|(name, value)| (name.into(), value.clone().into()))
+            .collect();
+
+        let mut input = Map::new();
+        input.insert("payload".into(), payload);
+        input.insert("attributes".into(), attributes.into());
+        input.insert("topic".into(), topic.into());
+        input.insert("key".into(), key.map_or(Dynamic::UNIT, Into::into));
+
+        // Only the function is called, top-level statements of the script are not run
+        let options = CallFnOptions::new().eval_ast(false).rewind_scope(true);
+        let output: Dynamic = self
+            .engine
+            .call_fn_with_options(
+                options,
+                &mut Scope::new(),
+                &self.ast,
+                TRANSFORM_FN,
+                (Dynamic::from_map(input.clone()),),
+            )
+            .map_err(|e| self.invalid(record, e.to_string()))?;
+
+        let outputs: Array = if output.is_unit() {
+            Vec::new()
+        } else if output.is_array() {
+            output.cast::<Array>()
+        } else {
+            vec![output]
+        };
+
+        outputs
+            .into_iter()
+            .map(|output| {
+                let map = output.try_cast::<Map>().ok_or_else(|| {
+                    self.invalid(
+                        record,
+                        "transform() must return a record map, an array of record maps or ()",
+                    )
+                })?;
+                self.script_record(record, &input, map, json)
+            })
+            .collect()
+    }
+
+    /// Read a record map returned by the script, taking missing fields from `input`
+    fn script_record<R: TransformRecord>(
+        &self,
+        record: &R,
+        input: &Map,
+        mut output: Map,
+        json: bool,
+    ) -> ConnectorResult<ScriptRecord> {
+        let mut field = |name: &str| {
+            output
+                .remove(name)
+                .or_else(|| input.get(name).cloned())
+                .unwrap_or(Dynamic::UNIT)
+        };
+
+        let payload = payload_from_dynamic(field("payload"), json)
+            .map_err(|e| self.invalid(record, format!("invalid payload: {}", e)))?;
+        let attributes = match field("attributes").try_cast::<Map>() {
+            Some(attributes) => attributes
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            None => return Err(self.invalid(record, "attributes must be a map")),
+        };
+        let topic = field("topic").to_string();
+        let key = field("key");
+        let key = (!key.is_unit()).then(|| key.to_string());
+
+        Ok(ScriptRecord {
+            payload,
+            attributes,
+            topic,
+            key,
+        })
+    }
+
+    fn invalid<R: TransformRecord>(
+        &self,
+        record: &R,
+        message: impl Into<String>,
+    ) -> ConnectorError {
+        ConnectorError::invalid_data(
+            format!("Script {} failed: {}", self.path, message.into()),
+            record.payload().to_vec(),
+        )
+    }
+}
+
+impl Transform<SinkRecord> for ScriptTransform {
+    fn apply(&self, record: SinkRecord) -> ConnectorResult<Vec<SinkRecord>> {
+        let outputs = self.run(&record, record.topic(), None)?;
+        Ok(outputs
+            .into_iter()
+            .map(|output| SinkRecord {
+                payload: output.payload,
+                attributes: output.attributes,
+                ..record.clone()
+            })
+            .collect())
+    }
+}
+
+impl Transform<SourceRecord> for ScriptTransform {
+    fn apply(&self, record: SourceRecord) -> ConnectorResult<Vec<SourceRecord>> {
+        let outputs = self.run(&record, &record.topic, record.key.as_deref())?;
+        Ok(outputs
+            .into_iter()
+            .map(|output| SourceRecord {
+                topic: output.topic,
+                payload: output.payload,
+                attributes: output.attributes,
+                key: output.key,
+                ..record.clone()
+            })
+            .collect())
+    }
+}
+
+/// The payload as script value: parsed JSON, a string for other UTF-8 payloads or a blob
+///
+/// Also returns whether the payload is JSON.
+fn payload_to_dynamic(payload: &[u8]) -> (Dynamic, bool) {
+    if let Ok(value) = serde_json::from_slice::<Value>(payload) {
+        if let Ok(value) = rhai::serde::to_dynamic(value) {
+            return (value, true);
+        }
+    }
+    match std::str::from_utf8(payload) {
+        Ok(text) => (text.into(), false),
+        Err(_) => (Dynamic::from_blob(payload.to_vec()), false),
+    }
+}
+
+/// The payload of a script value: blobs as they are, strings as they are unless the input
+/// payload was JSON, anything else serialized as JSON
+fn payload_from_dynamic(value: Dynamic, json: bool) -> Result<Vec<u8>, String> {
+    if value.is_blob() {
+        return value.into_blob().map_err(str::to_string);
+    }
+    if value.is_string() && !json {
+        return value
+            .into_string()
+            .map(String::into_bytes)
+            .map_err(str::to_string);
+    }
+
+    let value: Value = rhai::serde::from_dynamic(&value).map_err(|e| e.to_string())?;
+    serde_json::to_vec(&value).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::TransformChain;
+    use crate::{DanubeMetadata, TransformSettings};
+    use serde_json::json;
+
+    fn script(source: &str) -> ScriptTransform {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transform.rhai");
+        std::fs::write(&path, source).unwrap();
+        ScriptTransform::new(path.to_str().unwrap(), 10_000).unwrap()
+    }
+
+    fn record(payload: Value) -> SourceRecord {
+        SourceRecord::from_json("/iot/sensors", &payload)
+            .unwrap()
+            .with_attribute("device", "sensor-1")
+    }
+
+    #[test]
+    fn test_script_modifies_and_reroutes() {
+        let transform = script(
+            r#"
+            fn transform(record) {
+                record.payload.celsius = (record.payload.fahrenheit - 32.0) * 5.0 / 9.0;
+                record.payload.remove("fahrenheit");
+                record.attributes.unit = "celsius";
+                record.topic = "/iot/" + record.attributes.device;
+                record.key = record.attributes.device;
+                record
+            }
+            "#,
+        );
+
+        let records = transform
+            .apply(record(json!({"fahrenheit": 212.0})))
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        let payload: Value = serde_json::from_slice(&record.payload).unwrap();
+        assert_eq!(payload, json!({"celsius": 100.0}));
+        assert_eq!(record.attributes["unit"], "celsius");
+        assert_eq!(record.topic, "/iot/sensor-1");
+        assert_eq!(record.key.as_deref(), Some("sensor-1"));
+    }
+
+    #[test]
+    fn test_script_splits_and_drops() {
+        let transform = script(
+            r#"
+            fn transform(record) {
+                if record.payload.readings.is_empty() {
+                    return ();
+                }
+                record.payload.readings.map(|reading| #{ payload: reading })
+            }
+            "#,
+        );
+
+        let records = transform
+            .apply(record(json!({"readings": [{"t": 1}, {"t": 2}]})))
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].payload, br#"{"t":2}"#);
+        // Fields not returned keep the values of the input record
+        assert_eq!(records[1].attributes["device"], "sensor-1");
+        assert_eq!(records[1].topic, "/iot/sensors");
+
+        let records = transform.apply(record(json!({"readings": []}))).unwrap();
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn test_split_sink_records_have_positions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("split.rhai");
+        std::fs::write(
+            &path,
+            "fn transform(record) { record.payload.readings.map(|reading| #{ payload: reading }) }",
+        )
+        .unwrap();
+        let chain = TransformChain::for_sink(&[TransformSettings::Script {
+            path: path.to_str().unwrap().to_string(),
+            max_operations: 10_000,
+        }])
+        .unwrap();
+
+        let record = SinkRecord {
+            payload: br#"{"readings": [{"t": 1}, {"t": 2}]}"#.to_vec(),
+            attributes: HashMap::new(),
+            danube_metadata: DanubeMetadata {
+                topic: "/iot/sensors".to_string(),
+                offset: 7,
+                publish_time: 0,
+                message_id: "topic:/iot/sensors/producer:1/offset:7".to_string(),
+                producer_name: "producer".to_string(),
+                schema_type: None,
+                split_index: 0,
+                split_count: 1,
+            },
+            partition: None,
+        };
+        let records = chain.apply_to_message(record).unwrap();
+
+        // Both records keep the message offset but have their own position
+        let positions: Vec<_> = records.iter().map(SinkRecord::position).collect();
+        assert_eq!(positions, vec![(7, 0), (7, 1)]);
+        assert!(!records[0].is_last_split());
+        assert!(records[1].is_last_split());
+        assert_eq!(records[1].payload, br#"{"t":2}"#);
+    }
+
+    #[test]
+    fn test_script_sandbox() {
+        let transform = script(
+            r#"
+            fn transform(record) {
+                loop {}
+            }
+            "#,
+        );
+        let err = transform.apply(record(json!({}))).unwrap_err();
+        assert!(err.is_invalid_data());
+
+        let dir = tempfile::tempdir().unwrap();
+        let compiles = |source: &str| {
+            let path = dir.path().join("sandboxed.rhai");
+            std::fs::write(&path, source).unwrap();
+            ScriptTransform::new(path.to_str().unwrap(), 10_000).is_ok()
+        };
+        assert!(!compiles("fn reshape(record) { record }"));
+
+        // Scripts cannot read files through modules
+        let module = dir.path().join("module.rhai");
+        std::fs::write(&module, "export const SECRET = 42;").unwrap();
+        assert!(!compiles(&format!(
+            "import {:?} as m;\nfn transform(record) {{ record }}",
+            module.display().to_string()
+        )));
+        assert!(!compiles(&format!(
+            "fn transform(record) {{ import {:?} as m; record }}",
+            module.display().to_string()
+        )));
+    }
+}
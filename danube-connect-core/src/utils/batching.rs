@@ -3,6 +3,8 @@
 use std::time::{Duration, Instant};
 
 /// A generic batcher that collects items until reaching a size or timeout threshold
+///
+/// The timeout counts from the first item added to an empty batch.
 #[derive(Debug)]
 pub struct Batcher<T> {
     items: Vec<T>,
@@ -24,12 +26,21 @@ impl<T> Batcher<T> {
 
     /// Add an item to the batch
     pub fn add(&mut self, item: T) {
+        if self.items.is_empty() {
+            self.last_flush = Instant::now();
+        }
         self.items.push(item);
     }
 
-    /// Check if the batch should be flushed
+    /// Check if the batch should be flushed (never true for an empty batch)
     pub fn should_flush(&self) -> bool {
-        self.items.len() >= self.max_size || self.last_flush.elapsed() >= self.timeout
+        !self.items.is_empty()
+            && (self.items.len() >= self.max_size || self.last_flush.elapsed() >= self.timeout)
+    }
+
+    /// When the current batch times out (`None` for an empty batch)
+    pub fn deadline(&self) -> Option<Instant> {
+        (!self.items.is_empty()).then(|| self.last_flush + self.timeout)
     }
 
     /// Check if the batch is empty
@@ -97,4 +108,34 @@ mod tests {
         assert!(batcher.is_empty());
         assert_eq!(batcher.len(), 0);
     }
+
+    #[test]
+    fn test_batcher_timeout_starts_with_first_item() {
+        let mut batcher = Batcher::new(10, Duration::from_millis(50));
+
+        // An idle, empty batcher never asks to be flushed
+        thread::sleep(Duration::from_millis(60));
+        assert!(!batcher.should_flush());
+
+        batcher.add(1);
+        assert!(!batcher.should_flush());
+    }
+
+    #[test]
+    fn test_batcher_deadline() {
+        let mut batcher = Batcher::new(10, Duration::from_millis(50));
+        assert!(batcher.deadline().is_none());
+
+        let before = Instant::now();
+        batcher.add(1);
+        let deadline = batcher.deadline().unwrap();
+        assert!(deadline >= before + Duration::from_millis(50));
+
+        // Adding to a non-empty batch keeps the deadline
+        batcher.add(2);
+        assert_eq!(batcher.deadline(), Some(deadline));
+
+        batcher.flush();
+        assert!(batcher.deadline().is_none());
+    }
 }
@@ -3,8 +3,10 @@
 pub mod batching;
 pub mod health;
 pub mod serialization;
+pub mod topic_pattern;
 
 // Re-export commonly used types
 pub use batching::Batcher;
 pub use health::{HealthChecker, HealthStatus};
 pub use serialization::{json, string, SerializationError};
+pub use topic_pattern::{find_topic_match, is_topic_pattern, topic_matches};
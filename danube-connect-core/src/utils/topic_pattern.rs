@@ -0,0 +1,107 @@
+//! Topic pattern matching for Danube topic names.
+//!
+//! Patterns are matched segment by segment (segments are separated by `/`):
+//! - `*` inside a segment matches any characters of that segment (`sensors-*`)
+//! - a `**` segment matches any number of segments (`/iot/**`)
+
+/// Check if a topic is a topic pattern rather than a topic name
+pub fn is_topic_pattern(topic: &str) -> bool {
+    topic.contains('*')
+}
+
+/// Find the entry of `entries` whose topic or topic pattern matches the topic name `topic`
+///
+/// Sink records carry the concrete topic they were consumed from, also when their consumer
+/// was subscribed through a pattern. An entry for exactly this topic wins over patterns,
+/// which are tried in order.
+pub fn find_topic_match<'a, T>(
+    entries: &'a [T],
+    topic: &str,
+    entry_topic: impl Fn(&T) -> &str,
+) -> Option<&'a T> {
+    entries
+        .iter()
+        .find(|entry| entry_topic(entry) == topic)
+        .or_else(|| {
+            entries.iter().find(|entry| {
+                let pattern = entry_topic(entry);
+                is_topic_pattern(pattern) && topic_matches(pattern, topic)
+            })
+        })
+}
+
+/// Check if a Danube topic name matches a topic pattern
+pub fn topic_matches(pattern: &str, topic: &str) -> bool {
+    let pattern_parts: Vec<&str> = pattern.split('/').collect();
+    let topic_parts: Vec<&str> = topic.split('/').collect();
+
+    match_parts(&pattern_parts, &topic_parts)
+}
+
+fn match_parts(pattern_parts: &[&str], topic_parts: &[&str]) -> bool {
+    match (pattern_parts.split_first(), topic_parts.split_first()) {
+        (None, None) => true,
+        (Some((&"**", rest)), _) => {
+            // Multi-segment wildcard - try every number of skipped segments
+            (0..=topic_parts.len()).any(|skip| match_parts(rest, &topic_parts[skip..]))
+        }
+        (Some((pattern_head, pattern_rest)), Some((topic_head, topic_rest))) => {
+            match_segment(pattern_head.as_bytes(), topic_head.as_bytes())
+                && match_parts(pattern_rest, topic_rest)
+        }
+        _ => false,
+    }
+}
+
+fn match_segment(pattern: &[u8], segment: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => segment.is_empty(),
+        Some((b'*', rest)) => (0..=segment.len()).any(|skip| match_segment(rest, &segment[skip..])),
+        Some((head, rest)) => segment
+            .split_first()
+            .is_some_and(|(c, segment_rest)| c == head && match_segment(rest, segment_rest)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_match() {
+        assert!(topic_matches("/default/events", "/default/events"));
+        assert!(!topic_matches("/default/events", "/default/events-2"));
+        assert!(!topic_matches("/default/events", "/default"));
+    }
+
+    #[test]
+    fn test_segment_wildcard() {
+        assert!(topic_matches("/iot/*", "/iot/sensors"));
+        assert!(topic_matches("/iot/sensors-*", "/iot/sensors-eu"));
+        assert!(topic_matches("/iot/*-eu", "/iot/sensors-eu"));
+        assert!(!topic_matches("/iot/*", "/iot/sensors/temperature"));
+        assert!(!topic_matches("/iot/sensors-*", "/default/sensors-eu"));
+    }
+
+    #[test]
+    fn test_find_topic_match() {
+        let entries = ["/iot/**", "/events/*", "/events/orders"];
+        let find = |topic| find_topic_match(&entries, topic, |entry| entry);
+
+        assert_eq!(find("/events/orders"), Some(&"/events/orders"));
+        assert_eq!(find("/events/payments"), Some(&"/events/*"));
+        assert_eq!(find("/iot/sensors/temperature"), Some(&"/iot/**"));
+        assert_eq!(find("/default/events"), None);
+        assert!(is_topic_pattern("/events/*"));
+        assert!(!is_topic_pattern("/events/orders"));
+    }
+
+    #[test]
+    fn test_multi_segment_wildcard() {
+        assert!(topic_matches("/iot/**", "/iot/sensors"));
+        assert!(topic_matches("/iot/**", "/iot/sensors/temperature"));
+        assert!(topic_matches("**", "/default/events"));
+        assert!(topic_matches("/**/events", "/default/events"));
+        assert!(!topic_matches("/iot/**", "/default/events"));
+    }
+}